@@ -1,8 +1,10 @@
+use parsedns::utils::Serial;
+
 pub struct Zone {
     pub domain: String,
     pub m_name: String,
     pub r_name: String,
-    pub serial: u32,
+    pub serial: Serial,
     pub refresh: u32,
     pub retry: u32,
     pub expire: u32,
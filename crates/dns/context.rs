@@ -1,5 +1,1292 @@
-use moka::future::Cache;
+use std::{
+    fmt, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+use moka::{future::Cache, notification::RemovalCause, Expiry};
+use parsedns::{
+    dnssec::canonical_record,
+    header::RCode,
+    message::{Message, Section},
+    question::Question,
+    rr::{
+        class::{RecordClass, RecordQClass},
+        data::RecordData,
+        name::Name,
+        rrset::{RRset, RRsetKey},
+        types::{RecordQType, RecordType},
+    },
+    utils::TTL,
+};
+use tokio::fs;
+
+use super::packet::unanswered_cname;
+
+/// How far data can be trusted, from least to most. Cached data is only replaced by data that
+/// is at least as credible.
+///
+/// ```text
+/// Data should be ranked according to its source, as follows:
+///
+///   + Data from a primary zone file, other than glue data,
+///   + Data from a zone transfer, other than glue,
+///   + The authoritative data included in the answer section of an
+///     authoritative reply.
+///   + Data from the authority section of an authoritative answer,
+///   + Glue from a primary zone, or glue from a zone transfer,
+///   + Data from the answer section of a non-authoritative answer, and
+///     non-authoritative data from the answer section of authoritative
+///     answers,
+///   + Additional information from an authoritative answer,
+///     Data from the authority section of a non-authoritative answer,
+///     Additional information from non-authoritative answers.
+/// ```
+/// [RFC2181 5.4.1: Ranking data](https://datatracker.ietf.org/doc/html/rfc2181#section-5.4.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Credibility {
+    /// The additional section, or the authority section of a non-authoritative answer, which
+    /// includes the NS records and glue of referrals
+    Additional,
+    /// The answer section of a non-authoritative answer
+    NonAuthoritativeAnswer,
+    /// Glue from a zone
+    ZoneGlue,
+    /// The authority section of an authoritative answer
+    AuthoritativeAuthority,
+    /// The answer section of an authoritative answer
+    AuthoritativeAnswer,
+    /// A zone transfer, other than glue
+    ZoneTransfer,
+    /// A zone file, other than glue
+    Zone,
+}
+
+impl Credibility {
+    const ALL: [Self; 7] = [
+        Self::Additional,
+        Self::NonAuthoritativeAnswer,
+        Self::ZoneGlue,
+        Self::AuthoritativeAuthority,
+        Self::AuthoritativeAnswer,
+        Self::ZoneTransfer,
+        Self::Zone,
+    ];
+
+    /// The credibility of a section of a response, depending on its AA flag
+    pub fn of(section: Section, authoritative: bool) -> Self {
+        match (section, authoritative) {
+            (Section::Answer, true) => Self::AuthoritativeAnswer,
+            (Section::Answer, false) => Self::NonAuthoritativeAnswer,
+            (Section::Authority, true) => Self::AuthoritativeAuthority,
+            _ => Self::Additional,
+        }
+    }
+}
+
+/// Budgets are in bytes of encoded records, so a large TXT or DNSKEY set takes the room of many
+/// A records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Answers
+    pub positive_bytes: u64,
+    /// Denials, weighed by their SOA
+    pub negative_bytes: u64,
+    /// NS records and glue, which resolution needs to get anywhere, kept apart so floods of
+    /// answers don't push them out
+    pub infrastructure_bytes: u64,
+    /// TTLs below this are raised to it
+    pub min_ttl: u32,
+    /// TTLs above this are lowered to it
+    pub max_ttl: u32,
+    /// Negative answers are cached for the SOA minimum, but no longer than this
+    pub max_negative_ttl: u32,
+    /// How long after they expire answers are kept to serve when they can't be refreshed, none
+    /// at all when zero
+    ///
+    /// [RFC8767 5: Example Method](https://datatracker.ietf.org/doc/html/rfc8767#section-5)
+    pub stale_window: u32,
+    /// Entries hit this often are refreshed when a hit finds them in the last part of their TTL
+    pub prefetch_hits: u64,
+    /// How much of the TTL, in percent, is the last part where entries are prefetched, none at
+    /// all when zero
+    pub prefetch_percent: u8,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            positive_bytes: 32 << 20,
+            negative_bytes: 8 << 20,
+            infrastructure_bytes: 8 << 20,
+            min_ttl: 0,
+            max_ttl: 86400,
+            max_negative_ttl: 10800,
+            stale_window: 86400,
+            prefetch_hits: 2,
+            prefetch_percent: 10,
+        }
+    }
+}
+
+/// The TTL of stale answers, so clients come back soon for fresh ones
+///
+/// [RFC8767 4: Standards Action](https://datatracker.ietf.org/doc/html/rfc8767#section-4)
+pub const STALE_TTL: u32 = 30;
+
+struct CachedRRset {
+    rrset: RRset,
+    credibility: Credibility,
+    expires: Instant,
+    /// The TTL it was stored with
+    ttl: u32,
+    /// The size of the encoded records
+    weight: u32,
+    hits: AtomicU64,
+    prefetched: AtomicBool,
+}
+
+impl CachedRRset {
+    fn new(rrset: RRset, credibility: Credibility, ttl: u32, now: Instant) -> Self {
+        let weight = rrset
+            .records()
+            .map(|record| canonical_record(&record).map_or(0, |encoded| encoded.len()))
+            .sum::<usize>();
+        Self {
+            weight: u32::try_from(weight).unwrap_or(u32::MAX).max(1),
+            rrset,
+            credibility,
+            expires: now + Duration::from_secs(ttl.into()),
+            ttl,
+            hits: AtomicU64::new(0),
+            prefetched: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Counters of how the cache is used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Client queries answered from the cache, and those that weren't
+    pub hits: u64,
+    pub misses: u64,
+    /// Popular entries refreshed before they expired
+    pub prefetches: u64,
+    /// Entries evicted to stay within each budget
+    pub positive_evictions: u64,
+    pub negative_evictions: u64,
+    pub infrastructure_evictions: u64,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} prefetches, evictions: {} positive, {} negative, {} infrastructure",
+            self.hits,
+            self.misses,
+            self.prefetches,
+            self.positive_evictions,
+            self.negative_evictions,
+            self.infrastructure_evictions
+        )
+    }
+}
+
+/// Entries are kept for the stale window after they expire
+struct RRsetExpiry {
+    stale_window: Duration,
+}
+
+impl<K> Expiry<K, Arc<CachedRRset>> for RRsetExpiry {
+    fn expire_after_create(
+        &self,
+        _: &K,
+        value: &Arc<CachedRRset>,
+        created_at: Instant,
+    ) -> Option<Duration> {
+        Some((value.expires + self.stale_window).saturating_duration_since(created_at))
+    }
+
+    fn expire_after_update(
+        &self,
+        _: &K,
+        value: &Arc<CachedRRset>,
+        updated_at: Instant,
+        _: Option<Duration>,
+    ) -> Option<Duration> {
+        Some((value.expires + self.stale_window).saturating_duration_since(updated_at))
+    }
+}
+
+/// What a negative answer denies
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum NegativeKey {
+    /// The name and everything below it
+    NxDomain(Name, RecordClass),
+    /// The type at the name
+    NoData(RRsetKey),
+}
+
+/// A cached negative answer, with the SOA of the zone that gave it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Negative {
+    NxDomain(RRset),
+    NoData(RRset),
+}
+
+impl Negative {
+    pub fn rcode(&self) -> RCode {
+        match self {
+            Self::NxDomain(_) => RCode::NXDomain,
+            Self::NoData(_) => RCode::NoError,
+        }
+    }
+
+    /// The SOA to put in the authority section, with its TTL counted down
+    pub fn soa(&self) -> &RRset {
+        match self {
+            Self::NxDomain(soa) | Self::NoData(soa) => soa,
+        }
+    }
+}
+
+/// A cache holding up to `budget` bytes of records, counting the entries it evicts to stay
+/// within it
+fn partition<K>(
+    budget: u64,
+    stale_window: u32,
+    evictions: Arc<AtomicU64>,
+) -> Cache<K, Arc<CachedRRset>>
+where
+    K: std::hash::Hash + Eq + Send + Sync + 'static,
+{
+    Cache::builder()
+        .max_capacity(budget)
+        .weigher(|_, cached: &Arc<CachedRRset>| cached.weight)
+        .expire_after(RRsetExpiry {
+            stale_window: Duration::from_secs(stale_window.into()),
+        })
+        .eviction_listener(move |_, _, cause| {
+            if cause == RemovalCause::Size {
+                evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+        .build()
+}
+
+/// Whether an RRset is kept in the infrastructure budget: the NS records of zones, and glue
+fn is_infrastructure(rtype: RecordType, credibility: Credibility) -> bool {
+    match rtype {
+        RecordType::NS => true,
+        RecordType::A | RecordType::AAAA => credibility == Credibility::Additional,
+        _ => false,
+    }
+}
+
+/// RRsets by owner name, type and class, kept until their TTL runs out. The TTLs handed out
+/// count down with the time spent in the cache. Negative answers are kept alongside, with the
+/// SOA they came with. Answers, denials and infrastructure each have their own budget in bytes,
+/// and the entries least likely to be used again are evicted to stay within it.
+pub struct RecordCache {
+    cache: Cache<RRsetKey, Arc<CachedRRset>>,
+    negative: Cache<NegativeKey, Arc<CachedRRset>>,
+    infrastructure: Cache<RRsetKey, Arc<CachedRRset>>,
+    config: CacheConfig,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    prefetches: AtomicU64,
+    positive_evictions: Arc<AtomicU64>,
+    negative_evictions: Arc<AtomicU64>,
+    infrastructure_evictions: Arc<AtomicU64>,
+}
+
+impl RecordCache {
+    pub fn new(config: CacheConfig) -> Self {
+        let positive_evictions = Arc::new(AtomicU64::new(0));
+        let negative_evictions = Arc::new(AtomicU64::new(0));
+        let infrastructure_evictions = Arc::new(AtomicU64::new(0));
+        Self {
+            cache: partition(
+                config.positive_bytes,
+                config.stale_window,
+                positive_evictions.clone(),
+            ),
+            negative: partition(
+                config.negative_bytes,
+                config.stale_window,
+                negative_evictions.clone(),
+            ),
+            infrastructure: partition(
+                config.infrastructure_bytes,
+                config.stale_window,
+                infrastructure_evictions.clone(),
+            ),
+            config,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            prefetches: AtomicU64::new(0),
+            positive_evictions,
+            negative_evictions,
+            infrastructure_evictions,
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            prefetches: self.prefetches.load(Ordering::Relaxed),
+            positive_evictions: self.positive_evictions.load(Ordering::Relaxed),
+            negative_evictions: self.negative_evictions.load(Ordering::Relaxed),
+            infrastructure_evictions: self.infrastructure_evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn config(&self) -> CacheConfig {
+        self.config
+    }
+
+    pub fn entry_count(&self) -> u64 {
+        self.cache.entry_count() + self.negative.entry_count() + self.infrastructure.entry_count()
+    }
+
+    /// The bytes of records cached, which eviction keeps within the budgets
+    pub fn weighted_size(&self) -> u64 {
+        self.cache.weighted_size()
+            + self.negative.weighted_size()
+            + self.infrastructure.weighted_size()
+    }
+
+    /// The cached copy of an RRset, from whichever budget holds it
+    async fn lookup(&self, key: &RRsetKey) -> Option<Arc<CachedRRset>> {
+        match self.cache.get(key).await {
+            Some(cached) => Some(cached),
+            None => self.infrastructure.get(key).await,
+        }
+    }
+
+    /// Store an entry in the budget for it, dropping a copy from the other one
+    async fn store(&self, cached: CachedRRset) {
+        let key = cached.rrset.key().clone();
+        let (to, other) = match is_infrastructure(key.rtype, cached.credibility) {
+            true => (&self.infrastructure, &self.cache),
+            false => (&self.cache, &self.infrastructure),
+        };
+        other.invalidate(&key).await;
+        to.insert(key, Arc::new(cached)).await;
+    }
+
+    /// Store an RRset unless a more credible copy of it is cached, returning whether it was
+    /// stored
+    pub async fn insert(&self, rrset: RRset, credibility: Credibility) -> bool {
+        self.insert_at(rrset, credibility, Instant::now()).await
+    }
+
+    async fn insert_at(&self, mut rrset: RRset, credibility: Credibility, now: Instant) -> bool {
+        let ttl = u32::from(rrset.ttl()).clamp(self.config.min_ttl, self.config.max_ttl);
+        if ttl == 0 {
+            return false;
+        }
+        if let Some(cached) = self.lookup(rrset.key()).await {
+            if cached.credibility > credibility && cached.expires > now {
+                return false;
+            }
+        }
+
+        rrset.set_ttl(TTL::from(ttl));
+        self.store(CachedRRset::new(rrset, credibility, ttl, now))
+            .await;
+        true
+    }
+
+    /// Store the RRsets of a response that are at or below `bailiwick`, the zone of the server
+    /// that sent it. Anything else the server has no say over.
+    pub async fn insert_response(&self, response: &Message, bailiwick: &Name) {
+        for section in [Section::Answer, Section::Authority, Section::Additional] {
+            let credibility = Credibility::of(section, response.header.aa);
+            for rrset in response.rrsets(section) {
+                if rrset.name().is_subdomain_of(bailiwick) {
+                    self.insert(rrset, credibility).await;
+                }
+            }
+        }
+    }
+
+    /// Store the NXDOMAIN or NODATA a response for its question amounts to, after following the
+    /// CNAMEs in its answers, returning whether it was stored. Only denials that come with the
+    /// SOA of a zone within `bailiwick` holding the denied name are cached.
+    ///
+    /// ```text
+    /// Like normal answers negative answers have a time to live (TTL).  As
+    /// there is no record in the answer section to which this TTL can be
+    /// applied, the TTL must be carried by another method.  This is done by
+    /// including the SOA record from the zone in the authority section of
+    /// the reply.  When the authoritative server creates this record its TTL
+    /// is taken from the minimum of the SOA.MINIMUM field and SOA's TTL.
+    /// This TTL decrements in a similar manner to a normal cached answer and
+    /// upon reaching zero (0) signals the cached negative answer MUST NOT be
+    /// used.
+    /// ```
+    /// [RFC2308 5: Caching Negative Answers](https://datatracker.ietf.org/doc/html/rfc2308#section-5)
+    pub async fn insert_negative(&self, response: &Message, bailiwick: &Name) -> bool {
+        self.insert_negative_at(response, bailiwick, Instant::now())
+            .await
+    }
+
+    async fn insert_negative_at(&self, response: &Message, bailiwick: &Name, now: Instant) -> bool {
+        let Some(Question {
+            qname,
+            qtype: RecordQType::RecordType(qtype),
+            qclass: RecordQClass::RecordClass(class),
+        }) = response.questions.first()
+        else {
+            return false;
+        };
+
+        // The end of the CNAME chain is what is denied
+        let mut name = qname.clone();
+        for _ in 0..response.answers.len() {
+            let mut at_name = response.answers.iter().filter(|r| *r.name() == name);
+            if at_name.clone().any(|r| r.rtype() == *qtype) {
+                return false;
+            }
+            match at_name.find_map(|r| match r.rdata() {
+                RecordData::CNAME(cname) => Some(cname.cname().clone()),
+                _ => None,
+            }) {
+                Some(target) => name = target,
+                None => break,
+            }
+        }
+
+        let Some(mut soa) = response
+            .rrsets(Section::Authority)
+            .into_iter()
+            .find(|rrset| {
+                rrset.rtype() == RecordType::SOA
+                    && rrset.name().is_subdomain_of(bailiwick)
+                    && name.is_subdomain_of(rrset.name())
+            })
+        else {
+            return false;
+        };
+        let minimum = soa.rdatas().iter().find_map(|rdata| match rdata {
+            RecordData::SOA(soa) => Some(u32::from(soa.minimum())),
+            _ => None,
+        });
+        let ttl = u32::from(soa.ttl())
+            .min(minimum.unwrap_or_default())
+            .min(self.config.max_negative_ttl);
+        if ttl == 0 {
+            return false;
+        }
+
+        let key = match response.header.rcode {
+            RCode::NXDomain => NegativeKey::NxDomain(name, *class),
+            RCode::NoError => NegativeKey::NoData(RRsetKey::new(name, *qtype, *class)),
+            _ => return false,
+        };
+        soa.set_ttl(TTL::from(ttl));
+        let credibility = Credibility::of(Section::Authority, response.header.aa);
+        let cached = CachedRRset::new(soa, credibility, ttl, now);
+        self.negative.insert(key, Arc::new(cached)).await;
+        true
+    }
+
+    fn stale_window(&self) -> Duration {
+        Duration::from_secs(self.config.stale_window.into())
+    }
+
+    /// The cached RRset with its TTL counted down
+    pub async fn get(&self, key: &RRsetKey) -> Option<RRset> {
+        self.get_at(key, Instant::now(), Duration::ZERO).await
+    }
+
+    /// The cached RRset, or if it has expired within the stale window, the stale copy with a
+    /// TTL of [STALE_TTL]
+    pub async fn get_stale(&self, key: &RRsetKey) -> Option<RRset> {
+        self.get_at(key, Instant::now(), self.stale_window()).await
+    }
+
+    async fn get_at(&self, key: &RRsetKey, now: Instant, window: Duration) -> Option<RRset> {
+        let cached = self.lookup(key).await?;
+        cached.hits.fetch_add(1, Ordering::Relaxed);
+        remaining(&cached, now, window)
+    }
+
+    /// Count a client query as answered from the cache or not. The lookups themselves aren't
+    /// counted, as answering one query takes several of them.
+    pub fn count_query(&self, hit: bool) {
+        let counter = match hit {
+            true => &self.hits,
+            false => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether an entry is popular and close enough to expiring to be refreshed now, while it
+    /// is still served. True once for each entry, counting a prefetch, so the caller is expected
+    /// to refresh it.
+    pub async fn take_prefetch(&self, key: &RRsetKey) -> bool {
+        self.take_prefetch_at(key, Instant::now()).await
+    }
+
+    async fn take_prefetch_at(&self, key: &RRsetKey, now: Instant) -> bool {
+        let Some(cached) = self.lookup(key).await else {
+            return false;
+        };
+        let Some(remaining) = cached.expires.checked_duration_since(now) else {
+            return false;
+        };
+        let window = cached.ttl as u64 * self.config.prefetch_percent as u64 / 100;
+        if cached.hits.load(Ordering::Relaxed) < self.config.prefetch_hits
+            || remaining.as_secs() >= window
+            || cached.prefetched.swap(true, Ordering::Relaxed)
+        {
+            return false;
+        }
+        self.prefetches.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// The cached denial of the RRset, either of the RRset itself or of its name or a name above
+    /// it
+    ///
+    /// ```text
+    /// When an iterative caching DNS resolver receives an NXDOMAIN response,
+    /// it SHOULD store it in its cache and then all names and RRsets at or
+    /// below that node SHOULD be considered unreachable.  Subsequent queries
+    /// for such names SHOULD elicit an NXDOMAIN response.
+    /// ```
+    /// [RFC8020 2: Rules](https://datatracker.ietf.org/doc/html/rfc8020#section-2)
+    pub async fn get_negative(&self, key: &RRsetKey) -> Option<Negative> {
+        self.get_negative_at(key, Instant::now(), Duration::ZERO)
+            .await
+    }
+
+    /// The cached denial of the RRset, stale ones included as for [RecordCache::get_stale]
+    pub async fn get_negative_stale(&self, key: &RRsetKey) -> Option<Negative> {
+        self.get_negative_at(key, Instant::now(), self.stale_window())
+            .await
+    }
+
+    async fn get_negative_at(
+        &self,
+        key: &RRsetKey,
+        now: Instant,
+        window: Duration,
+    ) -> Option<Negative> {
+        for labels in (1..=key.name.label_count()).rev() {
+            let denied = NegativeKey::NxDomain(key.name.suffix(labels), key.class);
+            if let Some(soa) = self.negative.get(&denied).await {
+                if let Some(soa) = remaining(&soa, now, window) {
+                    return Some(Negative::NxDomain(soa));
+                }
+            }
+        }
+        let cached = self.negative.get(&NegativeKey::NoData(key.clone())).await?;
+        remaining(&cached, now, window).map(Negative::NoData)
+    }
+
+    /// An answer from the cache, with the records of the question or a CNAME to follow, or a
+    /// denial with the SOA of the zone that gave it. Stale data is only used when asked for.
+    pub async fn answer(&self, question: &Question, stale: bool) -> Option<Message> {
+        let (RecordQType::RecordType(rtype), RecordQClass::RecordClass(class)) =
+            (question.qtype, question.qclass)
+        else {
+            return None;
+        };
+        let key = |rtype| RRsetKey::new(question.qname.clone(), rtype, class);
+        let get = |key| async move {
+            match stale {
+                true => self.get_stale(&key).await,
+                false => self.get(&key).await,
+            }
+        };
+        let mut rrset = get(key(rtype)).await;
+        if rrset.is_none() && rtype != RecordType::CNAME {
+            rrset = get(key(RecordType::CNAME)).await;
+        }
+
+        let mut response = Message::new();
+        response.header.qr = true;
+        response.questions.push(question.clone());
+        match rrset {
+            Some(rrset) => response.answers.extend(rrset.records()),
+            None => {
+                let negative = match stale {
+                    true => self.get_negative_stale(&key(rtype)).await?,
+                    false => self.get_negative(&key(rtype)).await?,
+                };
+                response.header.rcode = negative.rcode();
+                response.authorities.extend(negative.soa().records());
+            }
+        }
+        Some(response)
+    }
+
+    /// The answer from the cache, following at most `max_chain` CNAMEs, with stale data included
+    /// when asked for. None unless the whole chain is cached.
+    pub async fn answer_chain(
+        &self,
+        question: &Question,
+        stale: bool,
+        max_chain: u32,
+    ) -> Option<Message> {
+        let mut answers = Vec::new();
+        let mut qname = question.qname.clone();
+        for _ in 0..=max_chain {
+            let lookup = Question::new(qname.clone(), question.qtype, question.qclass);
+            let mut response = self.answer(&lookup, stale).await?;
+            let target = unanswered_cname(&response, &qname, question.qtype);
+            answers.append(&mut response.answers);
+            match target {
+                Some(target) if response.header.rcode == RCode::NoError => qname = target,
+                _ => {
+                    response.questions = vec![question.clone()];
+                    response.answers = answers;
+                    return Some(response);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The cached RRset with the TTL it has left, or [STALE_TTL] if it expired less than `window`
+/// ago
+fn remaining(cached: &CachedRRset, now: Instant, window: Duration) -> Option<RRset> {
+    let ttl = if cached.expires > now {
+        (cached.expires - now).as_secs() as u32
+    } else if cached.expires + window > now {
+        STALE_TTL
+    } else {
+        return None;
+    };
+    let mut rrset = cached.rrset.clone();
+    rrset.set_ttl(TTL::from(ttl));
+    Some(rrset)
+}
+
+/// Marks cache snapshot files, followed by the version of their format
+const SNAPSHOT_MAGIC: &[u8; 8] = b"JDNSCACH";
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// What an entry of a snapshot holds
+const SNAPSHOT_RRSET: u8 = 0;
+const SNAPSHOT_NXDOMAIN: u8 = 1;
+const SNAPSHOT_NODATA: u8 = 2;
+
+fn invalid_snapshot(error: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Snapshots keep the cache across restarts. After the magic and version, each entry is
+///
+/// ```text
+/// +------+-------------+------------------+--------+------------------+
+/// | kind | credibility | expires, seconds | length | message (length) |
+/// |  u8  |     u8      | since epoch, u64 |  u32   |                  |
+/// +------+-------------+------------------+--------+------------------+
+/// ```
+///
+/// with the integers big endian. The message holds an RRset in its answers, or for negative
+/// entries the denied name, type and class in its question and the SOA in its authorities.
+/// Expiries are absolute, so entries that expired while the server was down are dropped.
+impl RecordCache {
+    /// Write the cache to `path`, returning the entries written. The snapshot is written next
+    /// to it and renamed over it, so a failed dump leaves the last snapshot in place.
+    pub async fn dump(&self, path: impl AsRef<Path>) -> io::Result<usize> {
+        let path = path.as_ref();
+        let (snapshot, entries) = self.snapshot(SystemTime::now(), Instant::now());
+        let partial = path.with_extension("partial");
+        fs::write(&partial, snapshot).await?;
+        fs::rename(partial, path).await?;
+        Ok(entries)
+    }
+
+    /// Fill the cache from the snapshot at `path`, returning the entries that hadn't expired
+    pub async fn load(&self, path: impl AsRef<Path>) -> io::Result<usize> {
+        let snapshot = fs::read(path).await?;
+        self.restore(&snapshot, SystemTime::now(), Instant::now())
+            .await
+    }
+
+    fn snapshot(&self, system_now: SystemTime, now: Instant) -> (Vec<u8>, usize) {
+        let mut snapshot = SNAPSHOT_MAGIC.to_vec();
+        snapshot.push(SNAPSHOT_VERSION);
+        let mut entries = 0;
+
+        for (_, cached) in self.cache.iter().chain(self.infrastructure.iter()) {
+            let mut message = Message::new();
+            message.answers.extend(cached.rrset.records());
+            entries += push_entry(
+                &mut snapshot,
+                SNAPSHOT_RRSET,
+                &cached,
+                &message,
+                system_now,
+                now,
+            ) as usize;
+        }
+        for (key, cached) in self.negative.iter() {
+            let (kind, name, rtype, class) = match &*key {
+                NegativeKey::NxDomain(name, class) => {
+                    (SNAPSHOT_NXDOMAIN, name, RecordType::SOA, *class)
+                }
+                NegativeKey::NoData(key) => (SNAPSHOT_NODATA, &key.name, key.rtype, key.class),
+            };
+            let mut message = Message::new();
+            message.questions.push(Question::new(
+                name.clone(),
+                RecordQType::RecordType(rtype),
+                RecordQClass::RecordClass(class),
+            ));
+            message.authorities.extend(cached.rrset.records());
+            entries += push_entry(&mut snapshot, kind, &cached, &message, system_now, now) as usize;
+        }
+        (snapshot, entries)
+    }
+
+    async fn restore(
+        &self,
+        snapshot: &[u8],
+        system_now: SystemTime,
+        now: Instant,
+    ) -> io::Result<usize> {
+        let rest = snapshot
+            .strip_prefix(SNAPSHOT_MAGIC)
+            .ok_or_else(|| invalid_snapshot("not a cache snapshot"))?;
+        let (&version, mut rest) = rest
+            .split_first()
+            .ok_or_else(|| invalid_snapshot("truncated snapshot"))?;
+        if version != SNAPSHOT_VERSION {
+            return Err(invalid_snapshot("unsupported snapshot version"));
+        }
+
+        let mut restored = 0;
+        while !rest.is_empty() {
+            if rest.len() < 14 {
+                return Err(invalid_snapshot("truncated snapshot"));
+            }
+            let (head, tail) = rest.split_at(14);
+            let length = u32::from_be_bytes(head[10..14].try_into().unwrap()) as usize;
+            if tail.len() < length {
+                return Err(invalid_snapshot("truncated snapshot"));
+            }
+            let (message, tail) = tail.split_at(length);
+            rest = tail;
+
+            let credibility = *Credibility::ALL
+                .get(head[1] as usize)
+                .ok_or_else(|| invalid_snapshot("unknown credibility"))?;
+            let seconds = u64::from_be_bytes(head[2..10].try_into().unwrap());
+            let expires = UNIX_EPOCH + Duration::from_secs(seconds);
+            let Some(left) = expires
+                .duration_since(system_now)
+                .ok()
+                .filter(|left| !left.is_zero())
+            else {
+                continue;
+            };
+            let message =
+                Message::from_bytes(message).map_err(|_| invalid_snapshot("invalid entry"))?;
+            let section = match head[0] {
+                SNAPSHOT_RRSET => Section::Answer,
+                _ => Section::Authority,
+            };
+            let rrset = message
+                .rrsets(section)
+                .into_iter()
+                .next()
+                .ok_or_else(|| invalid_snapshot("entry without records"))?;
+            let ttl = u32::from(rrset.ttl());
+            let cached = CachedRRset {
+                expires: now + left,
+                ..CachedRRset::new(rrset, credibility, ttl, now)
+            };
+
+            match (head[0], message.questions.first()) {
+                (SNAPSHOT_RRSET, _) => {
+                    self.store(cached).await;
+                }
+                (
+                    kind @ (SNAPSHOT_NXDOMAIN | SNAPSHOT_NODATA),
+                    Some(Question {
+                        qname,
+                        qtype: RecordQType::RecordType(rtype),
+                        qclass: RecordQClass::RecordClass(class),
+                    }),
+                ) => {
+                    let key = match kind {
+                        SNAPSHOT_NXDOMAIN => NegativeKey::NxDomain(qname.clone(), *class),
+                        _ => NegativeKey::NoData(RRsetKey::new(qname.clone(), *rtype, *class)),
+                    };
+                    self.negative.insert(key, Arc::new(cached)).await;
+                }
+                _ => return Err(invalid_snapshot("invalid entry")),
+            }
+            restored += 1;
+        }
+        Ok(restored)
+    }
+}
+
+/// Append an entry to a snapshot, returning false for the odd RRset too large for a message
+fn push_entry(
+    snapshot: &mut Vec<u8>,
+    kind: u8,
+    cached: &CachedRRset,
+    message: &Message,
+    system_now: SystemTime,
+    now: Instant,
+) -> bool {
+    let Ok(message) = message.to_bytes() else {
+        return false;
+    };
+    let expires = match cached.expires.checked_duration_since(now) {
+        Some(left) => system_now + left,
+        None => system_now - (now - cached.expires),
+    };
+    let seconds = expires
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    snapshot.push(kind);
+    snapshot.push(cached.credibility as u8);
+    snapshot.extend_from_slice(&seconds.to_be_bytes());
+    snapshot.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    snapshot.extend_from_slice(&message);
+    true
+}
+
+/// State shared by the parts of the server answering queries
 pub struct ServerContext {
-    pub cache: Cache<u16, u16>,
+    pub cache: RecordCache,
+}
+
+impl ServerContext {
+    pub fn new(cache: CacheConfig) -> Self {
+        Self {
+            cache: RecordCache::new(cache),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use parsedns::{
+        header::RCode,
+        message::{Message, Section},
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            data::RecordData,
+            record::Record,
+            rrset::{RRset, RRsetKey},
+            types::{RecordQType, RecordType},
+        },
+        utils::TTL,
+    };
+
+    use super::{CacheConfig, CacheStats, Credibility, Negative, RecordCache, STALE_TTL};
+
+    fn rrset(address: &str, ttl: u32) -> RRset {
+        let record = Record::new(
+            "ns.example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(ttl),
+            RecordData::from_presentation(RecordType::A, RecordClass::IN, address).unwrap(),
+        );
+        RRset::from_records([&record]).remove(0)
+    }
+
+    fn record(name: &str, ttl: u32, rtype: RecordType, rdata: &str) -> Record {
+        Record::new(
+            name.parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(ttl),
+            RecordData::from_presentation(rtype, RecordClass::IN, rdata).unwrap(),
+        )
+    }
+
+    /// An authoritative denial of `qname` from example.com.
+    fn denial(rcode: RCode, qname: &str, soa_ttl: u32) -> Message {
+        let mut response = Message::new();
+        response.header.qr = true;
+        response.header.aa = true;
+        response.header.rcode = rcode;
+        response.questions.push(Question::new(
+            qname.parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+        response.authorities.push(record(
+            "example.com.",
+            soa_ttl,
+            RecordType::SOA,
+            "ns.example.com. admin.example.com. 1 7200 900 1209600 300",
+        ));
+        response
+    }
+
+    fn key() -> RRsetKey {
+        RRsetKey::new(
+            "NS.example.com.".parse().unwrap(),
+            RecordType::A,
+            RecordClass::IN,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_credibility() {
+        assert_eq!(
+            Credibility::of(Section::Additional, true),
+            Credibility::Additional
+        );
+        assert!(Credibility::of(Section::Answer, true) > Credibility::of(Section::Answer, false));
+
+        let cache = RecordCache::new(CacheConfig::default());
+        assert!(
+            cache
+                .insert(rrset("192.0.2.1", 300), Credibility::AuthoritativeAnswer)
+                .await
+        );
+        // Glue doesn't replace authoritative data
+        assert!(
+            !cache
+                .insert(rrset("192.0.2.99", 300), Credibility::Additional)
+                .await
+        );
+        let cached = cache.get(&key()).await.unwrap();
+        assert_eq!(cached.rdatas(), rrset("192.0.2.1", 300).rdatas());
+
+        // Data as credible does
+        assert!(
+            cache
+                .insert(rrset("192.0.2.2", 300), Credibility::AuthoritativeAnswer)
+                .await
+        );
+        let cached = cache.get(&key()).await.unwrap();
+        assert_eq!(cached.rdatas(), rrset("192.0.2.2", 300).rdatas());
+    }
+
+    #[tokio::test]
+    async fn test_ttl() {
+        let config = CacheConfig {
+            min_ttl: 60,
+            max_ttl: 3600,
+            ..Default::default()
+        };
+        let cache = RecordCache::new(config);
+        let now = Instant::now();
+
+        cache
+            .insert_at(rrset("192.0.2.1", 86400), Credibility::Additional, now)
+            .await;
+        let cached = cache.get_at(&key(), now, Duration::ZERO).await.unwrap();
+        assert_eq!(cached.ttl(), TTL::from(3600));
+        let later = now + Duration::from_secs(1000);
+        let cached = cache.get_at(&key(), later, Duration::ZERO).await.unwrap();
+        assert_eq!(cached.ttl(), TTL::from(2600));
+        assert!(cache
+            .get_at(&key(), now + Duration::from_secs(3600), Duration::ZERO)
+            .await
+            .is_none());
+        // Stale within the window
+        let window = Duration::from_secs(600);
+        let cached = cache
+            .get_at(&key(), now + Duration::from_secs(3900), window)
+            .await
+            .unwrap();
+        assert_eq!(cached.ttl(), TTL::from(STALE_TTL));
+        assert!(cache
+            .get_at(&key(), now + Duration::from_secs(4200), window)
+            .await
+            .is_none());
+
+        cache
+            .insert_at(rrset("192.0.2.1", 5), Credibility::Additional, now)
+            .await;
+        let cached = cache.get_at(&key(), now, Duration::ZERO).await.unwrap();
+        assert_eq!(cached.ttl(), TTL::from(60));
+
+        // Expired data is replaced whatever its credibility
+        cache
+            .insert_at(rrset("192.0.2.1", 300), Credibility::Zone, now)
+            .await;
+        assert!(
+            cache
+                .insert_at(
+                    rrset("192.0.2.2", 300),
+                    Credibility::Additional,
+                    now + Duration::from_secs(301)
+                )
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_negative() {
+        let config = CacheConfig {
+            max_negative_ttl: 120,
+            ..Default::default()
+        };
+        let cache = RecordCache::new(config);
+        let example = "example.com.".parse().unwrap();
+        let now = Instant::now();
+        let key = |name: &str, rtype| RRsetKey::new(name.parse().unwrap(), rtype, RecordClass::IN);
+
+        // The SOA minimum, capped by the configuration
+        let response = denial(RCode::NXDomain, "gone.example.com.", 3600);
+        assert!(cache.insert_negative_at(&response, &example, now).await);
+        let Some(Negative::NxDomain(soa)) = cache
+            .get_negative_at(
+                &key("a.b.gone.example.com.", RecordType::MX),
+                now,
+                Duration::ZERO,
+            )
+            .await
+        else {
+            panic!("names below NXDOMAIN aren't denied");
+        };
+        assert_eq!(soa.ttl(), TTL::from(120));
+        assert!(cache
+            .get_negative_at(
+                &key("gone.example.com.", RecordType::A),
+                now + Duration::from_secs(120),
+                Duration::ZERO,
+            )
+            .await
+            .is_none());
+
+        // NODATA at the end of a CNAME chain only denies that type
+        let mut response = denial(RCode::NoError, "www.example.com.", 60);
+        response.answers.push(record(
+            "www.example.com.",
+            300,
+            RecordType::CNAME,
+            "web.example.com.",
+        ));
+        assert!(cache.insert_negative_at(&response, &example, now).await);
+        let negative = cache
+            .get_negative_at(&key("web.example.com.", RecordType::A), now, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(negative.rcode(), RCode::NoError);
+        assert_eq!(negative.soa().ttl(), TTL::from(60));
+        assert!(cache
+            .get_negative_at(
+                &key("web.example.com.", RecordType::AAAA),
+                now,
+                Duration::ZERO
+            )
+            .await
+            .is_none());
+        assert!(cache
+            .get_negative_at(&key("www.example.com.", RecordType::A), now, Duration::ZERO)
+            .await
+            .is_none());
+
+        // Without an SOA the server had a say over, nothing is cached
+        let response = denial(RCode::NXDomain, "gone.example.com.", 3600);
+        let other = "example.org.".parse().unwrap();
+        assert!(!cache.insert_negative_at(&response, &other, now).await);
+        let mut response = denial(RCode::NXDomain, "gone.example.com.", 3600);
+        response.authorities.clear();
+        assert!(!cache.insert_negative_at(&response, &example, now).await);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let config = CacheConfig {
+            prefetch_hits: 2,
+            prefetch_percent: 10,
+            ..Default::default()
+        };
+        let cache = RecordCache::new(config);
+        let now = Instant::now();
+        cache
+            .insert_at(
+                rrset("192.0.2.1", 1000),
+                Credibility::AuthoritativeAnswer,
+                now,
+            )
+            .await;
+        let late = now + Duration::from_secs(950);
+
+        // Not popular yet
+        cache.get_at(&key(), now, Duration::ZERO).await.unwrap();
+        assert!(!cache.take_prefetch_at(&key(), late).await);
+        cache.get_at(&key(), now, Duration::ZERO).await.unwrap();
+        // Popular, but early on
+        assert!(!cache.take_prefetch_at(&key(), now).await);
+        assert!(cache.take_prefetch_at(&key(), late).await);
+        // Only the one refresh
+        assert!(!cache.take_prefetch_at(&key(), late).await);
+
+        let missing = RRsetKey::new(
+            "missing.example.com.".parse().unwrap(),
+            RecordType::A,
+            RecordClass::IN,
+        );
+        assert!(cache.get_at(&missing, now, Duration::ZERO).await.is_none());
+        cache.count_query(true);
+        cache.count_query(false);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                prefetches: 1,
+                ..Default::default()
+            }
+        );
+
+        // The refreshed entry starts over
+        cache
+            .insert_at(
+                rrset("192.0.2.1", 1000),
+                Credibility::AuthoritativeAnswer,
+                late,
+            )
+            .await;
+        assert!(!cache.take_prefetch_at(&key(), late).await);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let cache = RecordCache::new(CacheConfig::default());
+        let now = Instant::now();
+        // Expiries are kept to the second
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let system_now = UNIX_EPOCH + Duration::from_secs(seconds.as_secs());
+        let example = "example.com.".parse().unwrap();
+        cache
+            .insert_at(
+                rrset("192.0.2.1", 300),
+                Credibility::AuthoritativeAnswer,
+                now,
+            )
+            .await;
+        let short = record("short.example.com.", 60, RecordType::A, "192.0.2.2");
+        cache
+            .insert_at(
+                RRset::from_records([&short]).remove(0),
+                Credibility::AuthoritativeAnswer,
+                now,
+            )
+            .await;
+        let response = denial(RCode::NXDomain, "gone.example.com.", 3600);
+        cache.insert_negative_at(&response, &example, now).await;
+        let (snapshot, entries) = cache.snapshot(system_now, now);
+        assert_eq!(entries, 3);
+
+        // Restored 100 seconds on, when the short lived entry has expired
+        let later = now + Duration::from_secs(100);
+        let restored = RecordCache::new(CacheConfig::default());
+        let count = restored
+            .restore(&snapshot, system_now + Duration::from_secs(100), later)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        let cached = restored
+            .get_at(&key(), later, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(cached.ttl(), TTL::from(200));
+        assert_eq!(cached.rdatas(), rrset("192.0.2.1", 300).rdatas());
+        let gone = RRsetKey::new(
+            "www.gone.example.com.".parse().unwrap(),
+            RecordType::A,
+            RecordClass::IN,
+        );
+        let Some(Negative::NxDomain(soa)) =
+            restored.get_negative_at(&gone, later, Duration::ZERO).await
+        else {
+            panic!("NXDOMAIN not restored");
+        };
+        assert_eq!(soa.ttl(), TTL::from(200));
+        // With its credibility
+        assert!(
+            !restored
+                .insert_at(rrset("192.0.2.99", 300), Credibility::Additional, later)
+                .await
+        );
+
+        let path = std::env::temp_dir().join(format!("jdns-{}.cache", std::process::id()));
+        assert_eq!(cache.dump(&path).await.unwrap(), 3);
+        assert_eq!(
+            RecordCache::new(CacheConfig::default())
+                .load(&path)
+                .await
+                .unwrap(),
+            3
+        );
+        std::fs::remove_file(path).unwrap();
+
+        for bad in [
+            &b"JDNSCACH\x02"[..],
+            b"not a snapshot",
+            &snapshot[..snapshot.len() - 1],
+        ] {
+            assert!(restored.restore(bad, system_now, now).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_budgets() {
+        let config = CacheConfig {
+            positive_bytes: 1000,
+            infrastructure_bytes: 1000,
+            ..Default::default()
+        };
+        let cache = RecordCache::new(config);
+        let ns = record("example.com.", 300, RecordType::NS, "ns.example.com.");
+        let ns = RRset::from_records([&ns]).remove(0);
+        cache.insert(ns.clone(), Credibility::Additional).await;
+        cache
+            .insert(rrset("192.0.2.1", 300), Credibility::Additional)
+            .await;
+
+        // Answers large and small, far over the budget
+        let long = format!("\"{}\"", "x".repeat(200));
+        for n in 0..50 {
+            let name = format!("{}.example.com.", n);
+            let rtype = match n % 2 {
+                0 => RecordType::A,
+                _ => RecordType::TXT,
+            };
+            let rdata = match rtype {
+                RecordType::A => "192.0.2.80",
+                _ => &long,
+            };
+            let rrset = RRset::from_records([&record(&name, 300, rtype, rdata)]).remove(0);
+            cache.insert(rrset, Credibility::AuthoritativeAnswer).await;
+        }
+        cache.cache.run_pending_tasks().await;
+        cache.infrastructure.run_pending_tasks().await;
+
+        assert!(cache.cache.weighted_size() <= 1000);
+        let stats = cache.stats();
+        assert!(stats.positive_evictions > 0);
+        assert_eq!(stats.infrastructure_evictions, 0);
+        // Name servers and glue survive the flood
+        assert_eq!(cache.get(ns.key()).await.unwrap().rdatas(), ns.rdatas());
+        assert!(cache.get(&key()).await.is_some());
+
+        // An authoritative answer moves the address out of the infrastructure budget
+        cache
+            .insert(rrset("192.0.2.1", 300), Credibility::AuthoritativeAnswer)
+            .await;
+        assert!(cache.infrastructure.get(&key()).await.is_none());
+        assert!(cache.get(&key()).await.is_some());
+    }
 }
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use parsedns::rr::name::Name;
+
+use super::upstream::Upstreams;
+
+/// What to do with queries for the names under a suffix
+#[derive(Clone)]
+pub enum Route {
+    /// Forward to this group of upstreams
+    Forward(Arc<Upstreams>),
+    /// Don't forward at all and answer NXDOMAIN, so names such as internal zones never leak
+    /// to upstreams that don't know them
+    NxDomain,
+}
+
+/// Chooses where queries are forwarded by the suffix of their name. The longest suffix that
+/// matches wins, compared label by label, so `corp.` matches `mail.corp.` but not `notcorp.`.
+/// Names no other suffix matches fall through to the default upstreams at the root.
+pub struct ForwardTable {
+    /// Longest suffixes first
+    routes: Vec<(Name, Route)>,
+}
+
+impl ForwardTable {
+    pub fn new(default: Upstreams) -> Self {
+        Self {
+            routes: vec![(Name::root(), Route::Forward(Arc::new(default)))],
+        }
+    }
+
+    /// Forward the names under `suffix` to `upstreams`
+    pub fn forward(&mut self, suffix: Name, upstreams: Upstreams) {
+        self.insert(suffix, Route::Forward(Arc::new(upstreams)));
+    }
+
+    /// Answer NXDOMAIN for the names under `suffix`
+    pub fn nxdomain(&mut self, suffix: Name) {
+        self.insert(suffix, Route::NxDomain);
+    }
+
+    /// Set the route of `suffix`, replacing an earlier one for the same suffix
+    pub fn insert(&mut self, suffix: Name, route: Route) {
+        match self.routes.iter_mut().find(|(name, _)| *name == suffix) {
+            Some(existing) => existing.1 = route,
+            None => {
+                let at = self
+                    .routes
+                    .partition_point(|(name, _)| name.label_count() >= suffix.label_count());
+                self.routes.insert(at, (suffix, route));
+            }
+        }
+    }
+
+    /// The route of the longest suffix of `qname`
+    pub fn route(&self, qname: &Name) -> &Route {
+        self.routes
+            .iter()
+            .find(|(suffix, _)| qname.is_subdomain_of(suffix))
+            .map(|(_, route)| route)
+            // The root matches every name
+            .unwrap_or_else(|| unreachable!("no route at the root"))
+    }
+
+    /// Every group of upstreams in the table
+    pub fn groups(&self) -> impl Iterator<Item = &Arc<Upstreams>> {
+        self.routes.iter().filter_map(|(_, route)| match route {
+            Route::Forward(upstreams) => Some(upstreams),
+            Route::NxDomain => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use super::{ForwardTable, Route};
+    use crate::dns::upstream::{HealthPolicy, Strategy, Upstreams};
+
+    fn upstreams(n: u8) -> Upstreams {
+        let addr = SocketAddr::from((Ipv4Addr::new(192, 0, 2, n), 53));
+        Upstreams::new(vec![(addr, 1)], Strategy::Failover, HealthPolicy::default())
+    }
+
+    /// The last octet of the first upstream of the route, or 0 for NXDOMAIN
+    fn route(table: &ForwardTable, qname: &str) -> u8 {
+        match table.route(&qname.parse().unwrap()) {
+            Route::Forward(upstreams) => match upstreams.order()[0] {
+                SocketAddr::V4(addr) => addr.ip().octets()[3],
+                SocketAddr::V6(_) => unreachable!(),
+            },
+            Route::NxDomain => 0,
+        }
+    }
+
+    #[test]
+    fn test_longest_suffix() {
+        let mut table = ForwardTable::new(upstreams(1));
+        table.forward("example.com.".parse().unwrap(), upstreams(2));
+        table.forward("internal.example.com.".parse().unwrap(), upstreams(3));
+        table.nxdomain("corp.".parse().unwrap());
+
+        assert_eq!(route(&table, "example.org."), 1);
+        assert_eq!(route(&table, "example.com."), 2);
+        assert_eq!(route(&table, "www.EXAMPLE.com."), 2);
+        assert_eq!(route(&table, "db.internal.example.com."), 3);
+        assert_eq!(route(&table, "notinternal.example.com."), 2);
+        assert_eq!(route(&table, "mail.corp."), 0);
+        assert_eq!(route(&table, "notcorp."), 1);
+        assert_eq!(table.groups().count(), 3);
+
+        // Later rules replace earlier ones for the same suffix
+        table.forward("corp.".parse().unwrap(), upstreams(4));
+        assert_eq!(route(&table, "mail.corp."), 4);
+        assert_eq!(table.groups().count(), 4);
+    }
+}
@@ -0,0 +1,644 @@
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use parsedns::{
+    header::RCode,
+    message::{Message, Section},
+    question::Question,
+    rr::{
+        class::{RecordClass, RecordQClass},
+        name::Name,
+        rdata::OPT,
+        record::Record,
+        types::{RecordQType, RecordType},
+    },
+};
+
+use super::{
+    context::ServerContext,
+    forward_table::{ForwardTable, Route},
+    handler::{Request, RequestHandler, Transport, EDE_STALE_ANSWER, EDNS_UDP_PAYLOAD_SIZE},
+    transaction::{ExchangeError, Transactions},
+    upstream::Upstreams,
+};
+
+/// CNAMEs followed when answering from the cache
+const MAX_CNAME_CHAIN: u32 = 8;
+
+/// How long to wait for an upstream and how often to try again
+///
+/// ```text
+/// The retransmission interval should be based on prior statistics if
+/// possible.  Too aggressive retransmission can easily slow responses for
+/// the community at large.
+/// ```
+/// [RFC1035 7.2: Sending the queries](https://datatracker.ietf.org/doc/html/rfc1035#section-7.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Time given to the first attempt
+    pub timeout: Duration,
+    /// Attempts made after the first one times out
+    pub retries: u32,
+    /// Each retry waits this many times longer than the attempt before it
+    pub backoff: u32,
+    /// Upper bound on the time given to one attempt
+    pub max_timeout: Duration,
+}
+
+impl RetryPolicy {
+    /// The time given to an attempt, counting from zero
+    pub fn timeout(&self, attempt: u32) -> Duration {
+        let factor = self.backoff.saturating_pow(attempt);
+        self.timeout.saturating_mul(factor).min(self.max_timeout)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(800),
+            retries: 2,
+            backoff: 2,
+            max_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Passes queries on to recursive resolvers upstream and relays their answers. The forwarding
+/// table picks the group of upstreams for each query by its name. A query that times out is sent
+/// again to the next upstream of the group, and the client gets SERVFAIL once the retries run
+/// out.
+///
+/// Answers are cached by RRset in the context, denials included. When the upstreams fail,
+/// answers that expired within the stale window of the cache are served instead, and popular
+/// answers are forwarded again in the background before they expire.
+///
+/// [RFC8767 5: Example Method](https://datatracker.ietf.org/doc/html/rfc8767#section-5)
+pub struct Forwarder {
+    /// For the background tasks prefetching answers
+    me: Weak<Self>,
+    table: ForwardTable,
+    retry: RetryPolicy,
+    transactions: Arc<Transactions>,
+    context: Arc<ServerContext>,
+}
+
+impl Forwarder {
+    /// Start probing the upstreams out of rotation
+    pub async fn new(
+        table: ForwardTable,
+        retry: RetryPolicy,
+        context: Arc<ServerContext>,
+    ) -> io::Result<Arc<Self>> {
+        if table.groups().any(|upstreams| upstreams.is_empty()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no upstreams to forward to",
+            ));
+        }
+
+        let transactions = Arc::new(Transactions::new());
+        for upstreams in table.groups() {
+            tokio::spawn(Self::probe(
+                upstreams.clone(),
+                transactions.clone(),
+                retry.timeout,
+            ));
+        }
+
+        Ok(Arc::new_cyclic(|me| Self {
+            me: me.clone(),
+            table,
+            retry,
+            transactions,
+            context,
+        }))
+    }
+
+    pub fn table(&self) -> &ForwardTable {
+        &self.table
+    }
+
+    /// Send the query of a client to one upstream of a group
+    async fn query(
+        &self,
+        upstreams: &Upstreams,
+        request: &Request,
+        question: &Question,
+        upstream: SocketAddr,
+        wait: Duration,
+    ) -> Result<Message, ExchangeError> {
+        let mut lookup = Message::new();
+        lookup.header.id = request.message.header.id;
+        lookup.header.rd = request.message.header.rd;
+        lookup.header.cd = request.message.header.cd;
+        lookup.questions.push(question.clone());
+        if let Some(edns) = request.edns {
+            let mut opt = OPT::new(EDNS_UDP_PAYLOAD_SIZE);
+            opt.set_dnssec_ok(edns.dnssec_ok);
+            lookup.additionals.push(Record::opt(opt));
+        }
+
+        exchange(
+            upstreams,
+            &self.transactions,
+            request.client,
+            lookup,
+            upstream,
+            wait,
+        )
+        .await
+    }
+
+    /// Send the query of a client to the upstreams of a group in turn until one of them answers,
+    /// caching the answer. None once the retries run out.
+    async fn forward(
+        &self,
+        upstreams: &Upstreams,
+        request: &Request,
+        question: &Question,
+    ) -> Option<Message> {
+        let order = upstreams.order();
+        for attempt in 0..=self.retry.retries {
+            let upstream = order[attempt as usize % order.len()];
+            let wait = self.retry.timeout(attempt);
+            let response = self
+                .query(upstreams, request, question, upstream, wait)
+                .await;
+            match response {
+                Ok(response) => {
+                    // Checking disabled lets through data that failed validation
+                    if !request.message.header.cd && cacheable(&response) {
+                        let cache = &self.context.cache;
+                        cache.insert_response(&response, &Name::root()).await;
+                        cache.insert_negative(&response, &Name::root()).await;
+                    }
+                    return Some(response);
+                }
+                Err(ExchangeError::NoFreeId) => break,
+                Err(_) => continue,
+            }
+        }
+        None
+    }
+
+    /// The answer from the cache, with stale data included when asked for. Popular answers
+    /// close to expiring are prefetched.
+    async fn cached(&self, question: &Question, stale: bool) -> Option<Message> {
+        let cache = &self.context.cache;
+        let response = cache.answer_chain(question, stale, MAX_CNAME_CHAIN).await?;
+        if !stale {
+            let mut due = false;
+            for rrset in response.rrsets(Section::Answer) {
+                due |= cache.take_prefetch(rrset.key()).await;
+            }
+            if due {
+                self.prefetch(question.clone());
+            }
+        }
+        Some(response)
+    }
+
+    /// Forward a popular question again before its answer expires from the cache, so it is
+    /// still there for the queries after
+    fn prefetch(&self, question: Question) {
+        let Some(forwarder) = self.me.upgrade() else {
+            return;
+        };
+        tokio::spawn(async move {
+            let Route::Forward(upstreams) = forwarder.table.route(&question.qname) else {
+                return;
+            };
+            let mut message = Message::new();
+            message.header.rd = true;
+            message.questions.push(question.clone());
+            // Prefetches are not asked on behalf of a client
+            let prefetcher = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0));
+            let request = Request::new(message, prefetcher, Transport::Udp);
+            if forwarder
+                .forward(upstreams, &request, &question)
+                .await
+                .is_none()
+            {
+                println!("Failed to prefetch {}", question.qname);
+            }
+        });
+    }
+
+    /// Every probe interval, ask the upstreams out of rotation for the root NS records. Any
+    /// answer brings an upstream back.
+    async fn probe(upstreams: Arc<Upstreams>, transactions: Arc<Transactions>, wait: Duration) {
+        // Probes are not asked on behalf of a client
+        let prober = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0));
+        let mut interval = tokio::time::interval(upstreams.health().probe_interval);
+        loop {
+            interval.tick().await;
+            for upstream in upstreams.unhealthy() {
+                let mut lookup = Message::new();
+                lookup.header.id = rand::random();
+                lookup.questions.push(Question::new(
+                    Name::root(),
+                    RecordQType::RecordType(RecordType::NS),
+                    RecordQClass::RecordClass(RecordClass::IN),
+                ));
+                let (upstreams, transactions) = (upstreams.clone(), transactions.clone());
+                tokio::spawn(async move {
+                    exchange(&upstreams, &transactions, prober, lookup, upstream, wait).await
+                });
+            }
+        }
+    }
+}
+
+/// Whether the records of a response can be cached, which takes a complete answer or denial
+fn cacheable(response: &Message) -> bool {
+    matches!(response.header.rcode, RCode::NoError | RCode::NXDomain) && !response.header.tc
+}
+
+/// Send `lookup` to an upstream and record how it went in the health of the upstream. Running
+/// out of query IDs says nothing about the upstream.
+async fn exchange(
+    upstreams: &Upstreams,
+    transactions: &Transactions,
+    client: SocketAddr,
+    lookup: Message,
+    upstream: SocketAddr,
+    wait: Duration,
+) -> Result<Message, ExchangeError> {
+    let started = Instant::now();
+    let response = transactions.exchange(client, lookup, upstream, wait).await;
+    match response {
+        Ok(_) => upstreams.record_success(upstream, started.elapsed()),
+        Err(ExchangeError::NoFreeId) => {}
+        Err(_) => upstreams.record_failure(upstream, wait),
+    }
+    response
+}
+
+impl RequestHandler for Forwarder {
+    async fn handle(&self, request: Request) -> Option<Message> {
+        let question = match request.message.questions.as_slice() {
+            [question] => question.clone(),
+            _ => return Some(request.response(RCode::FormErr)),
+        };
+
+        let upstreams = match self.table.route(&question.qname) {
+            Route::Forward(upstreams) => upstreams,
+            Route::NxDomain => {
+                let mut response = request.response(RCode::NXDomain);
+                response.header.ra = true;
+                return Some(response);
+            }
+        };
+
+        // The cache keeps no signatures with the records, so clients doing their own DNSSEC
+        // validation get the answers of the upstreams as they are
+        let dnssec = request.message.header.cd || request.edns.is_some_and(|edns| edns.dnssec_ok);
+        if !dnssec {
+            let cached = self.cached(&question, false).await;
+            self.context.cache.count_query(cached.is_some());
+            if let Some(cached) = cached {
+                return Some(cached_response(&request, cached, false));
+            }
+        }
+
+        let mut response = match self.forward(upstreams, &request, &question).await {
+            Some(response) if response.header.rcode != RCode::ServFail => response,
+            failed => {
+                if !dnssec {
+                    if let Some(stale) = self.cached(&question, true).await {
+                        println!(
+                            "Serving stale {} after the upstreams failed",
+                            question.qname
+                        );
+                        return Some(cached_response(&request, stale, true));
+                    }
+                }
+                failed.unwrap_or_else(|| request.response(RCode::ServFail))
+            }
+        };
+        response.header.ra = true;
+        Some(response)
+    }
+}
+
+/// The response to a request answered from the cache, marked when the answer is stale
+fn cached_response(request: &Request, cached: Message, stale: bool) -> Message {
+    let mut response = request.response(cached.header.rcode);
+    response.header.ra = true;
+    response.answers = cached.answers;
+    response.authorities = cached.authorities;
+    if stale {
+        request.extended_error(&mut response, EDE_STALE_ANSWER);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use parsedns::{
+        header::RCode,
+        message::Message,
+        rr::{
+            class::RecordClass, data::RecordData, name::Name, rdata::OPT, record::Record,
+            types::RecordType,
+        },
+        utils::TTL,
+    };
+    use tokio::net::UdpSocket;
+
+    use super::{Forwarder, RetryPolicy};
+    use crate::dns::{
+        context::{CacheConfig, ServerContext, STALE_TTL},
+        forward_table::ForwardTable,
+        handler::{fixtures::request, Request, RequestHandler, Transport},
+        upstream::{HealthPolicy, Strategy, Upstreams},
+    };
+
+    /// An upstream on loopback that only answers once it has ignored `silent` queries
+    async fn upstream(silent: usize) -> (SocketAddr, Arc<AtomicUsize>) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        tokio::spawn(async move {
+            let mut buff = [0; 512];
+            loop {
+                let (size, client) = socket.recv_from(&mut buff).await.unwrap();
+                if counter.fetch_add(1, Ordering::SeqCst) < silent {
+                    continue;
+                }
+                let mut response = Message::from_bytes(&buff[..size]).unwrap();
+                response.header.qr = true;
+                socket
+                    .send_to(&response.to_bytes().unwrap(), client)
+                    .await
+                    .unwrap();
+            }
+        });
+        (addr, received)
+    }
+
+    /// An upstream on loopback resolving every name under example.com. to 192.0.2.1, except
+    /// those under missing.example.com., which don't exist. It stops answering once it is down.
+    async fn resolving_upstream() -> (SocketAddr, Arc<AtomicUsize>, Arc<AtomicBool>) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let received = Arc::new(AtomicUsize::new(0));
+        let down = Arc::new(AtomicBool::new(false));
+        let (counter, stopped) = (received.clone(), down.clone());
+        tokio::spawn(async move {
+            let missing: Name = "missing.example.com.".parse().unwrap();
+            let mut buff = [0; 512];
+            loop {
+                let (size, client) = socket.recv_from(&mut buff).await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                if stopped.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let mut response = Message::from_bytes(&buff[..size]).unwrap();
+                response.header.qr = true;
+                let qname = response.questions[0].qname.clone();
+                if qname.is_subdomain_of(&missing) {
+                    response.header.rcode = RCode::NXDomain;
+                    response.authorities.push(Record::new(
+                        "example.com.".parse().unwrap(),
+                        RecordClass::IN,
+                        TTL::from(3600),
+                        RecordData::from_presentation(
+                            RecordType::SOA,
+                            RecordClass::IN,
+                            "ns.example.com. admin.example.com. 1 7200 900 1209600 300",
+                        )
+                        .unwrap(),
+                    ));
+                } else {
+                    response.answers.push(Record::new(
+                        qname,
+                        RecordClass::IN,
+                        TTL::from(300),
+                        RecordData::from_presentation(RecordType::A, RecordClass::IN, "192.0.2.1")
+                            .unwrap(),
+                    ));
+                }
+                socket
+                    .send_to(&response.to_bytes().unwrap(), client)
+                    .await
+                    .unwrap();
+            }
+        });
+        (addr, received, down)
+    }
+
+    fn context() -> Arc<ServerContext> {
+        Arc::new(ServerContext::new(CacheConfig::default()))
+    }
+
+    const RETRY: RetryPolicy = RetryPolicy {
+        timeout: Duration::from_millis(50),
+        retries: 2,
+        backoff: 2,
+        max_timeout: Duration::from_millis(150),
+    };
+
+    fn failover(upstreams: &[SocketAddr]) -> Upstreams {
+        let health = HealthPolicy {
+            max_failures: 1,
+            probe_interval: Duration::from_millis(100),
+        };
+        let upstreams = upstreams.iter().map(|&addr| (addr, 1)).collect();
+        Upstreams::new(upstreams, Strategy::Failover, health)
+    }
+
+    #[test]
+    fn test_backoff() {
+        let timeouts: Vec<_> = (0..4).map(|attempt| RETRY.timeout(attempt)).collect();
+        assert_eq!(
+            timeouts,
+            [50, 100, 150, 150].map(Duration::from_millis).to_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_next_upstream() {
+        // Ignores the first query, and answers the probe that follows
+        let (flaky, flaky_received) = upstream(1).await;
+        let (answering, answering_received) = upstream(0).await;
+        let table = ForwardTable::new(failover(&[flaky, answering]));
+        let forwarder = Forwarder::new(table, RETRY, context()).await.unwrap();
+        let upstreams = forwarder.table().groups().next().unwrap();
+
+        let response = forwarder.handle(request(7, "example.com.")).await.unwrap();
+        assert_eq!(response.header.id, 7);
+        assert_eq!(response.header.rcode, RCode::NoError);
+        assert!(response.header.ra);
+        assert_eq!(flaky_received.load(Ordering::SeqCst), 1);
+        assert_eq!(answering_received.load(Ordering::SeqCst), 1);
+        assert!(forwarder.transactions.is_empty());
+
+        // Out of rotation until the probe gets an answer
+        assert_eq!(upstreams.unhealthy(), [flaky]);
+        forwarder.handle(request(8, "example.com.")).await.unwrap();
+        assert_eq!(answering_received.load(Ordering::SeqCst), 2);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(flaky_received.load(Ordering::SeqCst) >= 2);
+        assert!(upstreams.unhealthy().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_servfail_when_exhausted() {
+        let (silent, received) = upstream(usize::MAX).await;
+        let upstreams = Upstreams::new(
+            vec![(silent, 1)],
+            Strategy::Failover,
+            HealthPolicy::default(),
+        );
+        let forwarder = Forwarder::new(ForwardTable::new(upstreams), RETRY, context())
+            .await
+            .unwrap();
+
+        let response = forwarder.handle(request(9, "example.com.")).await.unwrap();
+        assert_eq!(response.header.id, 9);
+        assert_eq!(response.header.rcode, RCode::ServFail);
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+        assert!(forwarder.transactions.is_empty());
+
+        let mut table = ForwardTable::new(failover(&[silent]));
+        table.forward("example.com.".parse().unwrap(), failover(&[]));
+        assert!(Forwarder::new(table, RETRY, context()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forwarding_table() {
+        let (public, public_received) = upstream(0).await;
+        let (internal, internal_received) = upstream(0).await;
+        let mut table = ForwardTable::new(failover(&[public]));
+        table.forward("internal.example.".parse().unwrap(), failover(&[internal]));
+        table.nxdomain("corp.".parse().unwrap());
+        let forwarder = Forwarder::new(table, RETRY, context()).await.unwrap();
+
+        let response = forwarder.handle(request(1, "example.com.")).await.unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+        let response = forwarder
+            .handle(request(2, "db.internal.example."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+        let response = forwarder.handle(request(3, "mail.corp.")).await.unwrap();
+        assert_eq!(response.header.id, 3);
+        assert_eq!(response.header.rcode, RCode::NXDomain);
+
+        assert_eq!(public_received.load(Ordering::SeqCst), 1);
+        assert_eq!(internal_received.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let (addr, received, _) = resolving_upstream().await;
+        let table = ForwardTable::new(failover(&[addr]));
+        let context = context();
+        let forwarder = Forwarder::new(table, RETRY, context.clone()).await.unwrap();
+
+        let first = forwarder
+            .handle(request(1, "www.example.com."))
+            .await
+            .unwrap();
+        let second = forwarder
+            .handle(request(2, "www.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(received.load(Ordering::SeqCst), 1);
+        assert_eq!(second.header.id, 2);
+        assert!(second.header.ra);
+        assert_eq!(second.answers[0].rdata(), first.answers[0].rdata());
+        assert!(u32::from(second.answers[0].ttl()) <= 300);
+
+        // Denials are cached too, for the names below the denied one as well
+        for (id, qname) in [(3, "missing.example.com."), (4, "www.missing.example.com.")] {
+            let response = forwarder.handle(request(id, qname)).await.unwrap();
+            assert_eq!(response.header.rcode, RCode::NXDomain);
+            assert_eq!(response.authorities[0].rtype(), RecordType::SOA);
+        }
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+        let stats = context.cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+
+        // Clients validating for themselves get what the upstream says
+        let mut checking_disabled = request(5, "www.example.com.");
+        checking_disabled.message.header.cd = true;
+        forwarder.handle(checking_disabled).await.unwrap();
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_serve_stale() {
+        let (addr, _, down) = resolving_upstream().await;
+        let table = ForwardTable::new(failover(&[addr]));
+        let context = Arc::new(ServerContext::new(CacheConfig {
+            max_ttl: 1,
+            ..Default::default()
+        }));
+        let forwarder = Forwarder::new(table, RETRY, context).await.unwrap();
+        forwarder
+            .handle(request(1, "www.example.com."))
+            .await
+            .unwrap();
+
+        down.store(true, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let mut edns = request(2, "www.example.com.");
+        edns.message.additionals.push(Record::opt(OPT::new(1232)));
+        let response = forwarder
+            .handle(Request::new(edns.message, edns.client, Transport::Udp))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+        assert_eq!(u32::from(response.answers[0].ttl()), STALE_TTL);
+        let ede = &response.opt().unwrap().options()[0];
+        assert_eq!((ede.code(), ede.data()), (15, &[0, 3][..]));
+
+        // Nothing to fall back on
+        let response = forwarder
+            .handle(request(3, "mail.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::ServFail);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let (addr, received, _) = resolving_upstream().await;
+        let table = ForwardTable::new(failover(&[addr]));
+        let context = Arc::new(ServerContext::new(CacheConfig {
+            prefetch_hits: 1,
+            prefetch_percent: 100,
+            ..Default::default()
+        }));
+        let forwarder = Forwarder::new(table, RETRY, context.clone()).await.unwrap();
+
+        forwarder
+            .handle(request(1, "www.example.com."))
+            .await
+            .unwrap();
+        forwarder
+            .handle(request(2, "www.example.com."))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+        assert_eq!(context.cache.stats().prefetches, 1);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{future::Future, net::SocketAddr, sync::Arc};
+
+use parsedns::{
+    header::RCode,
+    message::Message,
+    rr::{
+        data::RecordData,
+        rdata::{EdnsOption, OPT},
+        record::Record,
+    },
+};
+
+/// Payload size advertised when a client has no EDNS support
+///
+/// [RFC1035 4.2.1: UDP usage](https://datatracker.ietf.org/doc/html/rfc1035#section-4.2.1)
+pub const DEFAULT_UDP_PAYLOAD_SIZE: u16 = 512;
+
+/// Payload size we advertise in our own OPT records
+///
+/// [DNS Flag Day 2020](https://www.dnsflagday.net/2020/)
+pub const EDNS_UDP_PAYLOAD_SIZE: u16 = 1232;
+
+/// The EDNS option carrying extended DNS errors
+///
+/// [RFC8914 2: Extended DNS Error EDNS0 Option Format](https://datatracker.ietf.org/doc/html/rfc8914#section-2)
+const EXTENDED_DNS_ERROR: u16 = 15;
+
+/// The resolver was unable to resolve the answer within its time limits and decided to answer
+/// with previously cached data instead of answering with an error
+///
+/// [RFC8914 4.4: Extended DNS Error Code 3 - Stale Answer](https://datatracker.ietf.org/doc/html/rfc8914#section-4.4)
+pub const EDE_STALE_ANSWER: u16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// The EDNS parameters of a request
+///
+/// [RFC6891 6.1.3: OPT Record TTL Field Use](https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdnsInfo {
+    pub udp_payload_size: u16,
+    pub version: u8,
+    pub dnssec_ok: bool,
+}
+
+impl EdnsInfo {
+    fn of(opt: &OPT) -> Self {
+        Self {
+            // Values below 512 are treated as 512
+            udp_payload_size: opt.udp_payload_size().max(DEFAULT_UDP_PAYLOAD_SIZE),
+            version: opt.version(),
+            dnssec_ok: opt.dnssec_ok(),
+        }
+    }
+}
+
+/// A parsed request together with what is known about the client that sent it
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub message: Message,
+    pub client: SocketAddr,
+    pub transport: Transport,
+    pub edns: Option<EdnsInfo>,
+}
+
+impl Request {
+    pub fn new(message: Message, client: SocketAddr, transport: Transport) -> Self {
+        let edns = message.opt().map(EdnsInfo::of);
+        Self {
+            message,
+            client,
+            transport,
+            edns,
+        }
+    }
+
+    /// The largest response the client accepts over its transport
+    pub fn max_response_size(&self) -> usize {
+        match self.transport {
+            Transport::Udp => self
+                .edns
+                .map_or(DEFAULT_UDP_PAYLOAD_SIZE, |edns| edns.udp_payload_size)
+                as usize,
+            Transport::Tcp => u16::MAX as usize,
+        }
+    }
+
+    /// An empty response echoing the ID, opcode, RD flag and question of the request. Clients
+    /// that sent EDNS get an OPT record back.
+    ///
+    /// [RFC6891 7: Transport Considerations](https://datatracker.ietf.org/doc/html/rfc6891#section-7)
+    pub fn response(&self, rcode: RCode) -> Message {
+        let mut response = Message::new();
+        response.header.id = self.message.header.id;
+        response.header.qr = true;
+        response.header.opcode = self.message.header.opcode;
+        response.header.rd = self.message.header.rd;
+        response.header.cd = self.message.header.cd;
+        response.header.rcode = rcode;
+        response.questions = self.message.questions.clone();
+        if let Some(edns) = self.edns {
+            let mut opt = OPT::new(EDNS_UDP_PAYLOAD_SIZE);
+            opt.set_dnssec_ok(edns.dnssec_ok);
+            response.additionals.push(Record::opt(opt));
+        }
+        response
+    }
+
+    /// Add an extended DNS error to the OPT record of a response, for clients that sent EDNS
+    pub fn extended_error(&self, response: &mut Message, info_code: u16) {
+        let Some(edns) = self.edns else {
+            return;
+        };
+        let mut opt = response.opt().cloned().unwrap_or_else(|| {
+            let mut opt = OPT::new(EDNS_UDP_PAYLOAD_SIZE);
+            opt.set_dnssec_ok(edns.dnssec_ok);
+            opt
+        });
+        opt.push_option(EdnsOption::new(
+            EXTENDED_DNS_ERROR,
+            info_code.to_be_bytes().to_vec(),
+        ));
+        response
+            .additionals
+            .retain(|record| !matches!(record.rdata(), RecordData::OPT(_)));
+        response.additionals.push(Record::opt(opt));
+    }
+}
+
+/// Encode a response to fit in `max_size` bytes. Responses that don't fit are sent with only
+/// the question and OPT record, and the TC flag set so the client retries over TCP.
+///
+/// [RFC2181 9: The TC (truncated) header bit](https://datatracker.ietf.org/doc/html/rfc2181#section-9)
+pub fn encode_response(response: &Message, max_size: usize) -> Option<Vec<u8>> {
+    let bytes = response.to_bytes().ok()?;
+    if bytes.len() <= max_size {
+        return Some(bytes);
+    }
+
+    let mut truncated = response.clone();
+    truncated.header.tc = true;
+    truncated.answers.clear();
+    truncated.authorities.clear();
+    truncated
+        .additionals
+        .retain(|record| matches!(record.rdata(), RecordData::OPT(_)));
+    truncated.to_bytes().ok()
+}
+
+/// Answers requests. Handlers are stacked, with each middleware wrapping the handler it passes
+/// requests on to.
+pub trait RequestHandler: Send + Sync + 'static {
+    /// The response to send back, or None to drop the request without a reply
+    fn handle(&self, request: Request) -> impl Future<Output = Option<Message>> + Send;
+}
+
+/// Handlers that hand themselves to background tasks are shared
+impl<H: RequestHandler> RequestHandler for Arc<H> {
+    fn handle(&self, request: Request) -> impl Future<Output = Option<Message>> + Send {
+        H::handle(self, request)
+    }
+}
+
+/// What the tests of the handlers share
+#[cfg(test)]
+pub mod fixtures {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use parsedns::{
+        header::RCode,
+        message::Message,
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            types::{RecordQType, RecordType},
+        },
+    };
+
+    use super::{Request, RequestHandler, Transport};
+
+    /// A request for the A records of `qname` wanting recursion, from a client on loopback
+    pub fn request(id: u16, qname: &str) -> Request {
+        let mut message = Message::new();
+        message.header.id = id;
+        message.header.rd = true;
+        message.questions.push(Question::new(
+            qname.parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, 40000));
+        Request::new(message, client, Transport::Udp)
+    }
+
+    /// Answers every request with NOERROR after `delay`, counting the requests that reach it
+    #[derive(Default)]
+    pub struct Counting {
+        delay: Duration,
+        received: AtomicUsize,
+    }
+
+    impl Counting {
+        pub fn new(delay: Duration) -> Self {
+            Self {
+                delay,
+                received: AtomicUsize::new(0),
+            }
+        }
+
+        pub fn received(&self) -> usize {
+            self.received.load(Ordering::Relaxed)
+        }
+    }
+
+    impl RequestHandler for Counting {
+        async fn handle(&self, request: Request) -> Option<Message> {
+            self.received.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(self.delay).await;
+            Some(request.response(RCode::NoError))
+        }
+    }
+}
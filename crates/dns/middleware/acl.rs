@@ -0,0 +1,168 @@
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use parsedns::{header::RCode, message::Message};
+
+use crate::dns::handler::{Request, RequestHandler};
+
+/// An address prefix in CIDR notation, such as `192.0.2.0/24` or `2001:db8::/32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Network {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl Network {
+    /// None when the prefix is longer than the address
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        (prefix_len <= max).then_some(Self { addr, prefix_len })
+    }
+
+    /// Whether the address is inside the network. IPv4-mapped IPv6 addresses match IPv4
+    /// networks.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let mask = |bits: u32| u128::MAX.checked_shl(bits - self.prefix_len as u32);
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = mask(32).unwrap_or(0) as u32;
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = mask(128).unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// An address without a prefix length is a network of that single address
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid network {:?}", s);
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (
+                addr.parse::<IpAddr>().map_err(|_| invalid())?,
+                Some(prefix_len.parse::<u8>().map_err(|_| invalid())?),
+            ),
+            None => (s.parse::<IpAddr>().map_err(|_| invalid())?, None),
+        };
+        let prefix_len = prefix_len.unwrap_or(match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        });
+        Self::new(addr, prefix_len).ok_or_else(invalid)
+    }
+}
+
+impl From<Ipv4Addr> for Network {
+    fn from(addr: Ipv4Addr) -> Self {
+        Self {
+            addr: addr.into(),
+            prefix_len: 32,
+        }
+    }
+}
+
+impl From<Ipv6Addr> for Network {
+    fn from(addr: Ipv6Addr) -> Self {
+        Self {
+            addr: addr.into(),
+            prefix_len: 128,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclAction {
+    Allow,
+    /// Answer with REFUSED
+    Refuse,
+    /// Send no reply at all
+    Drop,
+}
+
+/// Decides by client address whether a request is served. The first rule whose network holds
+/// the client applies, clients matching no rule get the default action.
+///
+/// ```text
+/// Recursive resolvers SHOULD restrict the set of clients they serve, to
+/// avoid being abused as open resolvers in reflection attacks.
+/// ```
+/// [RFC5358 4: Recommended Configuration](https://datatracker.ietf.org/doc/html/rfc5358#section-4)
+pub struct Acl<H> {
+    inner: H,
+    rules: Vec<(Network, AclAction)>,
+    default: AclAction,
+}
+
+impl<H> Acl<H> {
+    pub fn new(inner: H, rules: Vec<(Network, AclAction)>, default: AclAction) -> Self {
+        Self {
+            inner,
+            rules,
+            default,
+        }
+    }
+
+    pub fn action(&self, client: IpAddr) -> AclAction {
+        self.rules
+            .iter()
+            .find(|(network, _)| network.contains(client))
+            .map_or(self.default, |&(_, action)| action)
+    }
+}
+
+impl<H: RequestHandler> RequestHandler for Acl<H> {
+    async fn handle(&self, request: Request) -> Option<Message> {
+        match self.action(request.client.ip()) {
+            AclAction::Allow => self.inner.handle(request).await,
+            AclAction::Refuse => Some(request.response(RCode::Refused)),
+            AclAction::Drop => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::Network;
+
+    #[test]
+    fn test_network_contains() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        let network: Network = "192.168.0.0/16".parse().unwrap();
+        assert!(network.contains(ip("192.168.10.1")));
+        assert!(network.contains(ip("::ffff:192.168.10.1")));
+        assert!(!network.contains(ip("192.169.0.1")));
+        assert!(!network.contains(ip("2001:db8::1")));
+
+        let network: Network = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains(ip("2001:db8:ffff::1")));
+        assert!(!network.contains(ip("2001:db9::1")));
+
+        assert!("0.0.0.0/0"
+            .parse::<Network>()
+            .unwrap()
+            .contains(ip("203.0.113.9")));
+        assert!("::1".parse::<Network>().unwrap().contains(ip("::1")));
+        assert!("10.0.0.0/33".parse::<Network>().is_err());
+    }
+}
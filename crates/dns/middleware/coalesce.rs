@@ -0,0 +1,163 @@
+use dashmap::{mapref::entry::Entry, DashMap};
+use parsedns::{
+    message::Message,
+    rr::{class::RecordQClass, name::Name, types::RecordQType},
+};
+use tokio::sync::broadcast;
+
+use crate::dns::handler::{Request, RequestHandler};
+
+/// The question and the flags that change the answer to it. Whether the client sent EDNS decides
+/// if the answer carries an OPT record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RequestKey {
+    qname: Name,
+    qtype: RecordQType,
+    qclass: RecordQClass,
+    edns: bool,
+    dnssec_ok: bool,
+    cd: bool,
+}
+
+impl RequestKey {
+    fn of(request: &Request) -> Option<Self> {
+        let message = &request.message;
+        match message.questions.as_slice() {
+            [question] => Some(Self {
+                qname: question.qname.clone(),
+                qtype: question.qtype,
+                qclass: question.qclass,
+                edns: request.edns.is_some(),
+                dnssec_ok: request.edns.is_some_and(|edns| edns.dnssec_ok),
+                cd: message.header.cd,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Takes the question out of flight when the request handling it is dropped before it is
+/// answered, so the requests waiting on it don't wait forever
+struct InFlight<'a> {
+    in_flight: &'a DashMap<RequestKey, broadcast::Sender<Option<Message>>>,
+    key: Option<RequestKey>,
+}
+
+impl InFlight<'_> {
+    /// Hand the response to the requests waiting on it
+    fn complete(mut self, response: &Option<Message>) {
+        if let Some((_, waiting)) = self.key.take().and_then(|key| self.in_flight.remove(&key)) {
+            // No one waiting is fine
+            let _ = waiting.send(response.clone());
+        }
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.in_flight.remove(&key);
+        }
+    }
+}
+
+/// Passes on one request for each question at a time. Requests for a question that is already
+/// being answered wait for that answer instead, which each gets with its own ID, so a storm of
+/// queries for a name that isn't cached yet sends one query upstream.
+pub struct Coalesce<H> {
+    inner: H,
+    in_flight: DashMap<RequestKey, broadcast::Sender<Option<Message>>>,
+}
+
+impl<H> Coalesce<H> {
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            in_flight: DashMap::new(),
+        }
+    }
+}
+
+impl<H: RequestHandler> RequestHandler for Coalesce<H> {
+    async fn handle(&self, request: Request) -> Option<Message> {
+        let Some(key) = RequestKey::of(&request) else {
+            return self.inner.handle(request).await;
+        };
+
+        let waiting = match self.in_flight.entry(key.clone()) {
+            Entry::Occupied(entry) => Some(entry.get().subscribe()),
+            Entry::Vacant(entry) => {
+                entry.insert(broadcast::channel(1).0);
+                None
+            }
+        };
+        if let Some(mut waiting) = waiting {
+            return match waiting.recv().await {
+                Ok(response) => {
+                    let mut response = response?;
+                    response.header.id = request.message.header.id;
+                    response.header.rd = request.message.header.rd;
+                    response.questions = request.message.questions.clone();
+                    Some(response)
+                }
+                // The request in flight was dropped, this one goes on its own
+                Err(_) => self.inner.handle(request).await,
+            };
+        }
+
+        let in_flight = InFlight {
+            in_flight: &self.in_flight,
+            key: Some(key),
+        };
+        let response = self.inner.handle(request).await;
+        in_flight.complete(&response);
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::Coalesce;
+    use crate::dns::handler::{
+        fixtures::{request, Counting},
+        RequestHandler,
+    };
+
+    #[tokio::test]
+    async fn test_coalesce() {
+        let coalesce = Arc::new(Coalesce::new(Counting::new(Duration::from_millis(50))));
+        let mut checking_disabled = request(4, "example.com.");
+        checking_disabled.message.header.cd = true;
+        let requests = [
+            request(1, "example.com."),
+            request(2, "EXAMPLE.com."),
+            request(3, "example.com."),
+            checking_disabled,
+        ];
+        let handles: Vec<_> = requests
+            .into_iter()
+            .map(|request| {
+                let coalesce = coalesce.clone();
+                tokio::spawn(async move { coalesce.handle(request).await.unwrap() })
+            })
+            .collect();
+        let mut responses = Vec::new();
+        for handle in handles {
+            responses.push(handle.await.unwrap());
+        }
+
+        // One for the three the same, one for the CD flag
+        assert_eq!(coalesce.inner.received(), 2);
+        for (response, id) in responses.iter().zip(1..) {
+            assert_eq!(response.header.id, id);
+        }
+        assert_eq!(responses[1].questions[0].qname.to_string(), "EXAMPLE.com.");
+        assert!(coalesce.in_flight.is_empty());
+
+        // Nothing is kept once answered
+        coalesce.handle(request(5, "example.com.")).await;
+        assert_eq!(coalesce.inner.received(), 3);
+    }
+}
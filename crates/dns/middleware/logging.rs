@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+use parsedns::message::Message;
+
+use crate::dns::handler::{Request, RequestHandler};
+
+/// Prints every request with the outcome and the time taken to answer it
+pub struct Logging<H> {
+    inner: H,
+}
+
+impl<H> Logging<H> {
+    pub fn new(inner: H) -> Self {
+        Self { inner }
+    }
+}
+
+impl<H: RequestHandler> RequestHandler for Logging<H> {
+    async fn handle(&self, request: Request) -> Option<Message> {
+        let start = Instant::now();
+        let client = request.client;
+        let question = match request.message.questions.first() {
+            Some(question) => format!("{} {} {}", question.qname, question.qclass, question.qtype),
+            None => String::from("<no question>"),
+        };
+
+        let response = self.inner.handle(request).await;
+        match &response {
+            Some(response) => println!(
+                "{} {} {} in {:?}",
+                client,
+                question,
+                response.header.rcode,
+                start.elapsed()
+            ),
+            None => println!("{} {} dropped", client, question),
+        }
+        response
+    }
+}
@@ -0,0 +1,14 @@
+//! Layers that wrap a [`RequestHandler`](super::handler::RequestHandler) to add a feature
+//! without the handler knowing about it. Each layer takes the handler it passes requests on to,
+//! so a server is assembled from the inside out, with the layers a request meets first on the
+//! outside.
+
+mod acl;
+mod coalesce;
+mod logging;
+mod rate_limit;
+
+pub use acl::{Acl, AclAction, Network};
+pub use coalesce::Coalesce;
+pub use logging::Logging;
+pub use rate_limit::RateLimit;
@@ -0,0 +1,102 @@
+use std::{net::IpAddr, time::Instant};
+
+use dashmap::DashMap;
+use parsedns::message::Message;
+
+use crate::dns::handler::{Request, RequestHandler};
+
+/// Clients tracked before idle ones are forgotten
+const MAX_CLIENTS: usize = 1 << 16;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Limits the queries per second of each client address with a token bucket. Requests over the
+/// limit are dropped without a reply, so the server can't be used to amplify floods of spoofed
+/// queries.
+///
+/// [Response Rate Limiting in the Domain Name System](https://www.isc.org/docs/rrl-tech-note.pdf)
+pub struct RateLimit<H> {
+    inner: H,
+    rate: f64,
+    burst: f64,
+    buckets: DashMap<IpAddr, Bucket>,
+}
+
+impl<H> RateLimit<H> {
+    /// Allow `rate` queries per second on average, and bursts of up to `burst` queries
+    pub fn new(inner: H, rate: u32, burst: u32) -> Self {
+        Self {
+            inner,
+            rate: rate as f64,
+            burst: burst.max(1) as f64,
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Take a token from the bucket of the client, returning false when it is empty
+    fn allow(&self, client: IpAddr, now: Instant) -> bool {
+        if self.buckets.len() >= MAX_CLIENTS {
+            // A bucket idle long enough to refill is the same as no bucket
+            let refill = self.burst / self.rate;
+            self.buckets
+                .retain(|_, bucket| now.duration_since(bucket.updated).as_secs_f64() < refill);
+        }
+
+        let mut bucket = self.buckets.entry(client).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<H: RequestHandler> RequestHandler for RateLimit<H> {
+    async fn handle(&self, request: Request) -> Option<Message> {
+        if self.allow(request.client.ip().to_canonical(), Instant::now()) {
+            self.inner.handle(request).await
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    use super::RateLimit;
+
+    #[test]
+    fn test_token_bucket() {
+        let limit = RateLimit::new((), 10, 3);
+        let (a, b) = (
+            IpAddr::from(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::from(Ipv4Addr::new(192, 0, 2, 2)),
+        );
+        let now = Instant::now();
+
+        assert!((0..3).all(|_| limit.allow(a, now)));
+        assert!(!limit.allow(a, now));
+        assert!(limit.allow(b, now));
+
+        // A token comes back every 100ms
+        assert!(limit.allow(a, now + Duration::from_millis(100)));
+        assert!(!limit.allow(a, now + Duration::from_millis(150)));
+        assert!((0..3).all(|_| limit.allow(a, now + Duration::from_secs(10))));
+    }
+}
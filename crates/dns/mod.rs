@@ -1,5 +1,11 @@
 pub mod authority;
 pub mod context;
+pub mod forward_table;
+pub mod forwarder;
+pub mod handler;
+pub mod middleware;
 pub mod packet;
-pub mod parser;
 pub mod resolver;
+pub mod root_hints;
+pub mod transaction;
+pub mod upstream;
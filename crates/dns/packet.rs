@@ -1,72 +1,71 @@
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 
-use dns_message_parser::{rr::NS, DomainName, DomainNameError};
-pub use dns_message_parser::{
-    rr::{A, RR},
-    Dns, Flags, Opcode, RCode,
+use parsedns::{
+    message::Message,
+    rr::{
+        data::RecordData,
+        name::Name,
+        types::{RecordQType, RecordType},
+    },
 };
 
-pub fn new_dns_packet() -> Dns {
-    // https://datatracker.ietf.org/doc/html/rfc1035#section-4.1.1
-    // https://datatracker.ietf.org/doc/html/rfc2535 for AD & CD
-    let flags = Flags { opcode: Opcode::Query, // Operation code
-        qr: false,             // Query Response
-        rd: false,             // Recursion Desired
-        ra: false,             // Recursion Available
-        tc: false,             // Truncation
-        aa: false,             // Authoritative Answer
-        ad: false,             // Authed Data
-        cd: false,             // Checking Disabled
-        rcode: RCode::NoError,
-    };
-
-    Dns {
-        id: 0,
-        flags,
-        questions: Vec::new(),
-        answers: Vec::new(),
-        authorities: Vec::new(),
-        additionals: Vec::new(),
-    }
-}
-
-pub fn get_random_a(packet: &Dns) -> Option<Ipv4Addr> {
-    packet.answers.iter().find_map(|record| match record {
-        RR::A(A { ipv4_addr, .. }) => Some(*ipv4_addr),
-        _ => None, })
-}
-
+/// The NS records of the authority section for a zone containing `qname`, as (zone, name server)
 pub fn get_ns<'a>(
-    packet: &'a Dns,
-    qname: &'a str,
-) -> impl Iterator<Item = (&'a DomainName, &'a DomainName)> {
+    packet: &'a Message,
+    qname: &'a Name,
+) -> impl Iterator<Item = (&'a Name, &'a Name)> {
     packet
         .authorities
         .iter()
-        .filter_map(|record| match record {
-            RR::NS(NS {
-                domain_name,
-                ns_d_name,
-                ..
-            }) => Some((domain_name, ns_d_name)),
+        .filter_map(|record| match record.rdata() {
+            RecordData::NS(ns) => Some((record.name(), ns.nsdname())),
             _ => None,
         })
-        .filter(move |(domain, _)| qname.ends_with(&domain.to_string()))
+        .filter(move |(domain, _)| qname.is_subdomain_of(domain))
 }
 
-pub fn get_resolved_ns<'a>(packet: &Dns, qname: &str) -> Option<Ipv4Addr> {
-    get_ns(packet, qname).find_map(|(_, host)| {
-        packet.additionals.iter().find_map(|record| match record {
-            RR::A(A {
-                domain_name,
-                ipv4_addr,
-                ..
-            }) if domain_name == host => Some(*ipv4_addr),
+/// The addresses of `host` given as glue in the additional section
+pub fn get_glue<'a>(packet: &'a Message, host: &'a Name) -> impl Iterator<Item = IpAddr> + 'a {
+    packet
+        .additionals
+        .iter()
+        .filter(move |record| record.name() == host)
+        .filter_map(|record| match record.rdata() {
+            RecordData::A(a) => Some(a.address().into()),
+            RecordData::AAAA(aaaa) => Some(aaaa.address().into()),
             _ => None,
         })
-    })
 }
 
-pub fn get_unresolved_ns<'a>(packet: &'a Dns, qname: &'a str) -> Option<&'a DomainName> {
-    get_ns(packet, qname).next().map(|(_, host)| host)
+/// The name the CNAMEs in the answers lead to from `qname` when they don't end in records of
+/// `qtype`, which is where the resolution carries on
+pub fn unanswered_cname(response: &Message, qname: &Name, qtype: RecordQType) -> Option<Name> {
+    let mut name = qname;
+    // Each CNAME in the answers can be followed once
+    for _ in 0..=response.answers.len() {
+        let at_name = || {
+            response
+                .answers
+                .iter()
+                .filter(|record| record.name() == name)
+        };
+        if at_name().any(|record| answers(qtype, record.rtype())) {
+            return None;
+        }
+        match at_name().find_map(|record| match record.rdata() {
+            RecordData::CNAME(cname) => Some(cname.cname()),
+            _ => None,
+        }) {
+            Some(target) => name = target,
+            None => break,
+        }
+    }
+    (name != qname).then(|| name.clone())
+}
+
+fn answers(qtype: RecordQType, rtype: RecordType) -> bool {
+    match qtype {
+        RecordQType::RecordType(qtype) => qtype == rtype,
+        _ => true,
+    }
 }
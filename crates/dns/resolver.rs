@@ -1,2 +1,1464 @@
+use std::{
+    fmt,
+    future::Future,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, Instant},
+};
 
+use dashmap::DashSet;
+use parsedns::{
+    header::RCode,
+    message::{Message, Section},
+    question::Question,
+    rr::{
+        class::{RecordClass, RecordQClass},
+        data::RecordData,
+        name::Name,
+        rdata::OPT,
+        record::Record,
+        rrset::RRsetKey,
+        types::{RecordQType, RecordType},
+    },
+};
+use rand::seq::SliceRandom;
+use tokio::{net::UdpSocket, sync::Mutex, task::JoinHandle, time::timeout};
 
+use super::{
+    context::ServerContext,
+    handler::{Request, RequestHandler, EDE_STALE_ANSWER, EDNS_UDP_PAYLOAD_SIZE},
+    packet::{get_glue, get_ns, unanswered_cname},
+    root_hints::RootHints,
+    transaction::Transactions,
+};
+
+/// How long to carry on with the old root servers when priming fails
+const PRIMING_RETRY: Duration = Duration::from_secs(60);
+/// Servers from the root hints asked before priming counts as failed
+const PRIMING_ATTEMPTS: usize = 4;
+/// How often a question answered from stale data is tried again in the background
+///
+/// [RFC8767 5: Example Method](https://datatracker.ietf.org/doc/html/rfc8767#section-5)
+const STALE_REFRESH: Duration = Duration::from_secs(30);
+
+/// Limits on the work done for one query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolverConfig {
+    /// Port the name servers listen on
+    pub port: u16,
+    /// Time given to a name server to answer
+    pub timeout: Duration,
+    /// Queries sent for one resolution, counting the lookups of name server addresses
+    pub max_queries: u32,
+    /// CNAMEs followed before giving up
+    pub max_cname_chain: u32,
+    /// Only tell the servers above the zone of a name the labels they need to see
+    pub qname_minimisation: bool,
+    /// Mark answers served from stale data with the Stale Answer extended DNS error
+    pub stale_answer_ede: bool,
+    /// Time a client waits for an answer before getting stale data, while the resolution
+    /// carries on in the background
+    ///
+    /// [RFC8767 5: Example Method](https://datatracker.ietf.org/doc/html/rfc8767#section-5)
+    pub client_response_timer: Duration,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        Self {
+            port: 53,
+            timeout: Duration::from_millis(800),
+            max_queries: 64,
+            max_cname_chain: 8,
+            qname_minimisation: true,
+            stale_answer_ede: true,
+            client_response_timer: Duration::from_millis(1800),
+        }
+    }
+}
+
+/// Why a name could not be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// None of the name servers of the zone gave an answer or a referral
+    NoAnswer(Name),
+    /// The query budget ran out
+    TooManyQueries,
+    /// The CNAME chain from the name is too long or loops
+    CnameChain(Name),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAnswer(zone) => write!(f, "no usable answer from the servers of {}", zone),
+            Self::TooManyQueries => write!(f, "too many queries"),
+            Self::CnameChain(name) => write!(f, "CNAME chain from {} too long", name),
+        }
+    }
+}
+
+/// The root servers in use and when to prime again
+struct Roots {
+    addresses: Vec<IpAddr>,
+    /// None until primed
+    expires: Option<Instant>,
+}
+
+/// Where asking the servers of a zone led
+enum Step {
+    Answer(Message),
+    /// Servers of a zone further down
+    Referral(Name, Vec<IpAddr>),
+}
+
+type Resolution<'a> = Pin<Box<dyn Future<Output = Result<Message, ResolveError>> + Send + 'a>>;
+
+/// Answers queries by itself, starting at the root servers and following referrals down to the
+/// servers authoritative for the name.
+///
+/// ```text
+/// 3. Send them queries until one returns a response.
+///
+/// 4. Analyze the response, either:
+///
+///      a. if the response answers the question or contains a name
+///         error, cache the data as well as returning it back to
+///         the client.
+///
+///      b. if the response contains a better delegation to other
+///         servers, cache the delegation information, and go to
+///         step 2.
+///
+///      c. if the response shows a CNAME and that is not the
+///         answer itself, cache the CNAME, change the SNAME to the
+///         canonical name in the CNAME RR and go to step 1.
+///
+///      d. if the response shows a servers failure or other
+///         bizarre contents, delete the server from the SLIST and
+///         go back to step 3.
+/// ```
+/// [RFC1034 5.3.3: Algorithm](https://datatracker.ietf.org/doc/html/rfc1034#section-5.3.3)
+///
+/// The root servers come from priming with the root hints, which is repeated when the NS records
+/// of the root expire. Responses are cached, and a resolution starts at the closest zone with
+/// cached name servers. When the servers can't be reached or are slow to answer, answers that
+/// expired within the stale window of the cache are served while they are retried in the
+/// background, and popular answers are refreshed in the background before they expire.
+///
+/// [RFC8767 5: Example Method](https://datatracker.ietf.org/doc/html/rfc8767#section-5)
+pub struct Resolver {
+    /// For the background tasks refreshing stale answers
+    me: Weak<Self>,
+    hints: RootHints,
+    roots: RwLock<Roots>,
+    priming: Mutex<()>,
+    config: ResolverConfig,
+    /// False on hosts without IPv6
+    ipv6: bool,
+    transactions: Transactions,
+    context: Arc<ServerContext>,
+    /// Questions answered from stale data that are being tried again
+    refreshing: DashSet<Question>,
+}
+
+impl Resolver {
+    /// Check which address families there are to reach name servers with
+    pub async fn new(
+        hints: RootHints,
+        config: ResolverConfig,
+        context: Arc<ServerContext>,
+    ) -> io::Result<Arc<Self>> {
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        let ipv6 = UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)).await.is_ok();
+
+        let resolver = Arc::new_cyclic(|me| Self {
+            me: me.clone(),
+            roots: RwLock::new(Roots {
+                addresses: hints.addresses(),
+                expires: None,
+            }),
+            hints,
+            priming: Mutex::new(()),
+            config,
+            ipv6,
+            transactions: Transactions::new(),
+            context,
+            refreshing: DashSet::new(),
+        });
+        if resolver.reachable(resolver.hints.addresses()).is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no reachable root servers to start from",
+            ));
+        }
+        Ok(resolver)
+    }
+
+    /// The root servers queries start at
+    pub fn roots(&self) -> Vec<IpAddr> {
+        self.roots.read().unwrap().addresses.clone()
+    }
+
+    /// Ask the servers from the root hints for the current root servers, returning how long the
+    /// answer is good for. Until priming works the root hints are used.
+    ///
+    /// ```text
+    /// The priming query MUST use a QTYPE of "NS" and QNAME of "." (the root
+    /// name).  ...  The recursive resolver SHOULD use EDNS0
+    /// [RFC6891] for priming queries and SHOULD announce and handle a
+    /// reassembly size of at least 1024 octets [RFC3226].  ...
+    /// The priming query SHOULD be sent with the RD bit cleared.
+    /// ```
+    /// [RFC8109 3: Priming Queries](https://datatracker.ietf.org/doc/html/rfc8109#section-3)
+    pub async fn prime(&self) -> Result<Duration, ResolveError> {
+        let question = Question::new(
+            Name::root(),
+            RecordQType::RecordType(RecordType::NS),
+            RecordQClass::RecordClass(RecordClass::IN),
+        );
+        // Priming queries are spread over the hints rather than always hitting the first one
+        let mut servers = self.reachable(self.hints.addresses());
+        servers.shuffle(&mut rand::thread_rng());
+        servers.truncate(PRIMING_ATTEMPTS);
+
+        for server in servers {
+            let response = match self.query(server, &question).await {
+                Some(response) if response.header.rcode == RCode::NoError => response,
+                _ => continue,
+            };
+            let mut ttl = None;
+            let mut addresses = Vec::new();
+            for record in &response.answers {
+                if let (true, RecordData::NS(ns)) = (record.name().is_root(), record.rdata()) {
+                    let record_ttl = Duration::from_secs(u32::from(record.ttl()).into());
+                    ttl = Some(ttl.map_or(record_ttl, |ttl: Duration| ttl.min(record_ttl)));
+                    addresses.extend(get_glue(&response, ns.nsdname()));
+                }
+            }
+            let addresses = self.reachable(addresses);
+            let ttl = match ttl {
+                Some(ttl) if !addresses.is_empty() => ttl,
+                // Without addresses for the root servers the answer is of no use
+                _ => continue,
+            };
+
+            *self.roots.write().unwrap() = Roots {
+                addresses,
+                expires: Some(Instant::now() + ttl),
+            };
+            return Ok(ttl);
+        }
+
+        self.roots.write().unwrap().expires = Some(Instant::now() + PRIMING_RETRY);
+        Err(ResolveError::NoAnswer(Name::root()))
+    }
+
+    /// Prime if the root servers have never been primed or their NS records have expired. Only
+    /// one query primes, the others carry on with the root servers there are.
+    async fn prime_if_expired(&self) {
+        let expired = |roots: &RwLock<Roots>| {
+            roots
+                .read()
+                .unwrap()
+                .expires
+                .is_none_or(|expires| expires <= Instant::now())
+        };
+        if !expired(&self.roots) {
+            return;
+        }
+        if let Ok(_priming) = self.priming.try_lock() {
+            if expired(&self.roots) {
+                if let Err(e) = self.prime().await {
+                    println!("Priming failed: {}", e);
+                }
+            }
+        }
+    }
+
+    /// The final response for `question`, with the CNAMEs followed on the way in the answers
+    pub async fn resolve(&self, question: &Question) -> Result<Message, ResolveError> {
+        self.prime_if_expired().await;
+        let mut budget = self.config.max_queries;
+        self.resolve_with(question.clone(), &mut budget).await
+    }
+
+    /// The answer to `question`, or the stale answer from the cache if it can't be resolved or
+    /// takes longer than the client response timer. The resolution carries on in the background
+    /// after the timer, and keeps being retried if it fails. Questions that are already being
+    /// refreshed in the background are answered stale straight away. Returns whether the answer
+    /// is stale.
+    async fn resolve_or_stale(&self, question: &Question) -> Result<(Message, bool), ResolveError> {
+        if self.refreshing.contains(question) {
+            if let Some(stale) = self.cached_chain(question, true).await {
+                return Ok((stale, true));
+            }
+        }
+        let Some(resolver) = self.me.upgrade() else {
+            return self
+                .resolve(question)
+                .await
+                .map(|response| (response, false));
+        };
+
+        let background = question.clone();
+        let mut resolution = tokio::spawn(async move { resolver.resolve(&background).await });
+        let resolved = match timeout(self.config.client_response_timer, &mut resolution).await {
+            Ok(resolved) => resolved,
+            Err(_) => match self.cached_chain(question, true).await {
+                Some(stale) => {
+                    println!("Serving stale {} while it resolves", question.qname);
+                    self.refresh_after(question.clone(), resolution);
+                    return Ok((stale, true));
+                }
+                None => resolution.await,
+            },
+        };
+        let resolved =
+            resolved.unwrap_or_else(|_| Err(ResolveError::NoAnswer(question.qname.clone())));
+
+        match resolved {
+            Ok(response) => Ok((response, false)),
+            Err(e) => match self.cached_chain(question, true).await {
+                Some(stale) => {
+                    println!("Serving stale {} after: {}", question.qname, e);
+                    self.refresh(question.clone());
+                    Ok((stale, true))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Retry a question answered stale once its resolution running in the background fails
+    fn refresh_after(
+        &self,
+        question: Question,
+        resolution: JoinHandle<Result<Message, ResolveError>>,
+    ) {
+        let Some(resolver) = self.me.upgrade() else {
+            return;
+        };
+        tokio::spawn(async move {
+            if !matches!(resolution.await, Ok(Ok(_))) {
+                resolver.refresh(question);
+            }
+        });
+    }
+
+    /// The answer from the cache, following CNAMEs, with stale data included when asked for
+    async fn cached_chain(&self, question: &Question, stale: bool) -> Option<Message> {
+        let response = self
+            .context
+            .cache
+            .answer_chain(question, stale, self.config.max_cname_chain)
+            .await?;
+        if !stale {
+            self.prefetch_due(&response, question).await;
+        }
+        Some(response)
+    }
+
+    /// Keep trying to resolve a question answered stale until it resolves or there is no stale
+    /// data left for it
+    fn refresh(&self, question: Question) {
+        let Some(resolver) = self.me.upgrade() else {
+            return;
+        };
+        if !self.refreshing.insert(question.clone()) {
+            return;
+        }
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(STALE_REFRESH).await;
+                if resolver.resolve(&question).await.is_ok()
+                    || resolver.cached_chain(&question, true).await.is_none()
+                {
+                    break;
+                }
+            }
+            resolver.refreshing.remove(&question);
+        });
+    }
+
+    /// Resolve a popular question again before its answer expires from the cache, so it is
+    /// still there for the queries after
+    fn prefetch(&self, question: Question) {
+        let Some(resolver) = self.me.upgrade() else {
+            return;
+        };
+        tokio::spawn(async move {
+            let mut budget = resolver.config.max_queries;
+            if let Err(e) = resolver.iterate(&question, &mut budget).await {
+                println!("Failed to prefetch {}: {}", question.qname, e);
+            }
+        });
+    }
+
+    /// The types of the address records of the families the host can reach
+    fn address_types(&self) -> impl Iterator<Item = RecordType> {
+        [RecordType::A]
+            .into_iter()
+            .chain(self.ipv6.then_some(RecordType::AAAA))
+    }
+
+    /// The addresses of the families the host can reach
+    fn reachable(&self, mut addresses: Vec<IpAddr>) -> Vec<IpAddr> {
+        addresses.retain(|address| address.is_ipv4() || self.ipv6);
+        addresses
+    }
+
+    /// Boxed as looking up the address of a name server is a resolution of its own
+    fn resolve_with<'a>(&'a self, question: Question, budget: &'a mut u32) -> Resolution<'a> {
+        Box::pin(async move {
+            let mut answers = Vec::new();
+            let mut seen = vec![question.qname.clone()];
+            for _ in 0..=self.config.max_cname_chain {
+                let qname = seen.last().unwrap().clone();
+                let lookup = Question::new(qname.clone(), question.qtype, question.qclass);
+                let mut response = match self.cached(&lookup, false).await {
+                    Some(response) => response,
+                    None => self.iterate(&lookup, budget).await?,
+                };
+
+                let target = unanswered_cname(&response, &qname, question.qtype);
+                answers.append(&mut response.answers);
+                match target {
+                    Some(target) if response.header.rcode == RCode::NoError => {
+                        if seen.contains(&target) {
+                            return Err(ResolveError::CnameChain(question.qname));
+                        }
+                        seen.push(target);
+                    }
+                    _ => {
+                        response.answers = answers;
+                        return Ok(response);
+                    }
+                }
+            }
+            Err(ResolveError::CnameChain(question.qname))
+        })
+    }
+
+    /// An answer from the cache, with the records of the question or a CNAME to follow, or a
+    /// denial. Popular answers close to expiring are prefetched.
+    async fn cached(&self, question: &Question, stale: bool) -> Option<Message> {
+        let response = self.context.cache.answer(question, stale).await?;
+        if !stale {
+            self.prefetch_due(&response, question).await;
+        }
+        Some(response)
+    }
+
+    /// Prefetch the questions along the answers from the cache to `question` that are due for it
+    async fn prefetch_due(&self, response: &Message, question: &Question) {
+        for rrset in response.rrsets(Section::Answer) {
+            if self.context.cache.take_prefetch(rrset.key()).await {
+                self.prefetch(Question::new(
+                    rrset.name().clone(),
+                    question.qtype,
+                    question.qclass,
+                ));
+            }
+        }
+    }
+
+    /// A referral to the deepest zone above the name of `question` with cached name servers, for
+    /// queries that don't want recursion and have no answer in the cache
+    async fn cached_referral(&self, question: &Question) -> Message {
+        let mut response = Message::new();
+        response.header.qr = true;
+        response.questions.push(question.clone());
+        for labels in (0..=question.qname.label_count()).rev() {
+            let zone = question.qname.suffix(labels);
+            let key = RRsetKey::new(zone, RecordType::NS, RecordClass::IN);
+            if let Some(ns) = self.context.cache.get(&key).await {
+                response.authorities.extend(ns.records());
+                break;
+            }
+        }
+        response
+    }
+
+    /// The deepest zone above `qname` whose name servers and their addresses are cached, or the
+    /// root servers
+    async fn closest_servers(&self, qname: &Name) -> (Name, Vec<IpAddr>) {
+        let cache = &self.context.cache;
+        for labels in (1..=qname.label_count()).rev() {
+            let zone = qname.suffix(labels);
+            let key = RRsetKey::new(zone.clone(), RecordType::NS, RecordClass::IN);
+            let Some(ns) = cache.get(&key).await else {
+                continue;
+            };
+
+            let mut addresses = Vec::new();
+            for host in ns.rdatas().iter().filter_map(|rdata| match rdata {
+                RecordData::NS(ns) => Some(ns.nsdname()),
+                _ => None,
+            }) {
+                for rtype in [RecordType::A, RecordType::AAAA] {
+                    let key = RRsetKey::new(host.clone(), rtype, RecordClass::IN);
+                    if let Some(rrset) = cache.get(&key).await {
+                        addresses.extend(rrset.rdatas().iter().filter_map(|rdata| match rdata {
+                            RecordData::A(a) => Some(IpAddr::from(a.address())),
+                            RecordData::AAAA(aaaa) => Some(IpAddr::from(aaaa.address())),
+                            _ => None,
+                        }));
+                    }
+                }
+            }
+            let addresses = self.reachable(addresses);
+            if !addresses.is_empty() {
+                return (zone, addresses);
+            }
+        }
+        (Name::root(), self.roots())
+    }
+
+    /// Follow referrals from the root until a server answers `question` or says the name
+    /// doesn't exist. With QNAME minimisation the servers above the zone of the name only see as
+    /// much of it as they need to refer onwards. Referrals move on to the next zone, and answers
+    /// without data mean an empty non-terminal or a name in the same zone, so the next query has
+    /// more labels. Some servers deny names that have names below them or break on minimised
+    /// queries, so NXDOMAIN or no answer at all falls back to the full name, as do aliases.
+    ///
+    /// [RFC9156 3: Algorithm to Perform QNAME Minimisation](https://datatracker.ietf.org/doc/html/rfc9156#section-3)
+    async fn iterate(
+        &self,
+        question: &Question,
+        budget: &mut u32,
+    ) -> Result<Message, ResolveError> {
+        let total = question.qname.label_count();
+        let (mut zone, mut servers) = self.closest_servers(&question.qname).await;
+        let mut minimise = self.config.qname_minimisation;
+        // Labels of the name already known to the servers of the zone, and minimised queries sent
+        let (mut known, mut minimised) = (0, 0);
+        loop {
+            let labels = if minimise {
+                minimised_labels(known.max(zone.label_count()), minimised, total)
+            } else {
+                total
+            };
+            let lookup = if labels < total {
+                minimised += 1;
+                // A rather than NS, which some servers get wrong
+                Question::new(
+                    question.qname.suffix(labels),
+                    RecordQType::RecordType(RecordType::A),
+                    question.qclass,
+                )
+            } else {
+                question.clone()
+            };
+
+            match self.ask(&zone, &servers, &lookup, budget).await? {
+                Some(Step::Referral(cut, next)) => {
+                    zone = cut;
+                    servers = next;
+                    known = labels;
+                }
+                Some(Step::Answer(response)) if labels == total => {
+                    self.context.cache.insert_negative(&response, &zone).await;
+                    return Ok(response);
+                }
+                Some(Step::Answer(response)) => {
+                    let alias = response.answers.iter().any(|record| {
+                        *record.name() == lookup.qname && record.rtype() == RecordType::CNAME
+                    });
+                    if response.header.rcode == RCode::NXDomain || alias {
+                        // Denials of minimised names aren't trusted, and aliases have to be
+                        // followed from the full name
+                        minimise = false;
+                    } else {
+                        // An empty non-terminal or a name in the same zone
+                        known = labels;
+                    }
+                }
+                // The servers may only be broken for minimised queries
+                None if labels < total => minimise = false,
+                None => return Err(ResolveError::NoAnswer(zone)),
+            }
+        }
+    }
+
+    /// Ask the servers of `zone` for `question` until one of them answers it or refers further
+    /// down. None when none of them does.
+    async fn ask(
+        &self,
+        zone: &Name,
+        servers: &[IpAddr],
+        question: &Question,
+        budget: &mut u32,
+    ) -> Result<Option<Step>, ResolveError> {
+        for &server in servers {
+            if *budget == 0 {
+                return Err(ResolveError::TooManyQueries);
+            }
+            *budget -= 1;
+
+            let response = match self.query(server, question).await {
+                Some(response) => response,
+                None => continue,
+            };
+            match response.header.rcode {
+                RCode::NoError | RCode::NXDomain => {}
+                // Failing or refusing, try the next server
+                _ => continue,
+            }
+            self.context.cache.insert_response(&response, zone).await;
+            if response.header.rcode == RCode::NXDomain
+                || response.header.aa
+                || !response.answers.is_empty()
+            {
+                return Ok(Some(Step::Answer(response)));
+            }
+            if let Some((cut, next)) = self.referral(&response, zone, question, budget).await? {
+                return Ok(Some(Step::Referral(cut, next)));
+            }
+            // Neither an answer nor a referral, the server is lame
+        }
+        Ok(None)
+    }
+
+    /// The zone a response delegates to below `zone`, and the addresses of its name servers.
+    /// Glue is only used when the server sending it is authoritative for the name server, other
+    /// addresses are looked up, AAAA records too when there is IPv6. None when the referral
+    /// doesn't lead further down or to any name server that can be reached.
+    async fn referral(
+        &self,
+        response: &Message,
+        zone: &Name,
+        question: &Question,
+        budget: &mut u32,
+    ) -> Result<Option<(Name, Vec<IpAddr>)>, ResolveError> {
+        // Referrals have to lead further down or they could go round in circles
+        let cut = match get_ns(response, &question.qname)
+            .map(|(cut, _)| cut)
+            .find(|cut| cut.is_subdomain_of(zone) && cut.label_count() > zone.label_count())
+        {
+            Some(cut) => cut.clone(),
+            None => return Ok(None),
+        };
+        let hosts: Vec<Name> = get_ns(response, &question.qname)
+            .filter(|(name, _)| **name == cut)
+            .map(|(_, host)| host.clone())
+            .collect();
+
+        let glue: Vec<IpAddr> = hosts
+            .iter()
+            .filter(|host| host.is_subdomain_of(zone))
+            .flat_map(|host| get_glue(response, host))
+            .collect();
+        let glue = self.reachable(glue);
+        if !glue.is_empty() {
+            return Ok(Some((cut, glue)));
+        }
+
+        for host in hosts {
+            for rtype in self.address_types() {
+                let lookup = Question::new(
+                    host.clone(),
+                    RecordQType::RecordType(rtype),
+                    RecordQClass::RecordClass(RecordClass::IN),
+                );
+                match self.resolve_with(lookup, budget).await {
+                    Ok(resolved) => {
+                        let addresses: Vec<IpAddr> = resolved
+                            .answers
+                            .iter()
+                            .filter_map(|record| match record.rdata() {
+                                RecordData::A(a) => Some(a.address().into()),
+                                RecordData::AAAA(aaaa) => Some(aaaa.address().into()),
+                                _ => None,
+                            })
+                            .collect();
+                        if !addresses.is_empty() {
+                            return Ok(Some((cut, addresses)));
+                        }
+                    }
+                    Err(ResolveError::TooManyQueries) => return Err(ResolveError::TooManyQueries),
+                    Err(_) => continue,
+                }
+            }
+        }
+        // None of the name servers has an address, the referral is as good as lame
+        Ok(None)
+    }
+
+    /// Ask one name server, without recursion. Truncated responses are of no use, there is no
+    /// TCP to ask again over, so the next server is asked instead.
+    ///
+    /// ```text
+    /// Where TC is set, the partial RRSet that would not completely fit may
+    /// be left in the response.  When a DNS client receives a reply with TC
+    /// set, it should ignore that response, and query again, using a
+    /// mechanism, such as a TCP connection, that will permit larger replies.
+    /// ```
+    /// [RFC2181 9: The TC (truncated) header bit](https://datatracker.ietf.org/doc/html/rfc2181#section-9)
+    async fn query(&self, server: IpAddr, question: &Question) -> Option<Message> {
+        if server.is_ipv6() && !self.ipv6 {
+            return None;
+        }
+
+        let mut lookup = Message::new();
+        lookup.header.rd = false;
+        lookup.questions.push(question.clone());
+        lookup
+            .additionals
+            .push(Record::opt(OPT::new(EDNS_UDP_PAYLOAD_SIZE)));
+
+        // Queries of the resolver itself are not asked on behalf of a client
+        let resolver = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0));
+        let server = SocketAddr::new(server, self.config.port);
+        let response = self
+            .transactions
+            .exchange(resolver, lookup, server, self.config.timeout)
+            .await
+            .ok()?;
+        if response.header.tc {
+            println!("Truncated response from {}", server);
+            return None;
+        }
+        Some(response)
+    }
+}
+
+/// How many labels of a name of `total` labels the next minimised query has, after `known`
+/// labels and `sent` minimised queries. The first labels are added one at a time and the rest in
+/// bigger steps, so names with many labels take at most `MAX_MINIMISE_COUNT` queries.
+///
+/// [RFC9156 2.3: Limit Number of Queries](https://datatracker.ietf.org/doc/html/rfc9156#section-2.3)
+fn minimised_labels(known: usize, sent: usize, total: usize) -> usize {
+    const MAX_MINIMISE_COUNT: usize = 10;
+    const MINIMISE_ONE_LAB: usize = 4;
+
+    if sent < MINIMISE_ONE_LAB {
+        return (known + 1).min(total);
+    }
+    let queries_left = MAX_MINIMISE_COUNT.saturating_sub(sent);
+    if queries_left <= 1 {
+        return total;
+    }
+    let remaining = total.saturating_sub(known);
+    (known + remaining.div_ceil(queries_left)).min(total)
+}
+
+impl RequestHandler for Resolver {
+    async fn handle(&self, request: Request) -> Option<Message> {
+        let question = match request.message.questions.as_slice() {
+            [question] => question.clone(),
+            _ => return Some(request.response(RCode::FormErr)),
+        };
+
+        // Without recursion desired the answer comes from the cache alone (RFC1034 4.3.1)
+        let cached = self.cached_chain(&question, false).await;
+        self.context.cache.count_query(cached.is_some());
+        let resolved = match cached {
+            Some(cached) => Ok((cached, false)),
+            None if request.message.header.rd => self.resolve_or_stale(&question).await,
+            None => Ok((self.cached_referral(&question).await, false)),
+        };
+        let mut response = match resolved {
+            Ok((resolved, stale)) => {
+                let mut response = request.response(resolved.header.rcode);
+                response.answers = resolved.answers;
+                response.authorities = resolved.authorities;
+                if stale && self.config.stale_answer_ede {
+                    request.extended_error(&mut response, EDE_STALE_ANSWER);
+                }
+                response
+            }
+            Err(e) => {
+                println!("Failed to resolve {}: {}", question.qname, e);
+                request.response(RCode::ServFail)
+            }
+        };
+        response.header.ra = true;
+        Some(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use parsedns::{
+        header::RCode,
+        message::Message,
+        rr::{
+            class::RecordClass,
+            data::RecordData,
+            name::Name,
+            rdata::OPT,
+            record::Record,
+            types::{RecordQType, RecordType},
+        },
+        utils::TTL,
+    };
+    use tokio::net::UdpSocket;
+
+    use super::{minimised_labels, Resolver, ResolverConfig};
+    use crate::dns::{
+        context::{CacheConfig, ServerContext, STALE_TTL},
+        handler::{fixtures::request, Request, RequestHandler, Transport},
+        root_hints::RootHints,
+    };
+
+    fn record(s: &str) -> Record {
+        let mut fields = s.splitn(3, ' ');
+        let (name, rtype, rdata) = (
+            fields.next().unwrap(),
+            fields.next().unwrap(),
+            fields.next().unwrap(),
+        );
+        let rtype: RecordType = rtype.parse().unwrap();
+        Record::new(
+            name.parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(3600),
+            RecordData::from_presentation(rtype, RecordClass::IN, rdata).unwrap(),
+        )
+    }
+
+    /// How a server authoritative for `zones` holding `records` answers. NS records outside the
+    /// apex of its zones are delegations. Denials come with the SOA of the zone if it has one.
+    /// Broken servers deny empty non-terminals.
+    fn answer(zones: &[Name], records: &[Record], broken: bool, query: &Message) -> Message {
+        let question = &query.questions[0];
+        let mut response = query.clone();
+        response.header.qr = true;
+        response.additionals.clear();
+
+        let delegation = records.iter().find(|record| {
+            record.rtype() == RecordType::NS
+                && !zones.contains(record.name())
+                && question.qname.is_subdomain_of(record.name())
+        });
+        if let Some(delegation) = delegation {
+            let cut = delegation.name();
+            for ns in records
+                .iter()
+                .filter(|r| r.name() == cut && r.rtype() == RecordType::NS)
+            {
+                let RecordData::NS(host) = ns.rdata() else {
+                    unreachable!()
+                };
+                response.authorities.push(ns.clone());
+                response.additionals.extend(
+                    records
+                        .iter()
+                        .filter(|r| r.name() == host.nsdname() && r.rtype() == RecordType::A)
+                        .cloned(),
+                );
+            }
+            return response;
+        }
+
+        response.header.aa = true;
+        response.authorities.extend(
+            records
+                .iter()
+                .filter(|r| {
+                    r.rtype() == RecordType::SOA && question.qname.is_subdomain_of(r.name())
+                })
+                .cloned(),
+        );
+        let at_name: Vec<&Record> = records
+            .iter()
+            .filter(|r| *r.name() == question.qname)
+            .collect();
+        if at_name.is_empty() {
+            let non_terminal = records
+                .iter()
+                .any(|r| r.name().is_subdomain_of(&question.qname));
+            if broken || !non_terminal {
+                response.header.rcode = RCode::NXDomain;
+            }
+            return response;
+        }
+        let RecordQType::RecordType(qtype) = question.qtype else {
+            unreachable!()
+        };
+        let mut matching: Vec<Record> = at_name
+            .iter()
+            .filter(|r| r.rtype() == qtype)
+            .map(|r| (*r).clone())
+            .collect();
+        if matching.is_empty() {
+            matching = at_name
+                .iter()
+                .filter(|r| r.rtype() == RecordType::CNAME)
+                .map(|r| (*r).clone())
+                .collect();
+        }
+        // Glue with the root NS records, for priming
+        for ns in matching.iter().filter_map(|r| match r.rdata() {
+            RecordData::NS(ns) => Some(ns.nsdname()),
+            _ => None,
+        }) {
+            response.additionals.extend(
+                records
+                    .iter()
+                    .filter(|r| r.name() == ns && r.rtype() == RecordType::A)
+                    .cloned(),
+            );
+        }
+        if !matching.is_empty() {
+            response.authorities.clear();
+        }
+        response.answers = matching;
+        response
+    }
+
+    /// Start a server, returning the names it gets asked about
+    async fn authority(
+        addr: SocketAddr,
+        zones: &[&str],
+        records: &[&str],
+        broken: bool,
+    ) -> Arc<Mutex<Vec<Name>>> {
+        let socket = UdpSocket::bind(addr).await.unwrap();
+        let zones: Vec<Name> = zones.iter().map(|zone| zone.parse().unwrap()).collect();
+        let records: Vec<Record> = records.iter().map(|r| record(r)).collect();
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let log = asked.clone();
+        tokio::spawn(async move {
+            let mut buff = [0; 512];
+            loop {
+                let (size, client) = socket.recv_from(&mut buff).await.unwrap();
+                let query = Message::from_bytes(&buff[..size]).unwrap();
+                assert!(!query.header.rd);
+                log.lock().unwrap().push(query.questions[0].qname.clone());
+                let response = answer(&zones, &records, broken, &query);
+                socket
+                    .send_to(&response.to_bytes().unwrap(), client)
+                    .await
+                    .unwrap();
+            }
+        });
+        asked
+    }
+
+    /// The server in the root hints hands out a.root. when primed
+    const ROOT: &[&str] = &[
+        ". NS a.root.",
+        "a.root. A 127.0.0.5",
+        "com. NS ns.tld.",
+        "net. NS ns.tld.",
+        "org. NS ns.tld.",
+        "ns.tld. A 127.0.0.2",
+    ];
+
+    /// Two roots, a server for com., net. and org., and servers for the zones below them, each on
+    /// its own loopback address. Returns the port and the names the primed root is asked about.
+    async fn hierarchy() -> (u16, Arc<Mutex<Vec<Name>>>) {
+        let root = SocketAddr::from((Ipv4Addr::new(127, 0, 0, 1), 0));
+        let port = UdpSocket::bind(root)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr = |n| SocketAddr::from((Ipv4Addr::new(127, 0, 0, n), port));
+
+        authority(addr(1), &["."], ROOT, false).await;
+        let root = authority(addr(5), &["."], ROOT, false).await;
+        authority(
+            addr(2),
+            &["com.", "net.", "org."],
+            &[
+                "example.com. NS ns1.example.com.",
+                "ns1.example.com. A 127.0.0.3",
+                "example.net. NS ns.example.net.",
+                "ns.example.net. A 127.0.0.4",
+                // No glue, the address has to be looked up
+                "other.com. NS ns.example.net.",
+                // Only reachable over IPv6
+                "six.com. NS ns6.example.net.",
+                // The first server truncates its answers
+                "tc.com. NS ns1.tc.com.",
+                "ns1.tc.com. A 127.0.0.8",
+                "tc.com. NS ns2.tc.com.",
+                "ns2.tc.com. A 127.0.0.9",
+                "example.org. NS ns1.example.org.",
+                "ns1.example.org. A 127.0.0.6",
+                "example.org. NS ns2.example.org.",
+                "ns2.example.org. A 127.0.0.7",
+            ],
+            false,
+        )
+        .await;
+        authority(
+            addr(3),
+            &["example.com."],
+            &[
+                "example.com. NS ns1.example.com.",
+                "example.com. SOA ns1.example.com. admin.example.com. 1 7200 900 1209600 300",
+                "ns1.example.com. A 127.0.0.3",
+                "www.example.com. CNAME web.example.net.",
+                "mail.example.com. A 192.0.2.25",
+                "x.y.example.com. A 192.0.2.26",
+            ],
+            false,
+        )
+        .await;
+        authority(
+            addr(4),
+            &["example.net.", "other.com."],
+            &[
+                "example.net. NS ns.example.net.",
+                "ns.example.net. A 127.0.0.4",
+                "web.example.net. A 192.0.2.80",
+                "other.com. NS ns.example.net.",
+                "www.other.com. A 192.0.2.81",
+                "x.y.example.net. A 192.0.2.82",
+                "ns6.example.net. AAAA ::1",
+            ],
+            true,
+        )
+        .await;
+        // Refers to a name server that doesn't exist
+        authority(
+            addr(6),
+            &["example.org."],
+            &[
+                "example.org. NS ns1.example.org.",
+                "sub.example.org. NS ns.nowhere.invalid.",
+            ],
+            false,
+        )
+        .await;
+        authority(
+            addr(7),
+            &["example.org."],
+            &[
+                "example.org. NS ns2.example.org.",
+                "www.sub.example.org. A 192.0.2.90",
+            ],
+            false,
+        )
+        .await;
+        (port, root)
+    }
+
+    fn context() -> Arc<ServerContext> {
+        Arc::new(ServerContext::new(CacheConfig::default()))
+    }
+
+    fn addresses(response: &Message) -> Vec<Ipv4Addr> {
+        response
+            .answers
+            .iter()
+            .filter_map(|record| match record.rdata() {
+                RecordData::A(a) => Some(a.address()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_iterate() {
+        let (port, _) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+        assert_eq!(resolver.roots(), [Ipv4Addr::new(127, 0, 0, 1)]);
+
+        let response = resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(resolver.roots(), [Ipv4Addr::new(127, 0, 0, 5)]);
+        assert_eq!(response.header.id, 42);
+        assert!(response.header.ra);
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 25)]);
+
+        // CNAME into another zone
+        let response = resolver
+            .handle(request(42, "www.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+        assert_eq!(response.answers.len(), 2);
+        assert_eq!(response.answers[0].rtype(), RecordType::CNAME);
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 80)]);
+
+        // Name server without glue
+        let response = resolver
+            .handle(request(42, "www.other.com."))
+            .await
+            .unwrap();
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 81)]);
+
+        let response = resolver
+            .handle(request(42, "missing.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NXDomain);
+        assert!(resolver.transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unusable_referrals() {
+        let (port, _) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+
+        // The first server of example.org. refers to a name server without an address
+        let response = resolver
+            .handle(request(42, "www.sub.example.org."))
+            .await
+            .unwrap();
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 90)]);
+
+        // The name server of six.com. only has an IPv6 address
+        let six = SocketAddr::from((Ipv6Addr::LOCALHOST, port));
+        if UdpSocket::bind(six).await.is_ok() {
+            authority(six, &["six.com."], &["www.six.com. A 192.0.2.83"], false).await;
+            let response = resolver.handle(request(42, "www.six.com.")).await.unwrap();
+            assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 83)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncated() {
+        let (port, _) = hierarchy().await;
+        let zone = ["tc.com."];
+        let records = ["www.tc.com. A 192.0.2.84", "www.tc.com. A 192.0.2.85"];
+        authority(
+            SocketAddr::from((Ipv4Addr::new(127, 0, 0, 9), port)),
+            &zone,
+            &records,
+            false,
+        )
+        .await;
+        // Answers with only the first record, as if the rest didn't fit
+        let socket = UdpSocket::bind((Ipv4Addr::new(127, 0, 0, 8), port))
+            .await
+            .unwrap();
+        let truncated = Arc::new(Mutex::new(0));
+        let count = truncated.clone();
+        tokio::spawn(async move {
+            let zones = ["tc.com.".parse().unwrap()];
+            let records: Vec<Record> = records.iter().map(|r| record(r)).collect();
+            let mut buff = [0; 512];
+            loop {
+                let (size, client) = socket.recv_from(&mut buff).await.unwrap();
+                let query = Message::from_bytes(&buff[..size]).unwrap();
+                let mut response = answer(&zones, &records, false, &query);
+                response.header.tc = true;
+                response.answers.truncate(1);
+                *count.lock().unwrap() += 1;
+                socket
+                    .send_to(&response.to_bytes().unwrap(), client)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+        let response = resolver.handle(request(42, "www.tc.com.")).await.unwrap();
+        assert!(*truncated.lock().unwrap() > 0);
+        assert!(!response.header.tc);
+        let expected = [Ipv4Addr::new(192, 0, 2, 84), Ipv4Addr::new(192, 0, 2, 85)];
+        assert_eq!(addresses(&response), expected);
+
+        // Only the complete answer was cached
+        let response = resolver.handle(request(42, "www.tc.com.")).await.unwrap();
+        assert_eq!(addresses(&response), expected);
+    }
+
+    #[tokio::test]
+    async fn test_no_recursion() {
+        let (port, root) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+        resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+        root.lock().unwrap().clear();
+
+        let no_recursion = |qname| {
+            let mut request = request(42, qname);
+            request.message.header.rd = false;
+            request
+        };
+        let response = resolver
+            .handle(no_recursion("mail.example.com."))
+            .await
+            .unwrap();
+        assert!(!response.header.rd);
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 25)]);
+
+        // Not in the cache, referred to the closest name servers there are
+        let response = resolver
+            .handle(no_recursion("www.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+        assert!(response.answers.is_empty());
+        assert_eq!(response.authorities[0].rtype(), RecordType::NS);
+        assert_eq!(response.authorities[0].name().to_string(), "example.com.");
+        assert!(root.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_qname_minimisation() {
+        let (port, root) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+
+        // Through the empty non-terminal y.example.com.
+        let response = resolver
+            .handle(request(42, "x.y.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 26)]);
+        // The server for example.net. denies y.example.net., the full name is asked instead
+        let response = resolver
+            .handle(request(42, "x.y.example.net."))
+            .await
+            .unwrap();
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 82)]);
+
+        let asked = root.lock().unwrap().clone();
+        let com: Name = "com.".parse().unwrap();
+        let net: Name = "net.".parse().unwrap();
+        assert!(asked
+            .iter()
+            .all(|name| name.is_root() || *name == com || *name == net));
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let (port, root) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+        resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+        root.lock().unwrap().clear();
+
+        // The answer, then the delegation to example.com. come from the cache
+        let response = resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 25)]);
+        let response = resolver
+            .handle(request(42, "x.y.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 26)]);
+        assert!(root.lock().unwrap().is_empty());
+
+        // Denials are cached for the SOA minimum, with names below them denied too
+        let soa_ttl = |response: &Message| u32::from(response.authorities[0].ttl());
+        let response = resolver
+            .handle(request(42, "missing.example.com."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NXDomain);
+        assert_eq!(soa_ttl(&response), 3600);
+        for qname in ["missing.example.com.", "www.missing.example.com."] {
+            let response = resolver.handle(request(42, qname)).await.unwrap();
+            assert_eq!(response.header.rcode, RCode::NXDomain);
+            assert_eq!(response.authorities[0].rtype(), RecordType::SOA);
+            assert!(soa_ttl(&response) <= 300);
+        }
+        // Counted once for each query, not for each lookup of the cache on the way
+        let stats = resolver.context.cache.stats();
+        assert_eq!((stats.hits, stats.misses), (3, 3));
+        assert!(resolver.transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_through_stack() {
+        let (port, _) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        // Every entry is in its prefetch window, from the second hit on
+        let context = Arc::new(ServerContext::new(CacheConfig {
+            prefetch_hits: 2,
+            prefetch_percent: 100,
+            ..Default::default()
+        }));
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context.clone()).await.unwrap();
+        let stack = crate::stack(resolver);
+
+        for _ in 0..3 {
+            let response = stack
+                .handle(request(42, "mail.example.com."))
+                .await
+                .unwrap();
+            assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 25)]);
+        }
+        assert_eq!(context.cache.stats().prefetches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_serve_stale() {
+        let (port, _) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let context = Arc::new(ServerContext::new(CacheConfig {
+            max_ttl: 1,
+            ..Default::default()
+        }));
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context.clone()).await.unwrap();
+        resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+
+        // The same cache, with servers that can't be reached
+        let config = ResolverConfig {
+            port: 9,
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.9".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        let mut message = request(42, "mail.example.com.").message;
+        message.additionals.push(Record::opt(OPT::new(1232)));
+        let question = message.questions[0].clone();
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, 40000));
+        let response = resolver
+            .handle(Request::new(message, client, Transport::Udp))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 25)]);
+        assert_eq!(u32::from(response.answers[0].ttl()), STALE_TTL);
+        let ede = &response.opt().unwrap().options()[0];
+        assert_eq!((ede.code(), ede.data()), (15, &[0, 3][..]));
+        assert!(resolver.refreshing.contains(&question));
+
+        let response = resolver
+            .handle(request(42, "www.other.com."))
+            .await
+            .unwrap();
+        assert_eq!(response.header.rcode, RCode::ServFail);
+    }
+
+    #[tokio::test]
+    async fn test_client_response_timer() {
+        let (port, _) = hierarchy().await;
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let context = Arc::new(ServerContext::new(CacheConfig {
+            max_ttl: 1,
+            ..Default::default()
+        }));
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.1".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context.clone()).await.unwrap();
+        resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+
+        // The same cache, with a root server that never answers
+        let silent = SocketAddr::from((Ipv4Addr::new(127, 0, 0, 10), port));
+        let _silent = UdpSocket::bind(silent).await.unwrap();
+        let config = ResolverConfig {
+            port,
+            timeout: Duration::from_secs(2),
+            client_response_timer: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.10".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        let started = Instant::now();
+        let response = resolver
+            .handle(request(42, "mail.example.com."))
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(addresses(&response), [Ipv4Addr::new(192, 0, 2, 25)]);
+        assert_eq!(u32::from(response.answers[0].ttl()), STALE_TTL);
+    }
+
+    #[test]
+    fn test_minimised_labels() {
+        let mut steps = Vec::new();
+        let (mut known, mut sent) = (0, 0);
+        while known < 12 {
+            known = minimised_labels(known, sent, 12);
+            sent += 1;
+            steps.push(known);
+        }
+        assert_eq!(steps, [1, 2, 3, 4, 6, 8, 9, 10, 11, 12]);
+        assert_eq!(minimised_labels(2, 0, 3), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_roots() {
+        let config = ResolverConfig {
+            port: 9,
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let hints = ". NS hint.root.\nhint.root. A 127.0.0.9".parse().unwrap();
+        let resolver = Resolver::new(hints, config, context()).await.unwrap();
+        let response = resolver.handle(request(42, "example.com.")).await.unwrap();
+        assert_eq!(response.header.rcode, RCode::ServFail);
+        assert!(resolver.prime().await.is_err());
+
+        assert!(Resolver::new(RootHints::new(vec![]), config, context())
+            .await
+            .is_err());
+    }
+}
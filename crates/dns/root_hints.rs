@@ -0,0 +1,173 @@
+use std::{
+    fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+    str::FromStr,
+};
+
+use parsedns::{error::PresentationError, rr::name::Name};
+
+/// The root hints published by IANA
+///
+/// [Root Files](https://www.iana.org/domains/root/files)
+const NAMED_ROOT: &str = include_str!("named.root");
+
+/// A root server and its addresses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootServer {
+    pub name: Name,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// Where to find the root servers before priming has asked them for the current list
+///
+/// ```text
+/// Recursive resolvers need a starting point to resolve queries.
+/// [RFC1034] describes a common scenario for recursive resolvers: they
+/// begin with an empty cache and some configuration for finding the
+/// names and addresses of the DNS root servers.  [RFC1034] describes
+/// that configuration as a list of servers that will give authoritative
+/// answers to queries about the root.  This has become a common
+/// implementation choice for recursive resolvers, and is the topic of
+/// this document.
+/// ```
+/// [RFC8109 1: Introduction](https://datatracker.ietf.org/doc/html/rfc8109#section-1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootHints {
+    servers: Vec<RootServer>,
+}
+
+impl RootHints {
+    pub fn new(servers: Vec<RootServer>) -> Self {
+        Self { servers }
+    }
+
+    /// The hints built in, a copy of the IANA named.root
+    pub fn iana() -> Self {
+        NAMED_ROOT.parse().expect("embedded named.root is valid")
+    }
+
+    /// Hints from a file in the named.root format, to use instead of the built in ones
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))
+    }
+
+    pub fn servers(&self) -> &[RootServer] {
+        &self.servers
+    }
+
+    /// The addresses of every root server
+    pub fn addresses(&self) -> Vec<IpAddr> {
+        self.servers
+            .iter()
+            .flat_map(|server| server.addresses.iter().copied())
+            .collect()
+    }
+}
+
+/// The NS records of the root and the A and AAAA records of the servers they name, in master file
+/// format. The TTL and class are optional, other records are skipped.
+impl FromStr for RootHints {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut servers: Vec<RootServer> = Vec::new();
+        for line in s.lines() {
+            let line = line.split(';').next().unwrap_or_default();
+            let mut fields = line.split_whitespace().peekable();
+            let owner: Name = match fields.next() {
+                Some(owner) => owner.parse()?,
+                None => continue,
+            };
+            fields.next_if(|field| field.bytes().all(|b| b.is_ascii_digit()));
+            fields.next_if(|field| field.eq_ignore_ascii_case("IN"));
+            let rtype = fields.next().ok_or(PresentationError::UnexpectedEnd)?;
+            let rdata = fields.next().ok_or(PresentationError::UnexpectedEnd)?;
+            if fields.next().is_some() {
+                return Err(PresentationError::TrailingData);
+            }
+
+            let address = match rtype.to_ascii_uppercase().as_str() {
+                "NS" if owner.is_root() => {
+                    let name: Name = rdata.parse()?;
+                    if !servers.iter().any(|server| server.name == name) {
+                        servers.push(RootServer {
+                            name,
+                            addresses: Vec::new(),
+                        });
+                    }
+                    continue;
+                }
+                "A" => IpAddr::V4(
+                    rdata
+                        .parse::<Ipv4Addr>()
+                        .map_err(|_| PresentationError::InvalidAddress)?,
+                ),
+                "AAAA" => IpAddr::V6(
+                    rdata
+                        .parse::<Ipv6Addr>()
+                        .map_err(|_| PresentationError::InvalidAddress)?,
+                ),
+                _ => continue,
+            };
+            if let Some(server) = servers.iter_mut().find(|server| server.name == owner) {
+                server.addresses.push(address);
+            }
+        }
+
+        servers.retain(|server| !server.addresses.is_empty());
+        if servers.is_empty() {
+            return Err(PresentationError::UnexpectedEnd);
+        }
+        Ok(Self { servers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use parsedns::error::PresentationError;
+
+    use super::RootHints;
+
+    #[test]
+    fn test_named_root() {
+        let hints = RootHints::iana();
+        assert_eq!(hints.servers().len(), 13);
+        assert_eq!(
+            hints.servers()[0].name,
+            "a.root-servers.net.".parse().unwrap()
+        );
+        assert_eq!(
+            hints.servers()[0].addresses[0],
+            IpAddr::V4(Ipv4Addr::new(198, 41, 0, 4))
+        );
+        assert!(hints
+            .servers()
+            .iter()
+            .all(|server| server.addresses.len() == 2));
+        assert_eq!(hints.addresses().iter().filter(|a| a.is_ipv6()).count(), 13);
+
+        let local: RootHints = "
+            ; a private root
+            .            NS   ns.root.
+            ns.root.     IN   A 127.0.0.1  ; no TTL
+            other.root.  3600 A 127.0.0.2
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(local.addresses(), [IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+
+        assert_eq!(
+            ". NS ns.root.\nns.root. A 127.0.0.256".parse::<RootHints>(),
+            Err(PresentationError::InvalidAddress)
+        );
+        assert_eq!(
+            "ns.root. A 127.0.0.1".parse::<RootHints>(),
+            Err(PresentationError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,495 @@
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use parsedns::{message::Message, question::Question};
+use rand::Rng;
+use tokio::{net::UdpSocket, sync::oneshot, time::timeout};
+
+use crate::udp::server::BUFF_MAX_SIZE;
+
+/// Random IDs tried before giving up on finding a free one
+const ID_ATTEMPTS: usize = 64;
+
+/// Random source ports tried before leaving the choice to the operating system
+const PORT_ATTEMPTS: usize = 8;
+
+/// An upstream query waiting for its response
+struct Transaction {
+    client: SocketAddr,
+    client_id: u16,
+    question: Question,
+    upstream: SocketAddr,
+    tx: oneshot::Sender<Message>,
+}
+
+/// Why a response was not accepted as the answer to a pending query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// No query is waiting with the ID of the response on the socket it arrived on
+    UnknownId { local: SocketAddr, id: u16 },
+    /// The response came from another address than the query went to
+    WrongSource {
+        expected: SocketAddr,
+        actual: SocketAddr,
+    },
+    /// The question of the response differs from the query
+    WrongQuestion,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownId { local, id } => {
+                write!(f, "no pending query with id {} on {}", id, local)
+            }
+            Self::WrongSource { expected, actual } => {
+                write!(f, "response from {} instead of {}", actual, expected)
+            }
+            Self::WrongQuestion => write!(f, "response question differs from the query"),
+        }
+    }
+}
+
+/// Why an exchange with a server brought no answer
+#[derive(Debug)]
+pub enum ExchangeError {
+    /// Every query ID is taken by a pending query
+    NoFreeId,
+    /// The query could not be sent
+    Send(io::Error),
+    /// No answer arrived in time
+    Timeout,
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFreeId => write!(f, "no free query id"),
+            Self::Send(e) => write!(f, "send error: {}", e),
+            Self::Timeout => write!(f, "no response in time"),
+        }
+    }
+}
+
+impl std::error::Error for ExchangeError {}
+
+/// Bind a socket to talk to `server` from a random port
+///
+/// ```text
+/// Resolver implementations MUST:
+///
+/// o  Use an unpredictable source port for outgoing queries from the
+///    range of available ports (53, or 1024 and above) that is as large
+///    as possible and practicable;
+///
+/// o  Use multiple different source ports simultaneously in case of
+///    multiple outstanding queries;
+///
+/// o  Use an unpredictable query ID for outgoing queries, utilizing the
+///    full range available (0-65535).
+/// ```
+/// [RFC5452 9.2: Extending the Q-ID Space by Using Ports and Addresses](https://datatracker.ietf.org/doc/html/rfc5452#section-9.2)
+async fn bind_random_port(server: SocketAddr) -> io::Result<UdpSocket> {
+    let local = match server {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    for _ in 0..PORT_ATTEMPTS {
+        let port = rand::thread_rng().gen_range(1024..=u16::MAX);
+        match UdpSocket::bind(SocketAddr::new(local, port)).await {
+            Ok(socket) => return Ok(socket),
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    UdpSocket::bind(SocketAddr::new(local, 0)).await
+}
+
+/// The queries sent upstream and not yet answered. Each query goes out from a socket of its own
+/// with a random ID, which together are mapped back to the client and the ID the client chose.
+///
+/// ```text
+/// When a resolver sends a query it is important to be able to match the response
+/// correctly to the query. ... a resolver MUST match responses to all of the
+/// following attributes of the query:
+///
+/// -  Source address against query destination address
+/// -  Destination address against query source address
+/// -  Destination port against query source port
+/// -  Query ID
+/// -  Query name
+/// -  Query class and type
+/// ```
+/// [RFC5452 3: Protocol and Implementation Details](https://datatracker.ietf.org/doc/html/rfc5452#section-3)
+#[derive(Default)]
+pub struct Transactions {
+    /// Keyed by the local address the query was sent from and its ID
+    pending: DashMap<(SocketAddr, u16), Transaction>,
+}
+
+impl Transactions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Register a query for `question` from a client, to be sent from `local`, returning the
+    /// random ID to send it upstream with and where the response will arrive. Returns None when
+    /// no free ID turns up within a bounded number of tries, as when all of them are in use.
+    pub fn start(
+        &self,
+        client: SocketAddr,
+        client_id: u16,
+        question: Question,
+        upstream: SocketAddr,
+        local: SocketAddr,
+    ) -> Option<(u16, oneshot::Receiver<Message>)> {
+        let (tx, rx) = oneshot::channel();
+        let transaction = Transaction {
+            client,
+            client_id,
+            question,
+            upstream,
+            tx,
+        };
+        for _ in 0..ID_ATTEMPTS {
+            if let Entry::Vacant(entry) = self.pending.entry((local, rand::random())) {
+                let (_, id) = *entry.key();
+                entry.insert(transaction);
+                return Some((id, rx));
+            }
+        }
+        None
+    }
+
+    /// Deliver a response from `source`, received on `local`, to the query it answers, with the
+    /// ID of the client restored. Responses that don't match a query are left for the real one
+    /// to arrive.
+    pub fn complete(
+        &self,
+        local: SocketAddr,
+        source: SocketAddr,
+        mut response: Message,
+    ) -> Result<(), Mismatch> {
+        let id = response.header.id;
+        let entry = match self.pending.entry((local, id)) {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(_) => return Err(Mismatch::UnknownId { local, id }),
+        };
+
+        let transaction = entry.get();
+        if transaction.upstream != source {
+            return Err(Mismatch::WrongSource {
+                expected: transaction.upstream,
+                actual: source,
+            });
+        }
+        match response.questions.as_slice() {
+            [question] if *question == transaction.question => {}
+            _ => return Err(Mismatch::WrongQuestion),
+        }
+
+        let transaction = entry.remove();
+        response.header.id = transaction.client_id;
+        if transaction.tx.send(response).is_err() {
+            println!("Client {} stopped waiting", transaction.client);
+        }
+        Ok(())
+    }
+
+    /// Forget a query sent from `local` that will not be answered
+    pub fn cancel(&self, local: SocketAddr, id: u16) {
+        self.pending.remove(&(local, id));
+    }
+
+    /// Send `lookup` to `server` under a random ID, from a socket of its own on a random port,
+    /// and wait up to `wait` for the answer, which comes back with the ID of the lookup. The
+    /// pending state of the query is gone either way.
+    pub async fn exchange(
+        &self,
+        client: SocketAddr,
+        mut lookup: Message,
+        server: SocketAddr,
+        wait: Duration,
+    ) -> Result<Message, ExchangeError> {
+        let Some(question) = lookup.questions.first().cloned() else {
+            return Err(ExchangeError::Send(io::Error::other(
+                "query without a question",
+            )));
+        };
+        let bound = async {
+            let socket = bind_random_port(server).await?;
+            let local = socket.local_addr()?;
+            Ok::<_, io::Error>((socket, local))
+        };
+        let (socket, local) = match bound.await {
+            Ok(bound) => bound,
+            Err(e) => {
+                println!("Send error to {}: {:?}", server, e);
+                return Err(ExchangeError::Send(e));
+            }
+        };
+        let (id, rx) = self
+            .start(client, lookup.header.id, question, server, local)
+            .ok_or(ExchangeError::NoFreeId)?;
+        lookup.header.id = id;
+
+        let sent = async {
+            let bytes = lookup.to_bytes().map_err(io::Error::other)?;
+            socket.send_to(&bytes, server).await
+        };
+        if let Err(e) = sent.await {
+            println!("Send error to {}: {:?}", server, e);
+            self.cancel(local, id);
+            return Err(ExchangeError::Send(e));
+        }
+
+        let response = timeout(wait, async {
+            tokio::select! {
+                response = rx => response.ok(),
+                () = self.receive(&socket, local) => None,
+            }
+        });
+        match response.await {
+            Ok(Some(response)) => Ok(response),
+            _ => {
+                println!("No response from {} after {:?}", server, wait);
+                self.cancel(local, id);
+                Err(ExchangeError::Timeout)
+            }
+        }
+    }
+
+    /// Read the responses arriving on `socket`, bound to `local`, and complete the queries they
+    /// answer
+    async fn receive(&self, socket: &UdpSocket, local: SocketAddr) {
+        let mut buff = [0; BUFF_MAX_SIZE];
+        loop {
+            let (size, addr) = match socket.recv_from(&mut buff).await {
+                Ok(received) => received,
+                Err(e) => {
+                    println!("Receive error: {:?}", e);
+                    continue;
+                }
+            };
+
+            let response = match Message::from_bytes(&buff[..size]) {
+                Ok(response) if response.header.qr => response,
+                _ => continue,
+            };
+            if let Err(e) = self.complete(local, addr, response) {
+                println!("Ignored response: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    };
+
+    use parsedns::{
+        message::Message,
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            types::{RecordQType, RecordType},
+        },
+    };
+
+    use tokio::{net::UdpSocket, sync::oneshot};
+
+    use super::{ExchangeError, Mismatch, Transaction, Transactions};
+
+    fn question(qname: &str) -> Question {
+        Question::new(
+            qname.parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        )
+    }
+
+    fn response(id: u16, qname: &str) -> Message {
+        let mut response = Message::new();
+        response.header.id = id;
+        response.header.qr = true;
+        response.questions.push(question(qname));
+        response
+    }
+
+    #[test]
+    fn test_match_response() {
+        let transactions = Transactions::new();
+        let upstream = SocketAddr::from((Ipv4Addr::new(192, 0, 2, 53), 53));
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, 40000));
+        let local = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 50000));
+
+        // Two clients using the same ID get separate transactions
+        let (id, mut rx) = transactions
+            .start(client, 7, question("example.com."), upstream, local)
+            .unwrap();
+        let (other_id, _) = transactions
+            .start(client, 7, question("example.org."), upstream, local)
+            .unwrap();
+        assert_ne!(id, other_id);
+        assert_eq!(transactions.len(), 2);
+
+        let spoofer = SocketAddr::from((Ipv4Addr::new(203, 0, 113, 1), 53));
+        assert_eq!(
+            transactions.complete(local, spoofer, response(id, "example.com.")),
+            Err(Mismatch::WrongSource {
+                expected: upstream,
+                actual: spoofer
+            })
+        );
+        assert_eq!(
+            transactions.complete(local, upstream, response(id, "example.org.")),
+            Err(Mismatch::WrongQuestion)
+        );
+        let unknown = (1..)
+            .map(|n| id.wrapping_add(n))
+            .find(|&n| n != other_id)
+            .unwrap();
+        assert_eq!(
+            transactions.complete(local, upstream, response(unknown, "example.com.")),
+            Err(Mismatch::UnknownId { local, id: unknown })
+        );
+        // The right ID from the right server, but on a socket the query wasn't sent from
+        let other_local = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 50001));
+        assert_eq!(
+            transactions.complete(other_local, upstream, response(id, "example.com.")),
+            Err(Mismatch::UnknownId {
+                local: other_local,
+                id
+            })
+        );
+        assert!(rx.try_recv().is_err());
+
+        assert_eq!(
+            transactions.complete(local, upstream, response(id, "EXAMPLE.com.")),
+            Ok(())
+        );
+        assert_eq!(rx.try_recv().unwrap().header.id, 7);
+        assert_eq!(transactions.len(), 1);
+
+        transactions.cancel(local, other_id);
+        assert!(transactions.is_empty());
+    }
+
+    #[test]
+    fn test_ids_exhausted() {
+        let transactions = Transactions::new();
+        let upstream = SocketAddr::from((Ipv4Addr::new(192, 0, 2, 53), 53));
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, 40000));
+        let local = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 50000));
+        for id in 0..=u16::MAX {
+            let transaction = Transaction {
+                client,
+                client_id: id,
+                question: question("example.com."),
+                upstream,
+                tx: oneshot::channel().0,
+            };
+            transactions.pending.insert((local, id), transaction);
+        }
+
+        assert!(transactions
+            .start(client, 7, question("example.org."), upstream, local)
+            .is_none());
+        // Another socket has IDs to spare
+        let other_local = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 50001));
+        assert!(transactions
+            .start(client, 7, question("example.org."), upstream, other_local)
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_source_port_per_query() {
+        // Answers two queries, reporting the ports they came from
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let ports = tokio::spawn(async move {
+            let mut buff = [0; 512];
+            let mut ports = Vec::new();
+            for _ in 0..2 {
+                let (size, client) = server.recv_from(&mut buff).await.unwrap();
+                let mut response = Message::from_bytes(&buff[..size]).unwrap();
+                response.header.qr = true;
+                let bytes = response.to_bytes().unwrap();
+                server.send_to(&bytes, client).await.unwrap();
+                ports.push(client.port());
+            }
+            ports
+        });
+
+        let transactions = Transactions::new();
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, 40000));
+        let lookup = |id| {
+            let mut lookup = Message::new();
+            lookup.header.id = id;
+            lookup.questions.push(question("example.com."));
+            lookup
+        };
+        let wait = Duration::from_secs(1);
+        let (first, second) = tokio::join!(
+            transactions.exchange(client, lookup(1), addr, wait),
+            transactions.exchange(client, lookup(2), addr, wait)
+        );
+        assert_eq!(first.unwrap().header.id, 1);
+        assert_eq!(second.unwrap().header.id, 2);
+        assert!(transactions.is_empty());
+
+        let ports = ports.await.unwrap();
+        assert_ne!(ports[0], ports[1]);
+    }
+
+    #[tokio::test]
+    async fn test_response_on_wrong_socket() {
+        // Sends the response to the first query to the port of the second
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buff = [0; 512];
+            let mut queries = Vec::new();
+            for _ in 0..2 {
+                let (size, client) = server.recv_from(&mut buff).await.unwrap();
+                queries.push((Message::from_bytes(&buff[..size]).unwrap(), client));
+            }
+            queries.sort_by_key(|(query, _)| query.questions[0].qname.to_string());
+            let (mut response, _) = queries[0].clone();
+            response.header.qr = true;
+            let bytes = response.to_bytes().unwrap();
+            server.send_to(&bytes, queries[1].1).await.unwrap();
+        });
+
+        let transactions = Transactions::new();
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, 40000));
+        let lookup = |qname| {
+            let mut lookup = Message::new();
+            lookup.questions.push(question(qname));
+            lookup
+        };
+        let wait = Duration::from_millis(300);
+        let (first, second) = tokio::join!(
+            transactions.exchange(client, lookup("a.example."), addr, wait),
+            transactions.exchange(client, lookup("b.example."), addr, wait)
+        );
+        assert!(matches!(first, Err(ExchangeError::Timeout)));
+        assert!(matches!(second, Err(ExchangeError::Timeout)));
+        assert!(transactions.is_empty());
+    }
+}
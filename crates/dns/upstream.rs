@@ -0,0 +1,284 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// How a query picks the upstream it goes to first. Retries go on down the same list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// In the order the upstreams were configured, the rest are only there for failures
+    Failover,
+    /// Each query starts at the upstream after the one the previous query started at
+    RoundRobin,
+    /// Fastest smoothed round trip time first
+    LowestLatency,
+    /// Random order, with upstreams of a higher weight more likely to come first
+    Random,
+}
+
+/// When an upstream is taken out of rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthPolicy {
+    /// Failures in a row after which an upstream gets no more queries
+    pub max_failures: u32,
+    /// How often an upstream out of rotation is probed to see if it is back
+    pub probe_interval: Duration,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 3,
+            probe_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// What is known about an upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpstreamStats {
+    pub addr: SocketAddr,
+    /// Smoothed round trip time, None until the first query completes
+    pub srtt: Option<Duration>,
+    /// Failures since the last success
+    pub failures: u32,
+    pub healthy: bool,
+}
+
+struct Upstream {
+    addr: SocketAddr,
+    weight: u32,
+    stats: Mutex<UpstreamStats>,
+}
+
+/// The upstreams of a forwarder and how well they have been answering. Upstreams that fail
+/// `max_failures` queries in a row are skipped until a probe gets an answer from them again.
+///
+/// The round trip time is smoothed the way TCP does it, with a gain of 1/8 for each sample.
+///
+/// [RFC6298 2: The Basic Algorithm](https://datatracker.ietf.org/doc/html/rfc6298#section-2)
+pub struct Upstreams {
+    upstreams: Vec<Upstream>,
+    strategy: Strategy,
+    health: HealthPolicy,
+    next: AtomicUsize,
+}
+
+impl Upstreams {
+    /// Upstreams with their weights, which only matter to the random strategy
+    pub fn new(
+        upstreams: Vec<(SocketAddr, u32)>,
+        strategy: Strategy,
+        health: HealthPolicy,
+    ) -> Self {
+        let upstreams = upstreams
+            .into_iter()
+            .map(|(addr, weight)| Upstream {
+                addr,
+                weight: weight.max(1),
+                stats: Mutex::new(UpstreamStats {
+                    addr,
+                    srtt: None,
+                    failures: 0,
+                    healthy: true,
+                }),
+            })
+            .collect();
+        Self {
+            upstreams,
+            strategy,
+            health,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.upstreams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.upstreams.is_empty()
+    }
+
+    pub fn health(&self) -> HealthPolicy {
+        self.health
+    }
+
+    pub fn stats(&self) -> Vec<UpstreamStats> {
+        self.upstreams
+            .iter()
+            .map(|u| *u.stats.lock().unwrap())
+            .collect()
+    }
+
+    /// The upstreams out of rotation, waiting for a probe
+    pub fn unhealthy(&self) -> Vec<SocketAddr> {
+        self.stats()
+            .into_iter()
+            .filter(|stats| !stats.healthy)
+            .map(|stats| stats.addr)
+            .collect()
+    }
+
+    /// The upstreams to try for a query, in order. When all of them are out of rotation they are
+    /// all tried anyway rather than failing the query outright.
+    pub fn order(&self) -> Vec<SocketAddr> {
+        let stats = self.stats();
+        let mut candidates: Vec<usize> = (0..stats.len()).filter(|&i| stats[i].healthy).collect();
+        if candidates.is_empty() {
+            candidates = (0..stats.len()).collect();
+        }
+
+        match self.strategy {
+            Strategy::Failover => {}
+            Strategy::RoundRobin => {
+                if !candidates.is_empty() {
+                    let start = self.next.fetch_add(1, Ordering::Relaxed) % candidates.len();
+                    candidates.rotate_left(start);
+                }
+            }
+            // Upstreams never queried come first so they get measured
+            Strategy::LowestLatency => {
+                candidates.sort_by_key(|&i| stats[i].srtt.unwrap_or(Duration::ZERO))
+            }
+            // Weighted sampling without replacement, each upstream keyed by u^(1/weight)
+            Strategy::Random => {
+                let mut keyed: Vec<(f64, usize)> = candidates
+                    .iter()
+                    .map(|&i| {
+                        let weight = self.upstreams[i].weight as f64;
+                        (rand::random::<f64>().powf(1.0 / weight), i)
+                    })
+                    .collect();
+                keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+                candidates = keyed.into_iter().map(|(_, i)| i).collect();
+            }
+        }
+        candidates
+            .into_iter()
+            .map(|i| self.upstreams[i].addr)
+            .collect()
+    }
+
+    /// An answer arrived from `addr` after `rtt`, which also brings it back into rotation
+    pub fn record_success(&self, addr: SocketAddr, rtt: Duration) {
+        self.update(addr, |stats| {
+            stats.srtt = Some(smooth(stats.srtt, rtt));
+            stats.failures = 0;
+            if !stats.healthy {
+                println!("Upstream {} is back", addr);
+                stats.healthy = true;
+            }
+        });
+    }
+
+    /// A query to `addr` went unanswered for `waited`, which counts as a round trip sample
+    pub fn record_failure(&self, addr: SocketAddr, waited: Duration) {
+        let max_failures = self.health.max_failures;
+        self.update(addr, |stats| {
+            stats.srtt = Some(smooth(stats.srtt, waited));
+            stats.failures = stats.failures.saturating_add(1);
+            if stats.healthy && stats.failures >= max_failures {
+                println!(
+                    "Upstream {} is down after {} failures",
+                    addr, stats.failures
+                );
+                stats.healthy = false;
+            }
+        });
+    }
+
+    fn update(&self, addr: SocketAddr, f: impl FnOnce(&mut UpstreamStats)) {
+        if let Some(upstream) = self.upstreams.iter().find(|u| u.addr == addr) {
+            f(&mut upstream.stats.lock().unwrap());
+        }
+    }
+}
+
+fn smooth(srtt: Option<Duration>, sample: Duration) -> Duration {
+    match srtt {
+        Some(srtt) => (srtt * 7 + sample) / 8,
+        None => sample,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    };
+
+    use super::{HealthPolicy, Strategy, Upstreams};
+
+    fn addr(n: u8) -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::new(192, 0, 2, n), 53))
+    }
+
+    fn upstreams(strategy: Strategy) -> Upstreams {
+        let health = HealthPolicy {
+            max_failures: 2,
+            ..Default::default()
+        };
+        Upstreams::new(
+            vec![(addr(1), 1), (addr(2), 1), (addr(3), 1)],
+            strategy,
+            health,
+        )
+    }
+
+    #[test]
+    fn test_strategies() {
+        let failover = upstreams(Strategy::Failover);
+        assert_eq!(failover.order(), [addr(1), addr(2), addr(3)]);
+
+        let round_robin = upstreams(Strategy::RoundRobin);
+        assert_eq!(round_robin.order()[0], addr(1));
+        assert_eq!(round_robin.order()[0], addr(2));
+        assert_eq!(round_robin.order(), [addr(3), addr(1), addr(2)]);
+
+        let latency = upstreams(Strategy::LowestLatency);
+        latency.record_success(addr(1), Duration::from_millis(80));
+        latency.record_success(addr(2), Duration::from_millis(10));
+        latency.record_success(addr(3), Duration::from_millis(40));
+        assert_eq!(latency.order(), [addr(2), addr(3), addr(1)]);
+        latency.record_success(addr(2), Duration::from_millis(490));
+        assert_eq!(latency.stats()[1].srtt, Some(Duration::from_millis(70)));
+        assert_eq!(latency.order(), [addr(3), addr(2), addr(1)]);
+
+        let random = Upstreams::new(
+            vec![(addr(1), 1), (addr(2), 1000)],
+            Strategy::Random,
+            HealthPolicy::default(),
+        );
+        let first = (0..100).filter(|_| random.order()[0] == addr(2)).count();
+        assert!(first > 90, "heavy upstream first {} times", first);
+    }
+
+    #[test]
+    fn test_health() {
+        let upstreams = upstreams(Strategy::Failover);
+        let waited = Duration::from_millis(800);
+
+        upstreams.record_failure(addr(1), waited);
+        assert_eq!(upstreams.order()[0], addr(1));
+        upstreams.record_failure(addr(1), waited);
+        assert_eq!(upstreams.order(), [addr(2), addr(3)]);
+        assert_eq!(upstreams.unhealthy(), [addr(1)]);
+
+        // With nothing healthy left everything is tried
+        upstreams.record_failure(addr(2), waited);
+        upstreams.record_failure(addr(2), waited);
+        upstreams.record_failure(addr(3), waited);
+        upstreams.record_failure(addr(3), waited);
+        assert_eq!(upstreams.order(), [addr(1), addr(2), addr(3)]);
+
+        upstreams.record_success(addr(2), Duration::from_millis(20));
+        assert_eq!(upstreams.order(), [addr(2)]);
+        assert_eq!(upstreams.stats()[1].failures, 0);
+    }
+}
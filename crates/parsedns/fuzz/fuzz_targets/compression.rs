@@ -0,0 +1,51 @@
+//! Names written with compression pointers must read back as the names that were written, and a
+//! name that was already written must cost no more than a single pointer.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parsedns::{
+    message::Message,
+    question::Question,
+    rr::{
+        class::{RecordClass, RecordQClass},
+        name::Name,
+        types::{RecordQType, RecordType},
+    },
+};
+
+const HEADER_LENGTH: usize = 12;
+const POINTER_LENGTH: usize = 2;
+
+fn wire_length(name: &Name) -> usize {
+    name.labels().map(|label| label.len() + 1).sum::<usize>() + 1
+}
+
+fuzz_target!(|names: Vec<Name>| {
+    let mut message = Message::new();
+    let mut bound = HEADER_LENGTH;
+    for (idx, name) in names.iter().enumerate() {
+        bound += 4 + if names[..idx].contains(name) {
+            POINTER_LENGTH.min(wire_length(name))
+        } else {
+            wire_length(name)
+        };
+        message.questions.push(Question::new(
+            name.clone(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+    }
+
+    let bytes = match message.to_bytes() {
+        Ok(bytes) => bytes,
+        // More questions than the count field can hold
+        Err(_) => return,
+    };
+    assert!(bytes.len() <= bound);
+
+    let parsed = Message::from_bytes(&bytes).expect("encoded message parses");
+    assert_eq!(parsed.questions.len(), names.len());
+    for (question, name) in parsed.questions.iter().zip(&names) {
+        assert_eq!(&question.qname, name);
+    }
+});
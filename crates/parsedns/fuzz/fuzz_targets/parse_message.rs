@@ -0,0 +1,17 @@
+//! Hostile packets must be rejected with an error, never a panic or an endless pointer loop.
+//! Anything that parses must encode, and the encoding must parse back to the same message.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parsedns::message::Message;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = Message::from_bytes(data) {
+        let _ = message.to_string();
+        let bytes = message.to_bytes().expect("parsed message encodes");
+        assert_eq!(
+            Message::from_bytes(&bytes).expect("encoded message parses"),
+            message
+        );
+    }
+});
@@ -0,0 +1,13 @@
+//! Every message that can be built must survive encoding and parsing unchanged.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parsedns::message::Message;
+
+fuzz_target!(|message: Message| {
+    let bytes = message.to_bytes().expect("arbitrary message encodes");
+    assert_eq!(
+        Message::from_bytes(&bytes).expect("encoded message parses"),
+        message
+    );
+});
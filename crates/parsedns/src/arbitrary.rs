@@ -0,0 +1,246 @@
+//! Generation of structured values from fuzzer input.
+//!
+//! The generated values are always encodable and parse back to themselves, so they can drive
+//! encode/parse round trips. Hostile input is covered by feeding the raw fuzzer bytes to the
+//! parser instead.
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{
+    header::{Header, OpCode, RCode},
+    message::Message,
+    net::{Ipv4Addr, Ipv6Addr},
+    question::Question,
+    rr::{
+        character_string::{CharacterString, TxtData},
+        class::{RecordClass, RecordQClass},
+        data::RecordData,
+        name::{Name, MAX_LABEL_LENGTH, MAX_NAME_LENGTH},
+        rdata::*,
+        record::Record,
+        types::{RecordQType, RecordType},
+    },
+    utils::{Serial, TTL},
+};
+
+const MAX_SECTION_LENGTH: usize = 8;
+const MAX_RDATA_LENGTH: usize = 64;
+
+/// Types without an RDATA format in this crate, reserved by
+/// [RFC6895 3.1: RRTYPE IANA Considerations](https://datatracker.ietf.org/doc/html/rfc6895#section-3.1)
+const PRIVATE_USE_TYPES: core::ops::RangeInclusive<u16> = 65280..=65534;
+
+fn bytes(u: &mut Unstructured, max: usize) -> Result<Vec<u8>> {
+    let len = u.int_in_range(0..=max)?;
+    Ok(u.bytes(len)?.to_vec())
+}
+
+fn class(u: &mut Unstructured) -> Result<RecordClass> {
+    u.choose(&[
+        RecordClass::IN,
+        RecordClass::CS,
+        RecordClass::CH,
+        RecordClass::HS,
+        RecordClass::NONE,
+    ])
+    .copied()
+}
+
+/// A TTL with the sign bit clear, anything else reads back as zero
+fn ttl(u: &mut Unstructured) -> Result<TTL> {
+    Ok(TTL::from(u32::arbitrary(u)? >> 1))
+}
+
+impl<'a> Arbitrary<'a> for Name {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut name = Name::root();
+        // Each label takes its length octet on top of its data, the root label one more
+        let mut remaining = MAX_NAME_LENGTH - 1;
+        while remaining > 1 && u.arbitrary()? {
+            let max = (remaining - 1).min(MAX_LABEL_LENGTH.into());
+            let len = u.int_in_range(1..=max)?;
+            name.push_label(u.bytes(len)?)
+                .expect("label and name lengths are bounded");
+            remaining -= len + 1;
+        }
+        Ok(name)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Header {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Header {
+            id: u.arbitrary()?,
+            qr: u.arbitrary()?,
+            opcode: OpCode::from(u.int_in_range(0..=15)?),
+            aa: u.arbitrary()?,
+            tc: u.arbitrary()?,
+            rd: u.arbitrary()?,
+            ra: u.arbitrary()?,
+            ad: u.arbitrary()?,
+            cd: u.arbitrary()?,
+            z: u.arbitrary()?,
+            rcode: RCode::from(u.int_in_range(0..=15)?),
+            qdcount: u.arbitrary()?,
+            ancount: u.arbitrary()?,
+            nscount: u.arbitrary()?,
+            arcount: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Question {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let qclass = if u.ratio(1, 8)? {
+            RecordQClass::Any
+        } else {
+            RecordQClass::RecordClass(class(u)?)
+        };
+        Ok(Question::new(
+            u.arbitrary()?,
+            RecordQType::from(u16::arbitrary(u)?),
+            qclass,
+        ))
+    }
+}
+
+fn character_string(u: &mut Unstructured) -> Result<CharacterString> {
+    Ok(CharacterString::new(bytes(u, 255)?).expect("length is bounded"))
+}
+
+fn opt(u: &mut Unstructured) -> Result<OPT> {
+    let mut opt = OPT::new(u.arbitrary()?);
+    opt.set_extended_rcode(u.arbitrary()?);
+    opt.set_dnssec_ok(u.arbitrary()?);
+    for _ in 0..u.int_in_range(0..=4)? {
+        opt.push_option(EdnsOption::new(u.arbitrary()?, bytes(u, MAX_RDATA_LENGTH)?));
+    }
+    Ok(opt)
+}
+
+/// RDATA that parses back as the same variant for the class of its record
+fn rdata(u: &mut Unstructured, class: RecordClass) -> Result<RecordData> {
+    let rdata = match u.int_in_range(0..=18)? {
+        0 if class == RecordClass::IN => RecordData::A(A::new(Ipv4Addr::from(u32::arbitrary(u)?))),
+        1 if class == RecordClass::IN => {
+            RecordData::AAAA(AAAA::new(Ipv6Addr::from(u128::arbitrary(u)?)))
+        }
+        2 if class == RecordClass::IN => RecordData::WKS(WKS::new(
+            Ipv4Addr::from(u32::arbitrary(u)?),
+            u.arbitrary()?,
+            bytes(u, MAX_RDATA_LENGTH)?,
+        )),
+        3 => RecordData::NS(NS::new(u.arbitrary()?)),
+        4 => RecordData::MD(MD::new(u.arbitrary()?)),
+        5 => RecordData::MF(MF::new(u.arbitrary()?)),
+        6 => RecordData::CNAME(CNAME::new(u.arbitrary()?)),
+        7 => RecordData::SOA(SOA::new(
+            u.arbitrary()?,
+            u.arbitrary()?,
+            Serial::from(u32::arbitrary(u)?),
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+            ttl(u)?,
+        )),
+        8 => RecordData::MB(MB::new(u.arbitrary()?)),
+        9 => RecordData::MG(MG::new(u.arbitrary()?)),
+        10 => RecordData::MR(MR::new(u.arbitrary()?)),
+        11 => RecordData::NULL(NULL::new(bytes(u, MAX_RDATA_LENGTH)?)),
+        12 => RecordData::PTR(PTR::new(u.arbitrary()?)),
+        13 => RecordData::MINFO(MINFO::new(u.arbitrary()?, u.arbitrary()?)),
+        14 => RecordData::MX(MX::new(u.arbitrary()?, u.arbitrary()?)),
+        15 => RecordData::RRSIG({
+            let mut rrsig = RRSIG::new(
+                RecordType::from(u16::arbitrary(u)?),
+                u.arbitrary()?,
+                u.arbitrary()?,
+                ttl(u)?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+            );
+            rrsig.set_signature(bytes(u, MAX_RDATA_LENGTH)?);
+            rrsig
+        }),
+        16 => RecordData::HINFO(HINFO::new(character_string(u)?, character_string(u)?)),
+        17 => RecordData::TXT(TXT::new({
+            let strings = (0..u.int_in_range(1..=4)?)
+                .map(|_| character_string(u))
+                .collect::<Result<_>>()?;
+            TxtData::new(strings).expect("at least one string")
+        })),
+        _ => RecordData::Unknown(Unknown::new(
+            RecordType::from(u.int_in_range(PRIVATE_USE_TYPES)?),
+            bytes(u, MAX_RDATA_LENGTH)?,
+        )),
+    };
+    Ok(rdata)
+}
+
+impl<'a> Arbitrary<'a> for Record {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let class = class(u)?;
+        Ok(Record::new(
+            u.arbitrary()?,
+            class,
+            ttl(u)?,
+            rdata(u, class)?,
+        ))
+    }
+}
+
+fn section<'a, T: Arbitrary<'a>>(u: &mut Unstructured<'a>) -> Result<Vec<T>> {
+    let len = u.int_in_range(0..=MAX_SECTION_LENGTH)?;
+    (0..len).map(|_| u.arbitrary()).collect()
+}
+
+/// Messages carry at most one OPT record, placed in the additional section
+impl<'a> Arbitrary<'a> for Message {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut message = Message {
+            header: u.arbitrary()?,
+            questions: section(u)?,
+            answers: section(u)?,
+            authorities: section(u)?,
+            additionals: section(u)?,
+        };
+        if u.arbitrary()? {
+            let idx = u.int_in_range(0..=message.additionals.len())?;
+            message.additionals.insert(idx, Record::opt(opt(u)?));
+        }
+
+        message.header.qdcount = message.questions.len() as u16;
+        message.header.ancount = message.answers.len() as u16;
+        message.header.nscount = message.authorities.len() as u16;
+        message.header.arcount = message.additionals.len() as u16;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::arbitrary::{Arbitrary, Unstructured};
+
+    use crate::message::Message;
+
+    #[test]
+    fn test_arbitrary_message_round_trip() {
+        // A simple xorshift generator stands in for the fuzzer
+        let mut state: u64 = 0x853C_49E6_748F_EA9B;
+        let mut data = vec![0u8; 4096];
+        for _ in 0..256 {
+            for byte in data.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = state as u8;
+            }
+
+            let message = Message::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let bytes = message.to_bytes().unwrap();
+            assert_eq!(Message::from_bytes(&bytes).unwrap(), message);
+        }
+    }
+}
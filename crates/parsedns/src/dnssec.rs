@@ -0,0 +1,177 @@
+//! Construction of the data that DNSSEC signatures cover.
+
+use alloc::vec::Vec;
+
+use crate::{
+    encoder::Encoder,
+    error::EncodeError,
+    rr::{name::Name, rdata::RRSIG, record::Record, rrset::RRset},
+    traits::Encode,
+};
+
+/// The owner name the signature covers. An RRset expanded from a wildcard is signed under the
+/// wildcard name, which the RRSIG labels field reveals.
+///
+/// [RFC4035 5.3.2: Reconstructing the Signed Data](https://datatracker.ietf.org/doc/html/rfc4035#section-5.3.2)
+fn signed_owner(owner: &Name, labels: u8) -> Name {
+    let labels = labels as usize;
+    if labels >= owner.label_count() {
+        return owner.clone();
+    }
+
+    // The wildcard label replaces at least one label so the name can't grow too long
+    let mut wildcard = Name::root();
+    let _ = wildcard.push_label(b"*");
+    for label in owner.labels().skip(owner.label_count() - labels) {
+        let _ = wildcard.push_label(label);
+    }
+    wildcard
+}
+
+/// The canonical form of a record: the owner and the names inside the RDATA of the types listed
+/// by RFC4034 in lowercase, and no name compressed
+///
+/// [RFC4034 6.2: Canonical RR Form](https://datatracker.ietf.org/doc/html/rfc4034#section-6.2)
+pub fn canonical_record(record: &Record) -> Result<Vec<u8>, EncodeError> {
+    let mut e = Encoder::canonical();
+    record.encode(&mut e)?;
+    Ok(e.finish())
+}
+
+/// The data an RRSIG signature is computed over
+///
+/// ```text
+/// signature = sign(RRSIG_RDATA | RR(1) | RR(2)... ) where
+///
+///    "|" denotes concatenation;
+///
+///    RRSIG_RDATA is the wire format of the RRSIG RDATA fields
+///       with the Signer's Name field in canonical form and
+///       the Signature field excluded;
+///
+///    RR(i) = owner | type | class | TTL | RDATA length | RDATA
+///
+///       "owner" is the fully qualified owner name of the RRset in
+///       canonical form (for RRs with wildcard owner names, the
+///       wildcard label is included in the owner name);
+///
+///       Each RR MUST have the same owner name as the RRSIG RR;
+///
+///       Each RR MUST have the same class as the RRSIG RR;
+///
+///       Each RR in the RRset MUST have the RR type listed in the
+///       RRSIG RR's Type Covered field;
+///
+///       Each RR in the RRset MUST have the TTL listed in the
+///       RRSIG Original TTL Field;
+///
+///       Any DNS names in the RDATA field of each RR MUST be in
+///       canonical form; and
+///
+///       The RRset MUST be sorted in canonical order.
+/// ```
+/// [RFC4034 3.1.8.1: Signature Calculation](https://datatracker.ietf.org/doc/html/rfc4034#section-3.1.8.1)
+///
+/// The signature field of `rrsig` is ignored, so the same input serves signing and validation.
+pub fn signing_input(rrset: &RRset, rrsig: &RRSIG) -> Result<Vec<u8>, EncodeError> {
+    let mut e = Encoder::canonical();
+    rrsig.encode_fields(&mut e)?;
+
+    let owner = signed_owner(rrset.name(), rrsig.labels());
+    for rdata in rrset.rdatas() {
+        let record = Record::new(
+            owner.clone(),
+            rrset.class(),
+            rrsig.original_ttl(),
+            rdata.clone(),
+        );
+        record.encode(&mut e)?;
+    }
+    Ok(e.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        net::Ipv4Addr,
+        rr::{
+            class::RecordClass,
+            data::RecordData,
+            rdata::{A, NS, RRSIG},
+            record::Record,
+            rrset::RRset,
+            types::RecordType,
+        },
+        utils::TTL,
+    };
+
+    use super::{canonical_record, signing_input};
+
+    #[test]
+    fn test_canonical_record() {
+        let record = Record::new(
+            "Example.COM.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(3600),
+            RecordData::NS(NS::new("NS1.Example.com.".parse().unwrap())),
+        );
+
+        let mut expected =
+            b"\x07example\x03com\x00\x00\x02\x00\x01\x00\x00\x0e\x10\x00\x11".to_vec();
+        expected.extend_from_slice(b"\x03ns1\x07example\x03com\x00");
+        assert_eq!(canonical_record(&record).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_signing_input() {
+        let a = |address: [u8; 4], ttl| {
+            Record::new(
+                "host.Example.com.".parse().unwrap(),
+                RecordClass::IN,
+                TTL::from(ttl),
+                RecordData::A(A::new(Ipv4Addr::from(address))),
+            )
+        };
+        let rrset = &RRset::from_records(&[a([192, 0, 2, 2], 60), a([192, 0, 2, 1], 30)])[0];
+        let mut rrsig = RRSIG::new(
+            RecordType::A,
+            13,
+            3,
+            TTL::from(3600),
+            1_700_086_400,
+            1_700_000_000,
+            12345,
+            "Example.com.".parse().unwrap(),
+        );
+        rrsig.set_signature(vec![0xFF; 64]);
+
+        let input = signing_input(rrset, &rrsig).unwrap();
+
+        let mut expected = vec![0, 1, 13, 3, 0, 0, 0x0e, 0x10];
+        expected.extend_from_slice(&1_700_086_400u32.to_be_bytes());
+        expected.extend_from_slice(&1_700_000_000u32.to_be_bytes());
+        expected.extend_from_slice(&12345u16.to_be_bytes());
+        expected.extend_from_slice(b"\x07example\x03com\x00");
+        for last in [1, 2] {
+            expected.extend_from_slice(b"\x04host\x07example\x03com\x00");
+            expected.extend_from_slice(&[0, 1, 0, 1, 0, 0, 0x0e, 0x10, 0, 4, 192, 0, 2, last]);
+        }
+        assert_eq!(input, expected);
+
+        // Synthesized from *.example.com.
+        let rrsig = RRSIG::new(
+            RecordType::A,
+            13,
+            2,
+            TTL::from(3600),
+            1_700_086_400,
+            1_700_000_000,
+            12345,
+            "example.com.".parse().unwrap(),
+        );
+        let input = signing_input(rrset, &rrsig).unwrap();
+        assert!(input
+            .windows(15)
+            .any(|w| w == b"\x01*\x07example\x03com\x00"));
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::rr::name::Name;
+
+/// Offsets at or above this value cannot be the target of a compression pointer
+const MAX_POINTER_OFFSET: usize = 0x3FFF;
+const POINTER_MASK: u16 = 0xC000;
+
+/// Writes the wire format of a message into a buffer.
+///
+/// When compression is enabled every name written is remembered so that later names sharing a
+/// suffix can be replaced by a pointer. Only names in positions that allow it are compressed.
+///
+/// [RFC1035 4.1.4: Message compression](https://datatracker.ietf.org/doc/html/rfc1035#section-4.1.4)
+pub struct Encoder {
+    buf: Vec<u8>,
+    names: Option<BTreeMap<Vec<u8>, u16>>,
+    lowercase: bool,
+}
+
+impl Encoder {
+    /// An encoder that compresses names
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::with_capacity(512),
+            names: Some(BTreeMap::new()),
+            lowercase: false,
+        }
+    }
+
+    /// An encoder that always writes full names
+    pub fn uncompressed() -> Self {
+        Self {
+            buf: Vec::with_capacity(512),
+            names: None,
+            lowercase: false,
+        }
+    }
+
+    /// An encoder that writes full names with uppercase US-ASCII letters replaced by lowercase
+    ///
+    /// [RFC4034 6.2: Canonical RR Form](https://datatracker.ietf.org/doc/html/rfc4034#section-6.2)
+    pub fn canonical() -> Self {
+        Self {
+            buf: Vec::with_capacity(512),
+            names: None,
+            lowercase: true,
+        }
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub fn write_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn write_bytes(&mut self, v: &[u8]) {
+        self.buf.extend_from_slice(v);
+    }
+
+    /// Overwrite a previously written u16, used for length fields only known after the data
+    pub fn patch_u16(&mut self, pos: usize, v: u16) {
+        self.buf[pos..pos + 2].copy_from_slice(&v.to_be_bytes());
+    }
+
+    /// Write a name, replacing the longest suffix already in the message with a pointer when
+    /// `compress` is set
+    pub fn write_name(&mut self, name: &Name, compress: bool) {
+        let labels: Vec<&[u8]> = name.labels().collect();
+
+        for idx in 0..labels.len() {
+            if let Some(names) = self.names.as_mut() {
+                let key = suffix_key(&labels[idx..]);
+                match names.get(&key) {
+                    Some(&ptr) if compress => {
+                        self.write_u16(POINTER_MASK | ptr);
+                        return;
+                    }
+                    Some(_) => {}
+                    None => {
+                        if self.buf.len() <= MAX_POINTER_OFFSET {
+                            names.insert(key, self.buf.len() as u16);
+                        }
+                    }
+                }
+            }
+
+            self.write_u8(labels[idx].len() as u8);
+            if self.lowercase {
+                self.buf
+                    .extend(labels[idx].iter().map(|b| b.to_ascii_lowercase()));
+            } else {
+                self.write_bytes(labels[idx]);
+            }
+        }
+
+        self.write_u8(0);
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Names compare case insensitively so suffixes are keyed by their lowercase wire form
+fn suffix_key(labels: &[&[u8]]) -> Vec<u8> {
+    let mut key = Vec::new();
+    for label in labels {
+        key.push(label.len() as u8);
+        key.extend(label.iter().map(|b| b.to_ascii_lowercase()));
+    }
+    key
+}
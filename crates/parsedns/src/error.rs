@@ -1,9 +1,11 @@
+use core::fmt;
+
 use crate::indexed_input::IBitInput;
 
 use super::indexed_input::IByteInput;
 use nom::error::{ErrorKind, FromExternalError, ParseError};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParserErrorType {
     LabelBytesTooLong(u16),
     UnrecognizedLabelCode(u8),
@@ -11,6 +13,7 @@ pub enum ParserErrorType {
     UnrecognizedRecordType(u16),
     RDLengthTooLong(u16),
     DomainNameTooLong(usize),
+    CharacterStringTooLong(usize),
     UnknownLabelType(u8),
     PointerNotPriorToLabel { idx: usize, ptr: u16 },
     LabelOverlapsWithOther { label: usize, other: usize },
@@ -60,3 +63,97 @@ impl<'a> ParseError<IByteInput<'a>> for ParserError {
         other
     }
 }
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.err_type, self.nom_kind) {
+            (Some(err_type), _) => write!(f, "{:?} at byte {}", err_type, self.position),
+            (None, Some(kind)) => write!(f, "{:?} at byte {}", kind, self.position),
+            (None, None) => write!(f, "invalid message at byte {}", self.position),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParserError {}
+
+/// Errors raised while writing a message to the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The encoded RDATA does not fit in the 16 bit RDLENGTH field
+    RDataTooLong(usize),
+    /// A message section holds more entries than its 16 bit count field allows
+    SectionTooLong(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RDataTooLong(len) => write!(f, "rdata of {} bytes exceeds 65535", len),
+            Self::SectionTooLong(len) => write!(f, "section of {} entries exceeds 65535", len),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Errors raised while reading the master file (presentation) format of names and RDATA
+///
+/// [RFC1035 5.1: Master file format](https://datatracker.ietf.org/doc/html/rfc1035#section-5.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresentationError {
+    /// The input ended before all fields were read
+    UnexpectedEnd,
+    /// Input remained after all fields were read
+    TrailingData,
+    /// A `\` escape was not followed by a character or three decimal digits below 256
+    InvalidEscape,
+    /// A quoted string was not closed
+    UnterminatedString,
+    /// A numeric field was not a valid number for its width
+    InvalidNumber,
+    /// An address field was not a valid address
+    InvalidAddress,
+    /// A hex field had an odd length or a non hex digit
+    InvalidHex,
+    /// A base64 field had a length that is not a multiple of four or a non base64 digit
+    InvalidBase64,
+    /// A type or class mnemonic was not recognized
+    UnknownMnemonic,
+    /// Two dots appeared in a row inside a name
+    EmptyLabel,
+    /// The data has no presentation format and must use the generic `\#` form
+    /// [RFC3597 5](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+    Unsupported,
+    /// The value violates a wire format limit
+    Wire(ParserErrorType),
+}
+
+impl From<ParserErrorType> for PresentationError {
+    fn from(e: ParserErrorType) -> Self {
+        Self::Wire(e)
+    }
+}
+
+impl fmt::Display for PresentationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+            Self::TrailingData => write!(f, "trailing data"),
+            Self::InvalidEscape => write!(f, "invalid escape sequence"),
+            Self::UnterminatedString => write!(f, "unterminated string"),
+            Self::InvalidNumber => write!(f, "invalid number"),
+            Self::InvalidAddress => write!(f, "invalid address"),
+            Self::InvalidHex => write!(f, "invalid hex string"),
+            Self::InvalidBase64 => write!(f, "invalid base64 string"),
+            Self::UnknownMnemonic => write!(f, "unknown mnemonic"),
+            Self::EmptyLabel => write!(f, "empty label"),
+            Self::Unsupported => write!(f, "no presentation format for this data"),
+            Self::Wire(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PresentationError {}
@@ -1,8 +1,11 @@
+use core::fmt;
+
 use nom::{combinator::map, IResult};
 
-use crate::error::ParserError;
+use crate::encoder::Encoder;
+use crate::error::{EncodeError, ParserError};
 use crate::indexed_input::{IBitInput, IByteInput, IndexedInput};
-use crate::traits::Parse;
+use crate::traits::{Encode, Parse};
 
 /// RCodes appear at the top level of a DNS response (4 bits) but also inside TSIG RRs [RFC2845](https://datatracker.ietf.org/doc/html/rfc2845), TKEY RRs
 /// [RFC2930](https://datatracker.ietf.org/doc/html/rfc2930),
@@ -13,6 +16,7 @@ use crate::traits::Parse;
 /// From [RFC6895](https://datatracker.ietf.org/doc/html/rfc6895#section-2.3)
 /// IANA [link](https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-6)
 ///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RCode {
     /// No error condition [RFC1035](https://datatracker.ietf.org/doc/html/rfc1035#section-4.1.1)
     NoError,
@@ -107,6 +111,65 @@ impl From<u8> for RCode {
     }
 }
 
+impl From<RCode> for u8 {
+    fn from(value: RCode) -> Self {
+        match value {
+            RCode::NoError => 0,
+            RCode::FormErr => 1,
+            RCode::ServFail => 2,
+            RCode::NXDomain => 3,
+            RCode::NotImp => 4,
+            RCode::Refused => 5,
+            RCode::YXDomain => 6,
+            RCode::YXRRSet => 7,
+            RCode::NXRRSet => 8,
+            RCode::NotAuth => 9,
+            RCode::NotZone => 10,
+            RCode::DSOTYPENI => 11,
+            RCode::BADSIGVERS => 16,
+            RCode::BADKEY => 17,
+            RCode::BADTIME => 18,
+            RCode::BADNAME => 19,
+            RCode::BADMODE => 20,
+            RCode::BADALG => 21,
+            RCode::BADTRUNC => 22,
+            RCode::BADCOOKIE => 23,
+            RCode::Unknown(other) => other,
+        }
+    }
+}
+
+/// The mnemonics used by `dig`, reserved values are shown as `RESERVED` and their value
+impl fmt::Display for RCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = match self {
+            Self::NoError => "NOERROR",
+            Self::FormErr => "FORMERR",
+            Self::ServFail => "SERVFAIL",
+            Self::NXDomain => "NXDOMAIN",
+            Self::NotImp => "NOTIMP",
+            Self::Refused => "REFUSED",
+            Self::YXDomain => "YXDOMAIN",
+            Self::YXRRSet => "YXRRSET",
+            Self::NXRRSet => "NXRRSET",
+            Self::NotAuth => "NOTAUTH",
+            Self::NotZone => "NOTZONE",
+            Self::DSOTYPENI => "DSOTYPENI",
+            Self::BADSIGVERS => "BADVERS",
+            Self::BADKEY => "BADKEY",
+            Self::BADTIME => "BADTIME",
+            Self::BADNAME => "BADNAME",
+            Self::BADMODE => "BADMODE",
+            Self::BADALG => "BADALG",
+            Self::BADTRUNC => "BADTRUNC",
+            Self::BADCOOKIE => "BADCOOKIE",
+            Self::Unknown(v) => return write!(f, "RESERVED{}", v),
+        };
+        f.write_str(mnemonic)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpCode {
     /// A standard query [RFC1035](https://www.rfc-editor.org/rfc/rfc1035#section-4.1.1)
     Query,
@@ -138,6 +201,35 @@ impl From<u8> for OpCode {
     }
 }
 
+impl From<OpCode> for u8 {
+    fn from(value: OpCode) -> Self {
+        match value {
+            OpCode::Query => 0,
+            OpCode::IQuery => 1,
+            OpCode::Status => 2,
+            OpCode::Notify => 4,
+            OpCode::Update => 5,
+            OpCode::DSO => 6,
+            OpCode::Unknown(other) => other,
+        }
+    }
+}
+
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = match self {
+            Self::Query => "QUERY",
+            Self::IQuery => "IQUERY",
+            Self::Status => "STATUS",
+            Self::Notify => "NOTIFY",
+            Self::Update => "UPDATE",
+            Self::DSO => "DSO",
+            Self::Unknown(v) => return write!(f, "RESERVED{}", v),
+        };
+        f.write_str(mnemonic)
+    }
+}
+
 ///
 /// Testing
 ///                                1  1  1  1  1  1
@@ -156,6 +248,7 @@ impl From<u8> for OpCode {
 /// |                    ARCOUNT                    |
 /// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
 /// From [RFC 2535](https://datatracker.ietf.org/doc/html/rfc2535#section-6.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
     /// A 16 bit identifier assigned by the program that generates any kind of query.
     /// This identifier is copied the corresponding reply and can be used by the
@@ -235,6 +328,60 @@ impl Header {
     }
 }
 
+impl Default for Header {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The header lines printed by `dig`
+///
+/// ```text
+/// ;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 4660
+/// ;; flags: qr rd ra; QUERY: 1, ANSWER: 2, AUTHORITY: 0, ADDITIONAL: 1
+/// ```
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            ";; ->>HEADER<<- opcode: {}, status: {}, id: {}",
+            self.opcode, self.rcode, self.id
+        )?;
+
+        write!(f, ";; flags:")?;
+        let flags = [
+            (self.qr, "qr"),
+            (self.aa, "aa"),
+            (self.tc, "tc"),
+            (self.rd, "rd"),
+            (self.ra, "ra"),
+            (self.ad, "ad"),
+            (self.cd, "cd"),
+        ];
+        for (_, flag) in flags.iter().filter(|(set, _)| *set) {
+            write!(f, " {}", flag)?;
+        }
+
+        // Dynamic update renames the sections [RFC2136 2](https://datatracker.ietf.org/doc/html/rfc2136#section-2)
+        let sections = match self.opcode {
+            OpCode::Update => ["ZONE", "PREREQ", "UPDATE", "ADDITIONAL"],
+            _ => ["QUERY", "ANSWER", "AUTHORITY", "ADDITIONAL"],
+        };
+        write!(
+            f,
+            "; {}: {}, {}: {}, {}: {}, {}: {}",
+            sections[0],
+            self.qdcount,
+            sections[1],
+            self.ancount,
+            sections[2],
+            self.nscount,
+            sections[3],
+            self.arcount
+        )
+    }
+}
+
 impl Parse for Header {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
         let (i, id) = take_u16(i.into())?;
@@ -248,8 +395,8 @@ impl Parse for Header {
         let (i, ad) = take_bit(i)?;
         let (i, cd) = take_bit(i)?;
         let (i, rcode) = take_nibble(i)?;
-        let (i, ancount) = take_u16(i)?;
         let (i, qdcount) = take_u16(i)?;
+        let (i, ancount) = take_u16(i)?;
         let (i, nscount) = take_u16(i)?;
         let (i, arcount) = take_u16(i)?;
 
@@ -276,9 +423,31 @@ impl Parse for Header {
     }
 }
 
-//impl Parse for Header {
-//    //fn parse(i: IInput) -> IResult<IInput, Self, ParserError> {}
-//}
+impl Encode for Header {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        let opcode: u8 = self.opcode.into();
+        let rcode: u8 = self.rcode.into();
+
+        let flags = (self.qr as u16) << 15
+            | ((opcode & 0xF) as u16) << 11
+            | (self.aa as u16) << 10
+            | (self.tc as u16) << 9
+            | (self.rd as u16) << 8
+            | (self.ra as u16) << 7
+            | (self.z as u16) << 6
+            | (self.ad as u16) << 5
+            | (self.cd as u16) << 4
+            | (rcode & 0xF) as u16;
+
+        e.write_u16(self.id);
+        e.write_u16(flags);
+        e.write_u16(self.qdcount);
+        e.write_u16(self.ancount);
+        e.write_u16(self.nscount);
+        e.write_u16(self.arcount);
+        Ok(())
+    }
+}
 
 pub fn take_bit(i: IBitInput) -> IResult<IBitInput, bool, ParserError> {
     map(IndexedInput::take(1u8), |bits: u8| bits != 0)(i)
@@ -294,11 +463,12 @@ pub fn take_u16(i: IBitInput) -> IResult<IBitInput, u16, ParserError> {
 
 #[cfg(test)]
 mod tests {
-    use nom::bits::complete::take;
     use nom::IResult;
 
-    use crate::header::IBitInput;
-    use crate::indexed_input::IndexedInput;
+    use crate::encoder::Encoder;
+    use crate::header::{Header, IBitInput, OpCode, RCode};
+    use crate::indexed_input::{IByteInput, IndexedInput};
+    use crate::traits::{Encode, Parse};
 
     #[test]
     fn test_bit() {
@@ -319,4 +489,30 @@ mod tests {
         let i = parser(i.0, 4).unwrap();
         println!("{:?}", i);
     }
+
+    #[test]
+    fn test_header_round_trip() {
+        let mut header = Header::new();
+        header.id = 0xBEEF;
+        header.qr = true;
+        header.opcode = OpCode::Update;
+        header.rd = true;
+        header.cd = true;
+        header.rcode = RCode::NXDomain;
+        header.qdcount = 1;
+        header.ancount = 2;
+        header.nscount = 3;
+        header.arcount = 4;
+
+        let mut e = Encoder::new();
+        header.encode(&mut e).unwrap();
+        let bytes = e.finish();
+        assert_eq!(
+            bytes,
+            [0xBE, 0xEF, 0xA9, 0x13, 0, 1, 0, 2, 0, 3, 0, 4].as_ref()
+        );
+
+        let (_, parsed) = Header::parse(IByteInput::new(&bytes)).unwrap();
+        assert_eq!(parsed, header);
+    }
 }
@@ -4,9 +4,9 @@ use nom::{
     ToUsize,
 };
 
+use core::ops::{AddAssign, Div, RangeFull, Shl, Shr};
 use core::ops::{RangeFrom, RangeTo};
 use core::slice;
-use std::ops::{AddAssign, Div, RangeFull, Shl, Shr};
 
 pub type IByteInput<'a> = IndexedInput<&'a [u8], ()>;
 pub type IBitInput<'a> = IndexedInput<&'a [u8], usize>;
@@ -54,7 +54,7 @@ impl<T: AsBytes> IndexedInput<T, ()> {
         let self_bytes = self.input.as_bytes();
         let self_ptr = self_bytes.as_ptr();
         unsafe {
-            assert!(self.idx <= isize::max_value() as usize, "Offset is too big");
+            assert!(self.idx <= isize::MAX as usize, "Offset is too big");
             let orig_input_ptr = self_ptr.offset(-(self.idx as isize));
             slice::from_raw_parts(orig_input_ptr, self.idx + self_bytes.len())
         }
@@ -71,7 +71,7 @@ where
     T: Slice<RangeFrom<usize>> + InputIter<Item = u8> + InputLength + Offset,
 {
     pub fn to_bytes(&self) -> IndexedInput<T, ()> {
-        let next_input = if self.bit_offset % 8 != 0 {
+        let next_input = if !self.bit_offset.is_multiple_of(8) {
             self.input.slice((1 + self.bit_offset / 8)..)
         } else {
             self.input.slice((self.bit_offset / 8)..)
@@ -112,7 +112,7 @@ where
                         let val: O = if offset == 0 {
                             byte.into()
                         } else {
-                            ((byte << offset) as u8 >> offset).into()
+                            ((byte << offset) >> offset).into()
                         };
 
                         if remaining < 8 - offset {
@@ -129,7 +129,6 @@ where
                     let next_input = ii.input.slice(cnt..);
                     let consumed_len = ii.input.offset(&next_input);
                     let next_offset = ii.idx + consumed_len;
-                    println!("{}", end_offset);
 
                     let ii = IndexedInput {
                         idx: next_offset,
@@ -151,15 +150,15 @@ impl<T> core::ops::Deref for IndexedInput<T, ()> {
     }
 }
 
-impl<'a> Into<IByteInput<'a>> for IBitInput<'a> {
-    fn into(self) -> IByteInput<'a> {
-        self.to_bytes()
+impl<'a> From<IBitInput<'a>> for IByteInput<'a> {
+    fn from(i: IBitInput<'a>) -> Self {
+        i.to_bytes()
     }
 }
 
-impl<'a> Into<IBitInput<'a>> for IByteInput<'a> {
-    fn into(self) -> IBitInput<'a> {
-        self.to_bits()
+impl<'a> From<IByteInput<'a>> for IBitInput<'a> {
+    fn from(i: IByteInput<'a>) -> Self {
+        i.to_bits()
     }
 }
 
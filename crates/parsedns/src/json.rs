@@ -0,0 +1,797 @@
+//! Serialization of messages to and from the JSON representation of
+//! [RFC8427: Representing DNS Messages in JSON](https://datatracker.ietf.org/doc/html/rfc8427)
+//!
+//! Names and RDATA appear in their master file (presentation) format. Raw wire bytes are only
+//! written when a value is wrapped in [`WithOctets`], and are preferred over the other members
+//! when reading.
+
+use core::fmt;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use serde::{
+    de::{self, IgnoredAny, MapAccess, Visitor},
+    ser::{self, SerializeMap},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    encoder::Encoder,
+    error::ParserError,
+    header::Header,
+    indexed_input::IByteInput,
+    message::Message,
+    question::Question,
+    rr::{
+        class::{RecordClass, RecordQClass},
+        data::RecordData,
+        name::Name,
+        rdata::*,
+        record::Record,
+        types::{RecordQType, RecordType},
+    },
+    traits::{Encode, Parse},
+    utils::{from_hex, to_hex, TTL},
+};
+
+/// Serialize a [`Message`], [`Header`] or [`Record`] together with its wire format in the
+/// `messageOctetsHEX`, `headerOctetsHEX` or `rdataHEX` members.
+pub struct WithOctets<'a, T>(pub &'a T);
+
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(PresentationVisitor::new("a domain name"))
+    }
+}
+
+/// Reads any type with a master file format from a JSON string
+struct PresentationVisitor<T> {
+    expecting: &'static str,
+    value: core::marker::PhantomData<T>,
+}
+
+impl<T> PresentationVisitor<T> {
+    fn new(expecting: &'static str) -> Self {
+        Self {
+            expecting,
+            value: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<'de, T> Visitor<'de> for PresentationVisitor<T>
+where
+    T: core::str::FromStr,
+    T::Err: fmt::Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+/// RDATA types with a master file format are written as a single string
+macro_rules! presentation_serde {
+    ($($rdata:ident),*) => {
+        $(
+            impl Serialize for $rdata {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $rdata {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserializer.deserialize_str(PresentationVisitor::new(concat!(
+                        stringify!($rdata),
+                        " RDATA in presentation format"
+                    )))
+                }
+            }
+        )*
+    };
+}
+
+presentation_serde!(
+    A, AAAA, CNAME, HINFO, MB, MD, MF, MG, MINFO, MR, MX, NS, NULL, PTR, RRSIG, SOA, TXT, WKS
+);
+
+/// OPT and unknown RDATA can only be read back as part of a [`Record`]
+impl Serialize for OPT {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Unknown {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for RecordData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// RFC8427 flags are written as 0 or 1 but booleans are accepted as well
+struct Flag(bool);
+
+impl<'de> Deserialize<'de> for Flag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FlagVisitor;
+
+        impl<'de> Visitor<'de> for FlagVisitor {
+            type Value = Flag;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("0, 1 or a boolean")
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(Flag(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                match v {
+                    0 => Ok(Flag(false)),
+                    1 => Ok(Flag(true)),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                match v {
+                    0 => Ok(Flag(false)),
+                    1 => Ok(Flag(true)),
+                    _ => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(FlagVisitor)
+    }
+}
+
+fn encode_hex<T: Encode, E: ser::Error>(value: &T) -> Result<String, E> {
+    let mut e = Encoder::uncompressed();
+    value.encode(&mut e).map_err(E::custom)?;
+    Ok(to_hex(&e.finish()))
+}
+
+fn decode_hex<E: de::Error>(hex: &str) -> Result<Vec<u8>, E> {
+    from_hex(hex).map_err(E::custom)
+}
+
+/// [RFC8427 2.1: Message and the First Two Parts of Its Header](https://datatracker.ietf.org/doc/html/rfc8427#section-2.1)
+fn serialize_header_fields<M: SerializeMap>(
+    map: &mut M,
+    header: &Header,
+    counts: [u16; 4],
+) -> Result<(), M::Error> {
+    map.serialize_entry("ID", &header.id)?;
+    map.serialize_entry("QR", &(header.qr as u8))?;
+    map.serialize_entry("Opcode", &u8::from(header.opcode))?;
+    map.serialize_entry("AA", &(header.aa as u8))?;
+    map.serialize_entry("TC", &(header.tc as u8))?;
+    map.serialize_entry("RD", &(header.rd as u8))?;
+    map.serialize_entry("RA", &(header.ra as u8))?;
+    map.serialize_entry("AD", &(header.ad as u8))?;
+    map.serialize_entry("CD", &(header.cd as u8))?;
+    map.serialize_entry("RCODE", &(u8::from(header.rcode) & 0xF))?;
+    map.serialize_entry("QDCOUNT", &counts[0])?;
+    map.serialize_entry("ANCOUNT", &counts[1])?;
+    map.serialize_entry("NSCOUNT", &counts[2])?;
+    map.serialize_entry("ARCOUNT", &counts[3])
+}
+
+/// Reads the header members of a message object, returns false for members of other parts
+fn deserialize_header_field<'de, A: MapAccess<'de>>(
+    map: &mut A,
+    header: &mut Header,
+    counts: &mut [Option<u16>; 4],
+    key: &str,
+) -> Result<bool, A::Error> {
+    match key {
+        "ID" => header.id = map.next_value()?,
+        "QR" => header.qr = map.next_value::<Flag>()?.0,
+        "Opcode" => header.opcode = map.next_value::<u8>()?.into(),
+        "AA" => header.aa = map.next_value::<Flag>()?.0,
+        "TC" => header.tc = map.next_value::<Flag>()?.0,
+        "RD" => header.rd = map.next_value::<Flag>()?.0,
+        "RA" => header.ra = map.next_value::<Flag>()?.0,
+        "AD" => header.ad = map.next_value::<Flag>()?.0,
+        "CD" => header.cd = map.next_value::<Flag>()?.0,
+        "RCODE" => header.rcode = map.next_value::<u8>()?.into(),
+        "QDCOUNT" => counts[0] = Some(map.next_value()?),
+        "ANCOUNT" => counts[1] = Some(map.next_value()?),
+        "NSCOUNT" => counts[2] = Some(map.next_value()?),
+        "ARCOUNT" => counts[3] = Some(map.next_value()?),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn serialize_header<S: Serializer>(
+    header: &Header,
+    octets: bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    let counts = [
+        header.qdcount,
+        header.ancount,
+        header.nscount,
+        header.arcount,
+    ];
+    serialize_header_fields(&mut map, header, counts)?;
+    if octets {
+        map.serialize_entry("headerOctetsHEX", &encode_hex::<_, S::Error>(header)?)?;
+    }
+    map.end()
+}
+
+impl Serialize for Header {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_header(self, false, serializer)
+    }
+}
+
+impl Serialize for WithOctets<'_, Header> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_header(self.0, true, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Header {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HeaderVisitor;
+
+        impl<'de> Visitor<'de> for HeaderVisitor {
+            type Value = Header;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC8427 header object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut header = Header::new();
+                let mut counts = [None; 4];
+                let mut octets = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "headerOctetsHEX" {
+                        octets = Some(decode_hex::<A::Error>(&map.next_value::<String>()?)?);
+                    } else if !deserialize_header_field(&mut map, &mut header, &mut counts, &key)? {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+
+                if let Some(octets) = octets {
+                    return parse_complete(&octets).map_err(de::Error::custom);
+                }
+
+                header.qdcount = counts[0].unwrap_or(0);
+                header.ancount = counts[1].unwrap_or(0);
+                header.nscount = counts[2].unwrap_or(0);
+                header.arcount = counts[3].unwrap_or(0);
+                Ok(header)
+            }
+        }
+
+        deserializer.deserialize_map(HeaderVisitor)
+    }
+}
+
+/// Questions are written as resource records without TTL or RDATA
+///
+/// [RFC8427 2.2: Message Sections](https://datatracker.ietf.org/doc/html/rfc8427#section-2.2)
+impl Serialize for Question {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry("NAME", &self.qname)?;
+        map.serialize_entry("TYPE", &u16::from(self.qtype))?;
+        map.serialize_entry("TYPEname", &self.qtype.to_string())?;
+        map.serialize_entry("CLASS", &u16::from(self.qclass))?;
+        map.serialize_entry("CLASSname", &self.qclass.to_string())?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Question {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct QuestionVisitor;
+
+        impl<'de> Visitor<'de> for QuestionVisitor {
+            type Value = Question;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC8427 question object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut qname = None;
+                let mut qtype = None;
+                let mut qclass = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "NAME" => qname = Some(map.next_value()?),
+                        "TYPE" => qtype = Some(RecordQType::from(map.next_value::<u16>()?)),
+                        "TYPEname" if qtype.is_none() => {
+                            qtype = Some(
+                                map.next_value::<String>()?
+                                    .parse()
+                                    .map_err(de::Error::custom)?,
+                            )
+                        }
+                        "CLASS" => qclass = Some(RecordQClass::from(map.next_value::<u16>()?)),
+                        "CLASSname" if qclass.is_none() => {
+                            qclass = Some(
+                                map.next_value::<String>()?
+                                    .parse()
+                                    .map_err(de::Error::custom)?,
+                            )
+                        }
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(Question {
+                    qname: qname.ok_or_else(|| de::Error::missing_field("NAME"))?,
+                    qtype: qtype.ok_or_else(|| de::Error::missing_field("TYPE"))?,
+                    qclass: qclass.unwrap_or(RecordQClass::RecordClass(RecordClass::IN)),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(QuestionVisitor)
+    }
+}
+
+/// [RFC8427 2.3: Resource Records](https://datatracker.ietf.org/doc/html/rfc8427#section-2.3)
+fn serialize_record<S: Serializer>(
+    record: &Record,
+    octets: bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let (class, ttl) = match record.rdata() {
+        RecordData::OPT(opt) => (opt.udp_payload_size(), opt.record_ttl()),
+        _ => (record.class().into(), record.ttl().into()),
+    };
+    let has_presentation = !matches!(
+        record.rdata(),
+        RecordData::OPT(_) | RecordData::Unknown(_) | RecordData::NULL(_)
+    );
+
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("NAME", record.name())?;
+    map.serialize_entry("TYPE", &u16::from(record.rtype()))?;
+    map.serialize_entry("TYPEname", &record.rtype().to_string())?;
+    map.serialize_entry("CLASS", &class)?;
+    map.serialize_entry("CLASSname", &RecordClass::from(class).to_string())?;
+    map.serialize_entry("TTL", &ttl)?;
+    if has_presentation {
+        map.serialize_entry(&format!("rdata{}", record.rtype()), record.rdata())?;
+    }
+    if octets || !has_presentation {
+        let rdata = encode_hex::<_, S::Error>(record.rdata())?;
+        map.serialize_entry("RDLENGTH", &(rdata.len() / 2))?;
+        map.serialize_entry("rdataHEX", &rdata)?;
+    }
+    map.end()
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_record(self, false, serializer)
+    }
+}
+
+impl Serialize for WithOctets<'_, Record> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_record(self.0, true, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RecordVisitor;
+
+        impl<'de> Visitor<'de> for RecordVisitor {
+            type Value = Record;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC8427 resource record object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut name: Option<Name> = None;
+                let mut rtype = None;
+                let mut class = None;
+                let mut ttl = None;
+                let mut rdata_hex = None;
+                let mut rdata_presentation = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "NAME" => name = Some(map.next_value()?),
+                        "TYPE" => rtype = Some(RecordType::from(map.next_value::<u16>()?)),
+                        "TYPEname" if rtype.is_none() => {
+                            rtype = Some(
+                                map.next_value::<String>()?
+                                    .parse()
+                                    .map_err(de::Error::custom)?,
+                            )
+                        }
+                        "CLASS" => class = Some(map.next_value::<u16>()?),
+                        "CLASSname" if class.is_none() => {
+                            let parsed: RecordClass = map
+                                .next_value::<String>()?
+                                .parse()
+                                .map_err(de::Error::custom)?;
+                            class = Some(parsed.into())
+                        }
+                        "TTL" => ttl = Some(map.next_value::<u32>()?),
+                        "rdataHEX" => {
+                            rdata_hex = Some(decode_hex::<A::Error>(&map.next_value::<String>()?)?)
+                        }
+                        other if other.starts_with("rdata") => {
+                            rdata_presentation = Some(map.next_value::<String>()?)
+                        }
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let name = name.ok_or_else(|| de::Error::missing_field("NAME"))?;
+                let rtype = rtype.ok_or_else(|| de::Error::missing_field("TYPE"))?;
+                let class = class.unwrap_or(RecordClass::IN.into());
+                let ttl = ttl.unwrap_or(0);
+
+                if let RecordType::OPT = rtype {
+                    let mut e = Encoder::uncompressed();
+                    e.write_name(&name, false);
+                    e.write_u16(rtype.into());
+                    e.write_u16(class);
+                    e.write_u32(ttl);
+                    let data = rdata_hex.unwrap_or_default();
+                    e.write_u16(data.len() as u16);
+                    e.write_bytes(&data);
+                    return parse_complete(&e.finish()).map_err(de::Error::custom);
+                }
+
+                let class = RecordClass::from(class);
+                let rdata = match (rdata_hex, rdata_presentation) {
+                    (Some(hex), _) => {
+                        RecordData::from_wire(rtype, class, &hex).map_err(de::Error::custom)?
+                    }
+                    (None, Some(presentation)) => {
+                        RecordData::from_presentation(rtype, class, &presentation)
+                            .map_err(de::Error::custom)?
+                    }
+                    (None, None) => return Err(de::Error::missing_field("rdataHEX")),
+                };
+
+                Ok(Record::new(name, class, TTL::from(ttl), rdata))
+            }
+        }
+
+        deserializer.deserialize_map(RecordVisitor)
+    }
+}
+
+fn parse_complete<T: Parse>(bytes: &[u8]) -> Result<T, ParserError> {
+    T::parse(IByteInput::new(bytes))
+        .map(|(_, value)| value)
+        .map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(_) => ParserError {
+                position: bytes.len(),
+                nom_kind: Some(nom::error::ErrorKind::Eof),
+                err_type: None,
+            },
+        })
+}
+
+/// [RFC8427 2.1: Message and the First Two Parts of Its Header](https://datatracker.ietf.org/doc/html/rfc8427#section-2.1)
+fn serialize_message<S: Serializer>(
+    message: &Message,
+    octets: bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let count = |len: usize| u16::try_from(len).map_err(ser::Error::custom);
+    let counts = [
+        count(message.questions.len())?,
+        count(message.answers.len())?,
+        count(message.authorities.len())?,
+        count(message.additionals.len())?,
+    ];
+
+    let mut map = serializer.serialize_map(None)?;
+    serialize_header_fields(&mut map, &message.header, counts)?;
+
+    if let [question] = message.questions.as_slice() {
+        map.serialize_entry("QNAME", &question.qname)?;
+        map.serialize_entry("QTYPE", &u16::from(question.qtype))?;
+        map.serialize_entry("QTYPEname", &question.qtype.to_string())?;
+        map.serialize_entry("QCLASS", &u16::from(question.qclass))?;
+        map.serialize_entry("QCLASSname", &question.qclass.to_string())?;
+    }
+    map.serialize_entry("questionRRs", &message.questions)?;
+
+    map.serialize_entry("answerRRs", &section(&message.answers, octets))?;
+    map.serialize_entry("authorityRRs", &section(&message.authorities, octets))?;
+    map.serialize_entry("additionalRRs", &section(&message.additionals, octets))?;
+
+    if octets {
+        let bytes = message.to_bytes().map_err(ser::Error::custom)?;
+        map.serialize_entry("messageOctetsHEX", &to_hex(&bytes))?;
+    }
+    map.end()
+}
+
+struct RecordJson<'a>(&'a Record, bool);
+
+fn section(records: &[Record], octets: bool) -> Vec<RecordJson<'_>> {
+    records
+        .iter()
+        .map(|record| RecordJson(record, octets))
+        .collect()
+}
+
+impl Serialize for RecordJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_record(self.0, self.1, serializer)
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_message(self, false, serializer)
+    }
+}
+
+impl Serialize for WithOctets<'_, Message> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_message(self.0, true, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MessageVisitor;
+
+        impl<'de> Visitor<'de> for MessageVisitor {
+            type Value = Message;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC8427 message object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut message = Message::new();
+                let mut counts = [None; 4];
+                let mut octets = None;
+                let mut questions = None;
+                let mut qname = None;
+                let mut qtype = None;
+                let mut qclass = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    if deserialize_header_field(&mut map, &mut message.header, &mut counts, &key)? {
+                        continue;
+                    }
+
+                    match key.as_str() {
+                        "messageOctetsHEX" => {
+                            octets = Some(decode_hex::<A::Error>(&map.next_value::<String>()?)?)
+                        }
+                        "questionRRs" => questions = Some(map.next_value()?),
+                        "answerRRs" => message.answers = map.next_value()?,
+                        "authorityRRs" => message.authorities = map.next_value()?,
+                        "additionalRRs" => message.additionals = map.next_value()?,
+                        "QNAME" => qname = Some(map.next_value()?),
+                        "QTYPE" => qtype = Some(RecordQType::from(map.next_value::<u16>()?)),
+                        "QCLASS" => qclass = Some(RecordQClass::from(map.next_value::<u16>()?)),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                if let Some(octets) = octets {
+                    return Message::from_bytes(&octets).map_err(de::Error::custom);
+                }
+
+                message.questions = match (questions, qname) {
+                    (Some(questions), _) => questions,
+                    (None, Some(qname)) => vec![Question {
+                        qname,
+                        qtype: qtype.ok_or_else(|| de::Error::missing_field("QTYPE"))?,
+                        qclass: qclass.unwrap_or(RecordQClass::RecordClass(RecordClass::IN)),
+                    }],
+                    (None, None) => Vec::new(),
+                };
+
+                let header = &mut message.header;
+                header.qdcount = counts[0].unwrap_or(message.questions.len() as u16);
+                header.ancount = counts[1].unwrap_or(message.answers.len() as u16);
+                header.nscount = counts[2].unwrap_or(message.authorities.len() as u16);
+                header.arcount = counts[3].unwrap_or(message.additionals.len() as u16);
+                Ok(message)
+            }
+        }
+
+        deserializer.deserialize_map(MessageVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::net::Ipv4Addr;
+
+    use serde_json::json;
+
+    use crate::{
+        header::{Header, RCode},
+        message::Message,
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            data::RecordData,
+            rdata::{A, MX, OPT, SOA},
+            record::Record,
+            types::{RecordQType, RecordType},
+        },
+        utils::TTL,
+    };
+
+    use super::WithOctets;
+
+    fn response() -> Message {
+        let mut message = Message::new();
+        message.header = Header {
+            id: 19678,
+            qr: true,
+            rd: true,
+            ra: true,
+            ..Header::new()
+        };
+        message.questions.push(Question::new(
+            "example.com.".parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+        message.answers.push(Record::new(
+            "example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(3600),
+            RecordData::A(A::new(Ipv4Addr::new(192, 0, 2, 1))),
+        ));
+        message.answers.push(Record::new(
+            "example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(3600),
+            RecordData::MX(MX::new(10, "mail.example.com.".parse().unwrap())),
+        ));
+        message.authorities.push(Record::new(
+            "example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(300),
+            RecordData::SOA(
+                "ns.example.com. hostmaster.example.com. 1 2 3 4 5"
+                    .parse::<SOA>()
+                    .unwrap(),
+            ),
+        ));
+        let mut opt = OPT::new(1232);
+        opt.set_dnssec_ok(true);
+        message.additionals.push(Record::opt(opt));
+        message.header.qdcount = 1;
+        message.header.ancount = 2;
+        message.header.nscount = 1;
+        message.header.arcount = 1;
+        message
+    }
+
+    #[test]
+    fn test_message_json() {
+        let message = response();
+        let value = serde_json::to_value(&message).unwrap();
+
+        assert_eq!(value["ID"], json!(19678));
+        assert_eq!(value["QR"], json!(1));
+        assert_eq!(value["QNAME"], json!("example.com."));
+        assert_eq!(value["QTYPEname"], json!("A"));
+        assert_eq!(value["answerRRs"][0]["rdataA"], json!("192.0.2.1"));
+        assert_eq!(
+            value["answerRRs"][1]["rdataMX"],
+            json!("10 mail.example.com.")
+        );
+        assert_eq!(
+            value["authorityRRs"][0]["rdataSOA"],
+            json!("ns.example.com. hostmaster.example.com. 1 2 3 4 5")
+        );
+        assert_eq!(value["additionalRRs"][0]["CLASS"], json!(1232));
+        assert_eq!(value["additionalRRs"][0]["TTL"], json!(0x8000));
+        assert!(value.get("messageOctetsHEX").is_none());
+        assert!(value["answerRRs"][0].get("rdataHEX").is_none());
+
+        let parsed: Message = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, message);
+    }
+
+    #[test]
+    fn test_message_json_with_octets() {
+        let message = response();
+        let value = serde_json::to_value(WithOctets(&message)).unwrap();
+        let bytes = message.to_bytes().unwrap();
+
+        assert_eq!(value["answerRRs"][0]["rdataHEX"], json!("C0000201"));
+        assert_eq!(value["answerRRs"][0]["RDLENGTH"], json!(4));
+        assert_eq!(
+            value["messageOctetsHEX"],
+            json!(crate::utils::to_hex(&bytes))
+        );
+
+        let parsed: Message = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, message);
+    }
+
+    #[test]
+    fn test_rfc8427_example() {
+        // RFC8427 4.1: Example of the Format of a DNS Query
+        let value = json!({
+            "ID": 19678, "QR": 0, "Opcode": 0,
+            "AA": 0, "TC": 0, "RD": 0, "RA": 0, "AD": 0, "CD": 0, "RCODE": 0,
+            "QDCOUNT": 1, "ANCOUNT": 0, "NSCOUNT": 0, "ARCOUNT": 0,
+            "QNAME": "example.com", "QTYPE": 1, "QCLASS": 1
+        });
+        let message: Message = serde_json::from_value(value).unwrap();
+
+        assert_eq!(message.header.id, 19678);
+        assert_eq!(message.header.rcode, RCode::NoError);
+        assert_eq!(message.questions.len(), 1);
+        assert_eq!(message.questions[0].qname.to_string(), "example.com");
+        assert_eq!(
+            message.questions[0].qtype,
+            RecordQType::RecordType(RecordType::A)
+        );
+    }
+
+    #[test]
+    fn test_unknown_rdata_uses_hex() {
+        let record: Record = serde_json::from_value(json!({
+            "NAME": "example.com.", "TYPE": 99, "CLASS": 1, "TTL": 60,
+            "rdataHEX": "0474657374"
+        }))
+        .unwrap();
+        assert_eq!(record.rtype(), RecordType::Unknown(99));
+
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(value["TYPEname"], json!("TYPE99"));
+        assert_eq!(value["rdataHEX"], json!("0474657374"));
+        assert_eq!(serde_json::from_value::<Record>(value).unwrap(), record);
+    }
+}
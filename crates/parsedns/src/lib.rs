@@ -1,7 +1,21 @@
-mod error;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+pub mod dnssec;
+mod encoder;
+pub mod error;
 pub mod header;
 mod indexed_input;
-mod question;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod message;
+pub mod net;
+mod presentation;
+pub mod question;
 pub mod rr;
 mod traits;
 pub mod utils;
+pub mod validate;
@@ -0,0 +1,434 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use nom::{error::ErrorKind, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError},
+    header::{Header, OpCode, RCode},
+    indexed_input::IByteInput,
+    question::Question,
+    rr::{
+        data::RecordData,
+        rdata::{EdnsOption, OPT},
+        record::Record,
+    },
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +---------------------+
+///     |        Header       |
+///     +---------------------+
+///     |       Question      | the question for the name server
+///     +---------------------+
+///     |        Answer       | RRs answering the question
+///     +---------------------+
+///     |      Authority      | RRs pointing toward an authority
+///     +---------------------+
+///     |      Additional     | RRs holding additional information
+///     +---------------------+
+/// ```
+///
+/// [RFC1035 4.1: Format](https://datatracker.ietf.org/doc/html/rfc1035#section-4.1)
+///
+/// The section counts in `header` are filled in from the sections when encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Message {
+    pub header: Header,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authorities: Vec<Record>,
+    pub additionals: Vec<Record>,
+}
+
+/// The sections of a message that hold resource records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Answer,
+    Authority,
+    Additional,
+}
+
+impl Section {
+    pub const ALL: [Section; 3] = [Self::Answer, Self::Authority, Self::Additional];
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Answer => write!(f, "answer"),
+            Self::Authority => write!(f, "authority"),
+            Self::Additional => write!(f, "additional"),
+        }
+    }
+}
+
+impl Message {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a complete message from the wire
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParserError> {
+        Self::parse(IByteInput::new(bytes))
+            .map(|(_, message)| message)
+            .map_err(|e| match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => e,
+                nom::Err::Incomplete(_) => ParserError {
+                    position: bytes.len(),
+                    nom_kind: Some(ErrorKind::Eof),
+                    err_type: None,
+                },
+            })
+    }
+
+    /// The header and first question of a message that could not be parsed as a whole, so the
+    /// error can still be answered. The question is None when it is missing or malformed.
+    pub fn header_and_question(bytes: &[u8]) -> Option<(Header, Option<Question>)> {
+        let (i, header) = Header::parse(IByteInput::new(bytes)).ok()?;
+        let question = match header.qdcount {
+            0 => None,
+            _ => Question::parse(i).ok().map(|(_, question)| question),
+        };
+        Some((header, question))
+    }
+
+    /// Encode the message with name compression
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut e = Encoder::new();
+        self.encode(&mut e)?;
+        Ok(e.finish())
+    }
+
+    pub fn records(&self, section: Section) -> &[Record] {
+        match section {
+            Section::Answer => &self.answers,
+            Section::Authority => &self.authorities,
+            Section::Additional => &self.additionals,
+        }
+    }
+
+    pub fn records_mut(&mut self, section: Section) -> &mut Vec<Record> {
+        match section {
+            Section::Answer => &mut self.answers,
+            Section::Authority => &mut self.authorities,
+            Section::Additional => &mut self.additionals,
+        }
+    }
+
+    /// The EDNS pseudo-record of the additional section
+    pub fn opt(&self) -> Option<&OPT> {
+        self.additionals
+            .iter()
+            .find_map(|record| match record.rdata() {
+                RecordData::OPT(opt) => Some(opt),
+                _ => None,
+            })
+    }
+
+    fn parse_section<'a, T: Parse>(
+        mut i: IByteInput<'a>,
+        count: u16,
+    ) -> IResult<IByteInput<'a>, Vec<T>, ParserError> {
+        // Counts are untrusted, each entry takes at least one byte
+        let mut section = Vec::with_capacity((count as usize).min(i.len()));
+        for _ in 0..count {
+            let entry;
+            (i, entry) = T::parse(i)?;
+            section.push(entry);
+        }
+        Ok((i, section))
+    }
+}
+
+impl Parse for Message {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        let (i, header) = Header::parse(i)?;
+        let (i, questions) = Self::parse_section(i, header.qdcount)?;
+        let (i, answers) = Self::parse_section(i, header.ancount)?;
+        let (i, authorities) = Self::parse_section(i, header.nscount)?;
+        let (i, additionals) = Self::parse_section(i, header.arcount)?;
+
+        Ok((
+            i,
+            Self {
+                header,
+                questions,
+                answers,
+                authorities,
+                additionals,
+            },
+        ))
+    }
+}
+
+/// The header with its counts taken from the sections and the RCODE extended by EDNS
+fn display_header(message: &Message) -> Header {
+    let mut header = message.header.clone();
+    header.qdcount = message.questions.len() as u16;
+    header.ancount = message.answers.len() as u16;
+    header.nscount = message.authorities.len() as u16;
+    header.arcount = message.additionals.len() as u16;
+
+    if let Some(opt) = message.opt() {
+        let rcode = (opt.extended_rcode() as u16) << 4 | (u8::from(header.rcode) & 0xF) as u16;
+        header.rcode = match u8::try_from(rcode) {
+            Ok(rcode) => RCode::from(rcode),
+            Err(_) => RCode::Unknown(u8::MAX),
+        };
+    }
+    header
+}
+
+/// Options that `dig` names, everything else is shown by code with its data in hex
+fn fmt_edns_option(f: &mut fmt::Formatter<'_>, option: &EdnsOption) -> fmt::Result {
+    let hex = crate::utils::to_hex(option.data());
+    match option.code() {
+        // Name server identifier [RFC5001](https://datatracker.ietf.org/doc/html/rfc5001)
+        3 => writeln!(f, "; NSID: {}", hex),
+        // [RFC7873](https://datatracker.ietf.org/doc/html/rfc7873)
+        10 => writeln!(f, "; COOKIE: {}", hex),
+        // Extended DNS errors [RFC8914](https://datatracker.ietf.org/doc/html/rfc8914)
+        15 if option.data().len() >= 2 => {
+            let code = u16::from_be_bytes([option.data()[0], option.data()[1]]);
+            let text = String::from_utf8_lossy(&option.data()[2..]);
+            if text.is_empty() {
+                writeln!(f, "; EDE: {}", code)
+            } else {
+                writeln!(f, "; EDE: {} (\"{}\")", code, text)
+            }
+        }
+        code => writeln!(f, "; OPT={}: {}", code, hex),
+    }
+}
+
+fn fmt_section(f: &mut fmt::Formatter<'_>, name: &str, records: &[&Record]) -> fmt::Result {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "\n;; {} SECTION:", name)?;
+    for record in records {
+        writeln!(f, "{}", record)?;
+    }
+    Ok(())
+}
+
+/// Formats the message the way `dig` prints a response: the header, the OPT pseudosection and
+/// then every non-empty section with its records in master file format
+///
+/// ```text
+/// ;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 4660
+/// ;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 1
+///
+/// ;; OPT PSEUDOSECTION:
+/// ; EDNS: version: 0, flags: do; udp: 1232
+///
+/// ;; QUESTION SECTION:
+/// ;example.com.                   IN      A
+///
+/// ;; ANSWER SECTION:
+/// example.com.            3600    IN      A       93.184.216.34
+/// ```
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", display_header(self))?;
+
+        if let Some(opt) = self.opt() {
+            writeln!(f, "\n;; OPT PSEUDOSECTION:")?;
+            write!(f, "; EDNS: version: {}, flags:", opt.version())?;
+            if opt.dnssec_ok() {
+                write!(f, " do")?;
+            }
+            if opt.z() != 0 {
+                write!(f, " MBZ: {:#06x}", opt.z())?;
+            }
+            writeln!(f, "; udp: {}", opt.udp_payload_size())?;
+            for option in opt.options() {
+                fmt_edns_option(f, option)?;
+            }
+        }
+
+        let (question, answer, authority) = match self.header.opcode {
+            OpCode::Update => ("ZONE", "PREREQUISITE", "UPDATE"),
+            _ => ("QUESTION", "ANSWER", "AUTHORITY"),
+        };
+
+        if !self.questions.is_empty() {
+            writeln!(f, "\n;; {} SECTION:", question)?;
+            for q in &self.questions {
+                writeln!(f, "{}", q)?;
+            }
+        }
+
+        let additionals: Vec<&Record> = self
+            .additionals
+            .iter()
+            .filter(|record| !matches!(record.rdata(), RecordData::OPT(_)))
+            .collect();
+
+        fmt_section(f, answer, &self.answers.iter().collect::<Vec<_>>())?;
+        fmt_section(f, authority, &self.authorities.iter().collect::<Vec<_>>())?;
+        fmt_section(f, "ADDITIONAL", &additionals)
+    }
+}
+
+fn section_count(len: usize) -> Result<u16, EncodeError> {
+    u16::try_from(len).map_err(|_| EncodeError::SectionTooLong(len))
+}
+
+impl Encode for Message {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        let mut header = self.header.clone();
+        header.qdcount = section_count(self.questions.len())?;
+        header.ancount = section_count(self.answers.len())?;
+        header.nscount = section_count(self.authorities.len())?;
+        header.arcount = section_count(self.additionals.len())?;
+        header.encode(e)?;
+
+        for question in &self.questions {
+            question.encode(e)?;
+        }
+        for record in self
+            .answers
+            .iter()
+            .chain(&self.authorities)
+            .chain(&self.additionals)
+        {
+            record.encode(e)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::net::Ipv4Addr;
+
+    use crate::{
+        header::OpCode,
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            data::RecordData,
+            rdata::{A, CNAME, OPT},
+            record::Record,
+            types::{RecordQType, RecordType},
+        },
+        utils::TTL,
+    };
+
+    use super::Message;
+
+    #[test]
+    fn test_parse_response() {
+        // dig www.example.com A, answered with a CNAME chain and an OPT record
+        let bytes = [
+            0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, // header
+            0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c',
+            b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01, // question
+            0xC0, 0x0C, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x0E, 0x10, 0x00, 0x02, 0xC0,
+            0x10, // www CNAME example.com
+            0xC0, 0x10, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x0E, 0x10, 0x00, 0x04, 0x5D, 0xB8,
+            0xD8, 0x22, // example.com A
+            0x00, 0x00, 0x29, 0x04, 0xD0, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, // OPT
+        ];
+
+        let message = Message::from_bytes(&bytes).unwrap();
+        assert_eq!(message.header.id, 0x1234);
+        assert_eq!(message.header.opcode, OpCode::Query);
+        assert!(message.header.qr && message.header.rd && message.header.ra);
+        assert_eq!(
+            message.questions[0].qname,
+            "www.example.com.".parse().unwrap()
+        );
+        assert_eq!(
+            message.answers[0].rdata(),
+            &RecordData::CNAME(CNAME::new("example.com.".parse().unwrap()))
+        );
+        assert_eq!(
+            message.answers[1].rdata(),
+            &RecordData::A(A::new(Ipv4Addr::new(93, 184, 216, 34)))
+        );
+
+        let opt = message.opt().unwrap();
+        assert_eq!(opt.udp_payload_size(), 1232);
+        assert!(opt.dnssec_ok());
+
+        assert_eq!(message.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_dig_format() {
+        let mut message = Message::new();
+        message.header.id = 4660;
+        message.header.qr = true;
+        message.header.rd = true;
+        message.header.ra = true;
+        message.questions.push(Question::new(
+            "example.com.".parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+        message.answers.push(Record::new(
+            "example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(3600),
+            RecordData::A(A::new(Ipv4Addr::new(93, 184, 216, 34))),
+        ));
+        message.answers.push(Record::new(
+            "a.very.long.name.example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(60),
+            RecordData::CNAME(CNAME::new("example.com.".parse().unwrap())),
+        ));
+        let mut opt = OPT::new(1232);
+        opt.set_dnssec_ok(true);
+        message.additionals.push(Record::opt(opt));
+
+        assert_eq!(
+            message.to_string(),
+            ";; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 4660\n\
+             ;; flags: qr rd ra; QUERY: 1, ANSWER: 2, AUTHORITY: 0, ADDITIONAL: 1\n\
+             \n\
+             ;; OPT PSEUDOSECTION:\n\
+             ; EDNS: version: 0, flags: do; udp: 1232\n\
+             \n\
+             ;; QUESTION SECTION:\n\
+             ;example.com.\t\t\tIN\tA\n\
+             \n\
+             ;; ANSWER SECTION:\n\
+             example.com.\t\t3600\tIN\tA\t93.184.216.34\n\
+             a.very.long.name.example.com.\t60\tIN\tCNAME\texample.com.\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut message = Message::new();
+        message.header.id = 7;
+        message.questions.push(Question::new(
+            "example.org.".parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+        message.answers.push(Record::new(
+            "example.org.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(60),
+            RecordData::A(A::new(Ipv4Addr::LOCALHOST)),
+        ));
+        message.additionals.push(Record::opt(OPT::new(4096)));
+
+        let bytes = message.to_bytes().unwrap();
+        let parsed = Message::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.header.qdcount, 1);
+        assert_eq!(parsed.header.ancount, 1);
+        assert_eq!(parsed.header.arcount, 1);
+        assert_eq!(parsed.questions, message.questions);
+        assert_eq!(parsed.answers, message.answers);
+        assert_eq!(parsed.additionals, message.additionals);
+    }
+}
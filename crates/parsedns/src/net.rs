@@ -0,0 +1,331 @@
+//! IP addresses carried in A, AAAA and WKS records.
+//!
+//! With the `std` feature these are the standard library types. Without it the crate provides
+//! its own minimal types with the same names and the subset of the standard API that the parser
+//! and the master file format need.
+
+#[cfg(feature = "std")]
+pub use std::net::{AddrParseError, Ipv4Addr, Ipv6Addr};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{AddrParseError, Ipv4Addr, Ipv6Addr};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use core::{fmt, str::FromStr};
+
+    /// An IP address could not be read from its text form
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct AddrParseError(());
+
+    impl fmt::Display for AddrParseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "invalid IP address syntax")
+        }
+    }
+
+    /// An IPv4 address, stored in network byte order
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Ipv4Addr([u8; 4]);
+
+    impl Ipv4Addr {
+        pub const LOCALHOST: Self = Self([127, 0, 0, 1]);
+        pub const UNSPECIFIED: Self = Self([0, 0, 0, 0]);
+
+        pub const fn new(a: u8, b: u8, c: u8, d: u8) -> Self {
+            Self([a, b, c, d])
+        }
+
+        pub const fn octets(&self) -> [u8; 4] {
+            self.0
+        }
+    }
+
+    impl From<[u8; 4]> for Ipv4Addr {
+        fn from(octets: [u8; 4]) -> Self {
+            Self(octets)
+        }
+    }
+
+    impl From<u32> for Ipv4Addr {
+        fn from(ip: u32) -> Self {
+            Self(ip.to_be_bytes())
+        }
+    }
+
+    impl From<Ipv4Addr> for u32 {
+        fn from(ip: Ipv4Addr) -> Self {
+            u32::from_be_bytes(ip.0)
+        }
+    }
+
+    impl fmt::Display for Ipv4Addr {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let [a, b, c, d] = self.0;
+            write!(f, "{}.{}.{}.{}", a, b, c, d)
+        }
+    }
+
+    impl fmt::Debug for Ipv4Addr {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(self, f)
+        }
+    }
+
+    /// Dotted decimal with exactly four parts and no leading zeros
+    impl FromStr for Ipv4Addr {
+        type Err = AddrParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut octets = [0; 4];
+            let mut parts = s.split('.');
+            for octet in octets.iter_mut() {
+                let part = parts.next().ok_or(AddrParseError(()))?;
+                let valid = !part.is_empty()
+                    && part.len() <= 3
+                    && part.bytes().all(|b| b.is_ascii_digit())
+                    && (part.len() == 1 || !part.starts_with('0'));
+                if !valid {
+                    return Err(AddrParseError(()));
+                }
+                *octet = part.parse().map_err(|_| AddrParseError(()))?;
+            }
+
+            match parts.next() {
+                Some(_) => Err(AddrParseError(())),
+                None => Ok(Self(octets)),
+            }
+        }
+    }
+
+    /// An IPv6 address, stored in network byte order
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Ipv6Addr([u8; 16]);
+
+    impl Ipv6Addr {
+        pub const LOCALHOST: Self = Self([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        pub const UNSPECIFIED: Self = Self([0; 16]);
+
+        #[allow(clippy::too_many_arguments)]
+        pub const fn new(a: u16, b: u16, c: u16, d: u16, e: u16, f: u16, g: u16, h: u16) -> Self {
+            let [a0, a1] = a.to_be_bytes();
+            let [b0, b1] = b.to_be_bytes();
+            let [c0, c1] = c.to_be_bytes();
+            let [d0, d1] = d.to_be_bytes();
+            let [e0, e1] = e.to_be_bytes();
+            let [f0, f1] = f.to_be_bytes();
+            let [g0, g1] = g.to_be_bytes();
+            let [h0, h1] = h.to_be_bytes();
+            Self([
+                a0, a1, b0, b1, c0, c1, d0, d1, e0, e1, f0, f1, g0, g1, h0, h1,
+            ])
+        }
+
+        pub const fn octets(&self) -> [u8; 16] {
+            self.0
+        }
+
+        pub fn segments(&self) -> [u16; 8] {
+            let mut segments = [0; 8];
+            for (segment, pair) in segments.iter_mut().zip(self.0.chunks_exact(2)) {
+                *segment = u16::from_be_bytes([pair[0], pair[1]]);
+            }
+            segments
+        }
+
+        fn from_segments(segments: [u16; 8]) -> Self {
+            let mut octets = [0; 16];
+            for (pair, segment) in octets.chunks_exact_mut(2).zip(segments) {
+                pair.copy_from_slice(&segment.to_be_bytes());
+            }
+            Self(octets)
+        }
+    }
+
+    impl From<[u8; 16]> for Ipv6Addr {
+        fn from(octets: [u8; 16]) -> Self {
+            Self(octets)
+        }
+    }
+
+    impl From<[u16; 8]> for Ipv6Addr {
+        fn from(segments: [u16; 8]) -> Self {
+            Self::from_segments(segments)
+        }
+    }
+
+    impl From<u128> for Ipv6Addr {
+        fn from(ip: u128) -> Self {
+            Self(ip.to_be_bytes())
+        }
+    }
+
+    impl From<Ipv6Addr> for u128 {
+        fn from(ip: Ipv6Addr) -> Self {
+            u128::from_be_bytes(ip.0)
+        }
+    }
+
+    /// The recommended text form: lowercase hex without leading zeros and the longest run of two
+    /// or more zero fields replaced by `::`, with IPv4-mapped addresses in dotted decimal
+    ///
+    /// [RFC5952 4: A Recommendation for IPv6 Text Representation](https://datatracker.ietf.org/doc/html/rfc5952#section-4)
+    impl fmt::Display for Ipv6Addr {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let segments = self.segments();
+            if let [0, 0, 0, 0, 0, 0xFFFF, _, _] = segments {
+                let [.., a, b, c, d] = self.0;
+                return write!(f, "::ffff:{}", Ipv4Addr::new(a, b, c, d));
+            }
+
+            // The first longest run of zero fields
+            let (mut zeros_start, mut zeros_len) = (0, 0);
+            let mut run_start = 0;
+            for (idx, &segment) in segments.iter().enumerate() {
+                if segment != 0 {
+                    run_start = idx + 1;
+                } else if idx + 1 - run_start > zeros_len {
+                    zeros_start = run_start;
+                    zeros_len = idx + 1 - run_start;
+                }
+            }
+
+            let write_fields = |f: &mut fmt::Formatter<'_>, fields: &[u16]| -> fmt::Result {
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ":")?;
+                    }
+                    write!(f, "{:x}", field)?;
+                }
+                Ok(())
+            };
+
+            if zeros_len < 2 {
+                return write_fields(f, &segments);
+            }
+            write_fields(f, &segments[..zeros_start])?;
+            write!(f, "::")?;
+            write_fields(f, &segments[zeros_start + zeros_len..])
+        }
+    }
+
+    impl fmt::Debug for Ipv6Addr {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(self, f)
+        }
+    }
+
+    /// Reads the fields on one side of `::` into `segments`, returning how many were read. The
+    /// last field may be an IPv4 address in dotted decimal, taking the place of two fields.
+    fn parse_fields(s: &str, segments: &mut [u16]) -> Result<usize, AddrParseError> {
+        if s.is_empty() {
+            return Ok(0);
+        }
+
+        let mut len = 0;
+        let mut fields = s.split(':').peekable();
+        while let Some(field) = fields.next() {
+            if fields.peek().is_none() && field.contains('.') {
+                let [a, b, c, d] = field.parse::<Ipv4Addr>()?.octets();
+                let pair = segments.get_mut(len..len + 2).ok_or(AddrParseError(()))?;
+                pair.copy_from_slice(&[u16::from_be_bytes([a, b]), u16::from_be_bytes([c, d])]);
+                return Ok(len + 2);
+            }
+
+            let valid = !field.is_empty()
+                && field.len() <= 4
+                && field.bytes().all(|b| b.is_ascii_hexdigit());
+            if !valid {
+                return Err(AddrParseError(()));
+            }
+            let segment = segments.get_mut(len).ok_or(AddrParseError(()))?;
+            *segment = u16::from_str_radix(field, 16).map_err(|_| AddrParseError(()))?;
+            len += 1;
+        }
+        Ok(len)
+    }
+
+    /// Text form of [RFC4291 2.2: Text Representation of Addresses](https://datatracker.ietf.org/doc/html/rfc4291#section-2.2)
+    impl FromStr for Ipv6Addr {
+        type Err = AddrParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut segments = [0; 8];
+            match s.split_once("::") {
+                None => {
+                    if parse_fields(s, &mut segments)? != segments.len() {
+                        return Err(AddrParseError(()));
+                    }
+                }
+                Some((head, tail)) => {
+                    let head_len = parse_fields(head, &mut segments)?;
+
+                    // `::` stands for at least one zero field
+                    let mut tail_segments = [0; 7];
+                    let tail_len = parse_fields(tail, &mut tail_segments)?;
+                    if head_len + tail_len > 7 {
+                        return Err(AddrParseError(()));
+                    }
+                    segments[8 - tail_len..].copy_from_slice(&tail_segments[..tail_len]);
+                }
+            }
+            Ok(Self::from_segments(segments))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Ipv4Addr, Ipv6Addr};
+
+        #[test]
+        fn test_ipv4_text() {
+            let address: Ipv4Addr = "192.0.2.1".parse().unwrap();
+            assert_eq!(address, Ipv4Addr::new(192, 0, 2, 1));
+            assert_eq!(u32::from(address), 0xC000_0201);
+            assert_eq!(address.to_string(), "192.0.2.1");
+
+            assert!("192.0.2".parse::<Ipv4Addr>().is_err());
+            assert!("192.0.2.1.1".parse::<Ipv4Addr>().is_err());
+            assert!("192.0.2.256".parse::<Ipv4Addr>().is_err());
+            assert!("192.0.02.1".parse::<Ipv4Addr>().is_err());
+        }
+
+        #[test]
+        fn test_ipv6_text() {
+            let cases = [
+                (
+                    "2001:db8::1",
+                    Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+                ),
+                ("::", Ipv6Addr::UNSPECIFIED),
+                ("::1", Ipv6Addr::LOCALHOST),
+                ("fe80::", Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0)),
+                (
+                    "2001:db8:0:1:1:1:1:1",
+                    Ipv6Addr::new(0x2001, 0xdb8, 0, 1, 1, 1, 1, 1),
+                ),
+                ("2001:0:0:1::1", Ipv6Addr::new(0x2001, 0, 0, 1, 0, 0, 0, 1)),
+                (
+                    "::ffff:192.0.2.1",
+                    Ipv6Addr::new(0, 0, 0, 0, 0, 0xffff, 0xc000, 0x201),
+                ),
+            ];
+            for (text, address) in cases {
+                assert_eq!(text.parse::<Ipv6Addr>().unwrap(), address);
+                assert_eq!(address.to_string(), text);
+            }
+
+            assert_eq!(
+                "2001:DB8:0:0:0:0:0:1"
+                    .parse::<Ipv6Addr>()
+                    .unwrap()
+                    .to_string(),
+                "2001:db8::1"
+            );
+            assert!("2001:db8::1::1".parse::<Ipv6Addr>().is_err());
+            assert!("2001:db8:0:0:0:0:0:0:1".parse::<Ipv6Addr>().is_err());
+            assert!("1:2:3:4:5:6:7::8".parse::<Ipv6Addr>().is_err());
+            assert!("12345::".parse::<Ipv6Addr>().is_err());
+        }
+    }
+}
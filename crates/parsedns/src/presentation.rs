@@ -0,0 +1,176 @@
+use alloc::{string::ToString, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::{
+    error::PresentationError,
+    utils::{from_hex, to_hex},
+};
+
+/// Splits the master file form of RDATA into whitespace separated fields. A `\` escapes the
+/// character after it, so escaped whitespace stays inside the field, as does whitespace between
+/// double quotes.
+///
+/// [RFC1035 5.1: Master file format](https://datatracker.ietf.org/doc/html/rfc1035#section-5.1)
+pub(crate) struct Tokens<'a> {
+    s: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(s: &'a str) -> Self {
+        Self { s }
+    }
+
+    pub fn next_token(&mut self) -> Result<&'a str, PresentationError> {
+        let s = self.s.trim_start();
+        if s.is_empty() {
+            return Err(PresentationError::UnexpectedEnd);
+        }
+
+        let (mut escaped, mut quoted) = (false, false);
+        let end = s
+            .char_indices()
+            .find(|&(_, c)| {
+                if escaped {
+                    escaped = false;
+                    false
+                } else if c == '\\' {
+                    escaped = true;
+                    false
+                } else if c == '"' {
+                    quoted = !quoted;
+                    false
+                } else {
+                    !quoted && c.is_ascii_whitespace()
+                }
+            })
+            .map(|(idx, _)| idx)
+            .unwrap_or(s.len());
+
+        self.s = &s[end..];
+        Ok(&s[..end])
+    }
+
+    pub fn parse<T: FromStr>(&mut self) -> Result<T, PresentationError> {
+        self.next_token()?
+            .parse()
+            .map_err(|_| PresentationError::InvalidNumber)
+    }
+
+    pub fn address<T: FromStr>(&mut self) -> Result<T, PresentationError> {
+        self.next_token()?
+            .parse()
+            .map_err(|_| PresentationError::InvalidAddress)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.s.trim_start().is_empty()
+    }
+
+    /// Everything not yet consumed
+    pub fn rest(&mut self) -> &'a str {
+        let rest = self.s.trim();
+        self.s = "";
+        rest
+    }
+
+    pub fn finish(self) -> Result<(), PresentationError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(PresentationError::TrailingData)
+        }
+    }
+
+    /// Read the generic RDATA form: `\#`, the data length, then the data as hex
+    ///
+    /// [RFC3597 5: Text representation](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+    pub fn generic(&mut self) -> Result<Vec<u8>, PresentationError> {
+        if self.next_token()? != "\\#" {
+            return Err(PresentationError::Unsupported);
+        }
+        let len: usize = self.parse()?;
+        let data = from_hex(self.rest())?;
+        if data.len() != len {
+            return Err(PresentationError::InvalidHex);
+        }
+        Ok(data)
+    }
+}
+
+/// Read the `\X` and `\DDD` escapes of a field
+///
+/// [RFC1035 5.1: Format](https://datatracker.ietf.org/doc/html/rfc1035#section-5.1)
+pub(crate) fn unescape(s: &str) -> Result<Vec<u8>, PresentationError> {
+    let mut data = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            data.push(b);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(d) if d.is_ascii_digit() => {
+                let mut value = (d - b'0') as u16;
+                for _ in 0..2 {
+                    match bytes.next() {
+                        Some(d) if d.is_ascii_digit() => value = value * 10 + (d - b'0') as u16,
+                        _ => return Err(PresentationError::InvalidEscape),
+                    }
+                }
+                data.push(u8::try_from(value).map_err(|_| PresentationError::InvalidEscape)?);
+            }
+            Some(c) => data.push(c),
+            None => return Err(PresentationError::InvalidEscape),
+        }
+    }
+    Ok(data)
+}
+
+/// Write the generic RDATA form
+///
+/// [RFC3597 5: Text representation](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+pub(crate) fn fmt_generic(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    if data.is_empty() {
+        write!(f, "\\# 0")
+    } else {
+        write!(f, "\\# {} {}", data.len(), to_hex(data))
+    }
+}
+
+/// Writes tab separated fields aligned to the columns used by `dig` and BIND master files
+pub(crate) struct Columns<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    col: usize,
+}
+
+impl<'a, 'b> Columns<'a, 'b> {
+    pub const TTL: usize = 24;
+    pub const CLASS: usize = 32;
+    pub const TYPE: usize = 40;
+    pub const RDATA: usize = 48;
+
+    const TAB_WIDTH: usize = 8;
+
+    pub fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+        Self { f, col: 0 }
+    }
+
+    /// Write a field, first moving to the column with tabs. A field that runs past the column is
+    /// separated from the previous one by a single tab.
+    pub fn field(&mut self, column: usize, value: impl fmt::Display) -> fmt::Result {
+        if self.col != 0 || column != 0 {
+            loop {
+                self.f.write_str("\t")?;
+                self.col = (self.col / Self::TAB_WIDTH + 1) * Self::TAB_WIDTH;
+                if self.col >= column {
+                    break;
+                }
+            }
+        }
+
+        let value = value.to_string();
+        self.col += value.chars().count();
+        self.f.write_str(&value)
+    }
+}
@@ -1 +1,82 @@
-pub struct QuestionEntry {}
+use core::fmt;
+
+use nom::{combinator::map, sequence::tuple, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError},
+    indexed_input::IByteInput,
+    presentation::Columns,
+    rr::{class::RecordQClass, name::Name, types::RecordQType},
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///                                     1  1  1  1  1  1
+///       0  1  2  3  4  5  6  7  8  9  0  1  2  3  4  5
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     |                                               |
+///     /                     QNAME                     /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     |                     QTYPE                     |
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     |                     QCLASS                    |
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+///
+/// [RFC1035 4.1.2: Question section format](https://datatracker.ietf.org/doc/html/rfc1035#section-4.1.2)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Question {
+    /// A domain name represented as a sequence of labels
+    pub qname: Name,
+    /// A two octet code which specifies the type of the query.
+    pub qtype: RecordQType,
+    /// A two octet code that specifies the class of the query.
+    pub qclass: RecordQClass,
+}
+
+impl Question {
+    pub fn new(qname: Name, qtype: RecordQType, qclass: RecordQClass) -> Self {
+        Self {
+            qname,
+            qtype,
+            qclass,
+        }
+    }
+}
+
+impl Parse for Question {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(
+            tuple((Name::parse, RecordQType::parse, RecordQClass::parse)),
+            |(qname, qtype, qclass)| Self {
+                qname,
+                qtype,
+                qclass,
+            },
+        )(i)
+    }
+}
+
+impl Encode for Question {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.qname, true);
+        self.qtype.encode(e)?;
+        self.qclass.encode(e)
+    }
+}
+
+/// The question line printed by `dig`, commented out as it is not a resource record
+///
+/// ```text
+/// ;example.com.                 IN      A
+/// ```
+impl fmt::Display for Question {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut columns = Columns::new(f);
+        columns.field(0, format_args!(";{}", self.qname))?;
+        columns.field(Columns::CLASS, self.qclass)?;
+        columns.field(Columns::TYPE, self.qtype)
+    }
+}
@@ -0,0 +1,282 @@
+use alloc::{vec, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use nom::{
+    combinator::map,
+    multi::{length_data, many1},
+    number::complete::be_u8,
+    IResult,
+};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, ParserErrorType, PresentationError},
+    indexed_input::IByteInput,
+    presentation::{unescape, Tokens},
+    traits::{Encode, Parse},
+};
+
+const MAX_CHARACTER_STRING_LENGTH: usize = 255;
+
+/// ```text
+/// <character-string> is a single
+/// length octet followed by that number of characters.  <character-string>
+/// is treated as binary information, and can be up to 256 characters in
+/// length (including the length octet).
+/// ```
+/// [RFC1035 3.3: Standard RRs](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CharacterString(Vec<u8>);
+
+impl CharacterString {
+    pub fn new(data: Vec<u8>) -> Result<Self, ParserErrorType> {
+        if data.len() > MAX_CHARACTER_STRING_LENGTH {
+            return Err(ParserErrorType::CharacterStringTooLong(data.len()));
+        }
+        Ok(Self(data))
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl TryFrom<&[u8]> for CharacterString {
+    type Error = ParserErrorType;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Self::new(data.to_vec())
+    }
+}
+
+impl TryFrom<&str> for CharacterString {
+    type Error = ParserErrorType;
+
+    /// The octets of the string as is, without reading escapes
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::try_from(s.as_bytes())
+    }
+}
+
+impl Parse for CharacterString {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(length_data(be_u8), |data: IByteInput| Self(data.to_vec()))(i)
+    }
+}
+
+impl Encode for CharacterString {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u8(self.0.len() as u8);
+        e.write_bytes(&self.0);
+        Ok(())
+    }
+}
+
+/// Always quoted. Quotes and backslashes are escaped with a backslash, and non printable octets
+/// as `\DDD`.
+impl fmt::Display for CharacterString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+        for &b in &self.0 {
+            match b {
+                b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+                0x20..=0x7E => write!(f, "{}", b as char)?,
+                _ => write!(f, "\\{:03}", b)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// ```text
+/// <character-string> is expressed in one or two ways: as a contiguous set
+/// of characters without interior spaces, or as a string beginning with a "
+/// and ending with a ".  Inside a " delimited string any character can
+/// occur, except for a " itself, which must be quoted using \ (back slash).
+/// ```
+/// [RFC1035 5.1: Format](https://datatracker.ietf.org/doc/html/rfc1035#section-5.1)
+impl FromStr for CharacterString {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = match s.strip_prefix('"') {
+            Some(quoted) => quoted
+                .strip_suffix('"')
+                // The closing quote must not itself be escaped
+                .filter(|inner| inner.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 0)
+                .ok_or(PresentationError::UnterminatedString)?,
+            None => s,
+        };
+        Ok(Self::new(unescape(inner)?)?)
+    }
+}
+
+/// One or more character-strings, the RDATA of TXT and similar records. Values longer than a
+/// single character-string, such as SPF policies or DKIM keys, are split across several strings
+/// and read back by concatenating them.
+///
+/// [RFC7208 3.3: Multiple Strings in a Single DNS Record](https://datatracker.ietf.org/doc/html/rfc7208#section-3.3)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TxtData(Vec<CharacterString>);
+
+impl TxtData {
+    /// None when there are no strings, the RDATA holds at least one
+    pub fn new(strings: Vec<CharacterString>) -> Option<Self> {
+        if strings.is_empty() {
+            None
+        } else {
+            Some(Self(strings))
+        }
+    }
+
+    /// Split a value of any length into as few character-strings as possible
+    pub fn from_long(data: &[u8]) -> Self {
+        if data.is_empty() {
+            return Self(vec![CharacterString::default()]);
+        }
+        Self(
+            data.chunks(MAX_CHARACTER_STRING_LENGTH)
+                .map(|chunk| CharacterString(chunk.to_vec()))
+                .collect(),
+        )
+    }
+
+    pub fn strings(&self) -> &[CharacterString] {
+        &self.0
+    }
+
+    /// The strings joined without separators
+    pub fn concat(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|s| s.data()).copied().collect()
+    }
+}
+
+impl Parse for TxtData {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(many1(CharacterString::parse), Self)(i)
+    }
+}
+
+impl Encode for TxtData {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.0.iter().try_for_each(|s| s.encode(e))
+    }
+}
+
+impl fmt::Display for TxtData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, s) in self.0.iter().enumerate() {
+            if idx != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", s)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TxtData {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let mut strings = Vec::new();
+        while !tokens.is_empty() {
+            strings.push(tokens.next_token()?.parse()?);
+        }
+        Self::new(strings).ok_or(PresentationError::UnexpectedEnd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{
+        error::{ParserErrorType, PresentationError},
+        rr::{class::RecordClass, data::RecordData, rdata::TXT, types::RecordType},
+        traits::Encode,
+    };
+
+    use super::{CharacterString, TxtData};
+
+    #[test]
+    fn test_escaping() {
+        let s: CharacterString = r#""say \"hi\"\\ \009end""#.parse().unwrap();
+        assert_eq!(s.data(), b"say \"hi\"\\ \tend");
+        assert_eq!(s.to_string(), r#""say \"hi\"\\ \009end""#);
+
+        let unquoted: CharacterString = r"a\032b".parse().unwrap();
+        assert_eq!(unquoted.data(), b"a b");
+
+        assert_eq!(
+            r#""open"#.parse::<CharacterString>(),
+            Err(PresentationError::UnterminatedString)
+        );
+        assert_eq!(
+            r#""escaped\""#.parse::<CharacterString>(),
+            Err(PresentationError::UnterminatedString)
+        );
+        assert_eq!(
+            r"\256".parse::<CharacterString>(),
+            Err(PresentationError::InvalidEscape)
+        );
+        assert_eq!(
+            CharacterString::new(vec![b'a'; 256]),
+            Err(ParserErrorType::CharacterStringTooLong(256))
+        );
+    }
+
+    #[test]
+    fn test_txt_presentation() {
+        let rdata = RecordData::from_presentation(
+            RecordType::TXT,
+            RecordClass::IN,
+            r#""v=spf1 ip4:192.0.2.0/24" " -all" unquoted"#,
+        )
+        .unwrap();
+        let RecordData::TXT(txt) = &rdata else {
+            panic!("not TXT: {:?}", rdata);
+        };
+        assert_eq!(txt.strings().len(), 3);
+        assert_eq!(txt.concat(), b"v=spf1 ip4:192.0.2.0/24 -allunquoted");
+        assert_eq!(
+            rdata.to_string(),
+            r#""v=spf1 ip4:192.0.2.0/24" " -all" "unquoted""#
+        );
+
+        let mut e = crate::encoder::Encoder::new();
+        rdata.encode(&mut e).unwrap();
+        let bytes = e.finish();
+        assert_eq!(bytes[0], 23);
+        assert_eq!(
+            RecordData::from_wire(RecordType::TXT, RecordClass::IN, &bytes).unwrap(),
+            rdata
+        );
+        assert!(RecordData::from_wire(RecordType::TXT, RecordClass::IN, &[]).is_err());
+    }
+
+    #[test]
+    fn test_long_strings() {
+        let key: alloc::vec::Vec<u8> = (0..600).map(|i| b'A' + (i % 26) as u8).collect();
+        let txt = TXT::from_long(&key);
+        let lengths: alloc::vec::Vec<_> = txt.strings().iter().map(|s| s.len()).collect();
+        assert_eq!(lengths, [255, 255, 90]);
+        assert_eq!(txt.concat(), key);
+        assert_eq!(txt.to_string().parse::<TXT>().unwrap(), txt);
+
+        assert_eq!(
+            TxtData::from_long(b"").strings(),
+            [CharacterString::default()]
+        );
+        assert_eq!(TxtData::new(vec![]), None);
+    }
+}
@@ -1,19 +1,18 @@
-use nom::{
-    combinator::{map, verify},
-    error::Error,
-    number::complete::be_u16,
-    IResult,
-};
+use core::{fmt, str::FromStr};
+
+use nom::{number::complete::be_u16, IResult};
 
 use crate::{
-    error::{ParserError, ParserErrorType},
+    encoder::Encoder,
+    error::{EncodeError, ParserError, ParserErrorType, PresentationError},
     indexed_input::IByteInput,
-    traits::Parse,
+    traits::{Encode, Parse},
 };
 
 /// CLASS fields appear in resource records.
 ///
 /// [RFC1035 3.2.4: CLASS values](https://datatracker.ietf.org/doc/html/rfc1035#section-3.2.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RecordClass {
     /// Internet
     IN,
@@ -26,6 +25,9 @@ pub enum RecordClass {
     // None, used in UPDATE queries to require that an RRset does not exist prior to the update.
     // [RFC2136](https://www.rfc-editor.org/rfc/rfc2136)
     NONE,
+    /// Any class, only valid in records of UPDATE messages that delete RRsets and in meta-RRs such
+    /// as TSIG. [RFC2136 2.5.2](https://datatracker.ietf.org/doc/html/rfc2136#section-2.5.2)
+    ANY,
     /// Unknown record class
     Unknown(u16),
 }
@@ -33,6 +35,7 @@ pub enum RecordClass {
 /// QCLASS fields appear in the question section of a query.  QCLASS values are a superset of CLASS values; every CLASS is a valid QCLASS.
 ///
 /// [RFC1035 3.2.5: QCLASS values](https://www.rfc-editor.org/rfc/rfc1035.html#section-3.2.5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RecordQClass {
     /// A general class
     RecordClass(RecordClass),
@@ -49,6 +52,24 @@ impl From<u16> for RecordQClass {
     }
 }
 
+impl From<RecordQClass> for u16 {
+    fn from(value: RecordQClass) -> Self {
+        match value {
+            RecordQClass::RecordClass(c) => c.into(),
+            RecordQClass::Any => 255,
+        }
+    }
+}
+
+impl From<RecordClass> for RecordQClass {
+    fn from(value: RecordClass) -> Self {
+        match value {
+            RecordClass::ANY => Self::Any,
+            c => Self::RecordClass(c),
+        }
+    }
+}
+
 impl From<u16> for RecordClass {
     fn from(value: u16) -> Self {
         match value {
@@ -57,11 +78,85 @@ impl From<u16> for RecordClass {
             3 => Self::CH,
             4 => Self::HS,
             254 => Self::NONE,
+            255 => Self::ANY,
             v => Self::Unknown(v),
         }
     }
 }
 
+impl From<RecordClass> for u16 {
+    fn from(value: RecordClass) -> Self {
+        match value {
+            RecordClass::IN => 1,
+            RecordClass::CS => 2,
+            RecordClass::CH => 3,
+            RecordClass::HS => 4,
+            RecordClass::NONE => 254,
+            RecordClass::ANY => 255,
+            RecordClass::Unknown(v) => v,
+        }
+    }
+}
+
+/// Unknown classes are written as `CLASS` followed by the decimal class number
+///
+/// [RFC3597 5: Text representation](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+impl fmt::Display for RecordClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IN => write!(f, "IN"),
+            Self::CS => write!(f, "CS"),
+            Self::CH => write!(f, "CH"),
+            Self::HS => write!(f, "HS"),
+            Self::NONE => write!(f, "NONE"),
+            Self::ANY => write!(f, "ANY"),
+            Self::Unknown(v) => write!(f, "CLASS{}", v),
+        }
+    }
+}
+
+impl FromStr for RecordClass {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_ascii_uppercase();
+        match upper.as_str() {
+            "IN" => Ok(Self::IN),
+            "CS" => Ok(Self::CS),
+            "CH" => Ok(Self::CH),
+            "HS" => Ok(Self::HS),
+            "NONE" => Ok(Self::NONE),
+            "ANY" => Ok(Self::ANY),
+            _ => upper
+                .strip_prefix("CLASS")
+                .and_then(|v| v.parse::<u16>().ok())
+                .map(Self::from)
+                .ok_or(PresentationError::UnknownMnemonic),
+        }
+    }
+}
+
+impl fmt::Display for RecordQClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RecordClass(c) => write!(f, "{}", c),
+            Self::Any => write!(f, "ANY"),
+        }
+    }
+}
+
+impl FromStr for RecordQClass {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("ANY") || s == "*" {
+            Ok(Self::Any)
+        } else {
+            s.parse::<RecordClass>().map(|c| Self::from(u16::from(c)))
+        }
+    }
+}
+
 impl Parse for RecordClass {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
         let (ir, v) = be_u16(i)?;
@@ -77,6 +172,13 @@ impl Parse for RecordClass {
     }
 }
 
+impl Encode for RecordClass {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u16((*self).into());
+        Ok(())
+    }
+}
+
 impl Parse for RecordQClass {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
         let (ir, v) = be_u16(i)?;
@@ -91,3 +193,10 @@ impl Parse for RecordQClass {
         }
     }
 }
+
+impl Encode for RecordQClass {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u16((*self).into());
+        Ok(())
+    }
+}
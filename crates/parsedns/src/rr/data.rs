@@ -1,19 +1,223 @@
-use std::net::Ipv4Addr;
+use core::fmt;
 
-use nom::IResult;
+use nom::{
+    combinator::{all_consuming, map},
+    IResult,
+};
 
-use crate::{error::ParserError, indexed_input::IByteInput, traits::Parse};
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    traits::{Encode, Parse},
+};
 
-use super::rdata::*;
-
-//pub struct Record {
-//    pub name: String,
-//    pub class: Class,
-//    pub ttl: u32,
-//    pub data: RecordData,
-//}
+use super::{class::RecordClass, rdata::*, types::RecordType};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RecordData {
     A(A),
+    NS(NS),
+    MD(MD),
+    MF(MF),
+    CNAME(CNAME),
     SOA(SOA),
+    MB(MB),
+    MG(MG),
+    MR(MR),
+    NULL(NULL),
+    WKS(WKS),
+    PTR(PTR),
+    HINFO(HINFO),
+    MINFO(MINFO),
+    MX(MX),
+    TXT(TXT),
+    AAAA(AAAA),
+    RRSIG(RRSIG),
+    OPT(OPT),
+    Unknown(Unknown),
+}
+
+impl RecordData {
+    pub fn rtype(&self) -> RecordType {
+        match self {
+            Self::A(_) => RecordType::A,
+            Self::NS(_) => RecordType::NS,
+            Self::MD(_) => RecordType::MD,
+            Self::MF(_) => RecordType::MF,
+            Self::CNAME(_) => RecordType::CNAME,
+            Self::SOA(_) => RecordType::SOA,
+            Self::MB(_) => RecordType::MB,
+            Self::MG(_) => RecordType::MG,
+            Self::MR(_) => RecordType::MR,
+            Self::NULL(_) => RecordType::NULL,
+            Self::WKS(_) => RecordType::WKS,
+            Self::PTR(_) => RecordType::PTR,
+            Self::HINFO(_) => RecordType::HINFO,
+            Self::MINFO(_) => RecordType::MINFO,
+            Self::MX(_) => RecordType::MX,
+            Self::TXT(_) => RecordType::TXT,
+            Self::AAAA(_) => RecordType::AAAA,
+            Self::RRSIG(_) => RecordType::RRSIG,
+            Self::OPT(_) => RecordType::OPT,
+            Self::Unknown(unknown) => unknown.rtype(),
+        }
+    }
+
+    /// Parse RDATA that has already been split out of a record using RDLENGTH. All of the input
+    /// must be consumed.
+    pub(crate) fn parse<'a>(
+        i: IByteInput<'a>,
+        rtype: RecordType,
+        class: RecordClass,
+    ) -> IResult<IByteInput<'a>, Self, ParserError> {
+        match (rtype, class) {
+            (RecordType::A, RecordClass::IN) => map(all_consuming(A::parse), Self::A)(i),
+            (RecordType::NS, _) => map(all_consuming(NS::parse), Self::NS)(i),
+            (RecordType::MD, _) => map(all_consuming(MD::parse), Self::MD)(i),
+            (RecordType::MF, _) => map(all_consuming(MF::parse), Self::MF)(i),
+            (RecordType::CNAME, _) => map(all_consuming(CNAME::parse), Self::CNAME)(i),
+            (RecordType::SOA, _) => map(all_consuming(SOA::parse), Self::SOA)(i),
+            (RecordType::MB, _) => map(all_consuming(MB::parse), Self::MB)(i),
+            (RecordType::MG, _) => map(all_consuming(MG::parse), Self::MG)(i),
+            (RecordType::MR, _) => map(all_consuming(MR::parse), Self::MR)(i),
+            (RecordType::NULL, _) => map(all_consuming(NULL::parse), Self::NULL)(i),
+            (RecordType::WKS, RecordClass::IN) => map(all_consuming(WKS::parse), Self::WKS)(i),
+            (RecordType::PTR, _) => map(all_consuming(PTR::parse), Self::PTR)(i),
+            (RecordType::HINFO, _) => map(all_consuming(HINFO::parse), Self::HINFO)(i),
+            (RecordType::MINFO, _) => map(all_consuming(MINFO::parse), Self::MINFO)(i),
+            (RecordType::MX, _) => map(all_consuming(MX::parse), Self::MX)(i),
+            (RecordType::TXT, _) => map(all_consuming(TXT::parse), Self::TXT)(i),
+            (RecordType::AAAA, RecordClass::IN) => map(all_consuming(AAAA::parse), Self::AAAA)(i),
+            (RecordType::RRSIG, _) => map(all_consuming(RRSIG::parse), Self::RRSIG)(i),
+            (rtype, _) => map(|i| Unknown::parse(i, rtype), Self::Unknown)(i),
+        }
+    }
+
+    /// Parse RDATA from uncompressed wire bytes
+    pub fn from_wire(
+        rtype: RecordType,
+        class: RecordClass,
+        data: &[u8],
+    ) -> Result<Self, ParserError> {
+        Self::parse(IByteInput::new(data), rtype, class)
+            .map(|(_, rdata)| rdata)
+            .map_err(|e| match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => e,
+                nom::Err::Incomplete(_) => ParserError {
+                    position: data.len(),
+                    nom_kind: Some(nom::error::ErrorKind::Eof),
+                    err_type: None,
+                },
+            })
+    }
+
+    /// Read RDATA in its master file format. The generic `\#` form is accepted for every type.
+    pub fn from_presentation(
+        rtype: RecordType,
+        class: RecordClass,
+        s: &str,
+    ) -> Result<Self, PresentationError> {
+        if s.trim_start().starts_with("\\#") {
+            let unknown = Unknown::from_presentation(rtype, s)?;
+            return Self::from_wire(rtype, class, unknown.data())
+                .map_err(|e| e.err_type.map_or(PresentationError::InvalidHex, Into::into));
+        }
+
+        let rdata = match (rtype, class) {
+            (RecordType::A, RecordClass::IN) => Self::A(s.parse()?),
+            (RecordType::NS, _) => Self::NS(s.parse()?),
+            (RecordType::MD, _) => Self::MD(s.parse()?),
+            (RecordType::MF, _) => Self::MF(s.parse()?),
+            (RecordType::CNAME, _) => Self::CNAME(s.parse()?),
+            (RecordType::SOA, _) => Self::SOA(s.parse()?),
+            (RecordType::MB, _) => Self::MB(s.parse()?),
+            (RecordType::MG, _) => Self::MG(s.parse()?),
+            (RecordType::MR, _) => Self::MR(s.parse()?),
+            (RecordType::WKS, RecordClass::IN) => Self::WKS(s.parse()?),
+            (RecordType::PTR, _) => Self::PTR(s.parse()?),
+            (RecordType::HINFO, _) => Self::HINFO(s.parse()?),
+            (RecordType::MINFO, _) => Self::MINFO(s.parse()?),
+            (RecordType::MX, _) => Self::MX(s.parse()?),
+            (RecordType::TXT, _) => Self::TXT(s.parse()?),
+            (RecordType::AAAA, RecordClass::IN) => Self::AAAA(s.parse()?),
+            (RecordType::RRSIG, _) => Self::RRSIG(s.parse()?),
+            _ => return Err(PresentationError::Unsupported),
+        };
+        Ok(rdata)
+    }
+}
+
+impl Encode for RecordData {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        match self {
+            Self::A(rdata) => rdata.encode(e),
+            Self::NS(rdata) => rdata.encode(e),
+            Self::MD(rdata) => rdata.encode(e),
+            Self::MF(rdata) => rdata.encode(e),
+            Self::CNAME(rdata) => rdata.encode(e),
+            Self::SOA(rdata) => rdata.encode(e),
+            Self::MB(rdata) => rdata.encode(e),
+            Self::MG(rdata) => rdata.encode(e),
+            Self::MR(rdata) => rdata.encode(e),
+            Self::NULL(rdata) => rdata.encode(e),
+            Self::WKS(rdata) => rdata.encode(e),
+            Self::PTR(rdata) => rdata.encode(e),
+            Self::HINFO(rdata) => rdata.encode(e),
+            Self::MINFO(rdata) => rdata.encode(e),
+            Self::MX(rdata) => rdata.encode(e),
+            Self::TXT(rdata) => rdata.encode(e),
+            Self::AAAA(rdata) => rdata.encode(e),
+            Self::RRSIG(rdata) => rdata.encode(e),
+            Self::OPT(rdata) => rdata.encode(e),
+            Self::Unknown(rdata) => rdata.encode(e),
+        }
+    }
+}
+
+impl fmt::Display for RecordData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A(rdata) => rdata.fmt(f),
+            Self::NS(rdata) => rdata.fmt(f),
+            Self::MD(rdata) => rdata.fmt(f),
+            Self::MF(rdata) => rdata.fmt(f),
+            Self::CNAME(rdata) => rdata.fmt(f),
+            Self::SOA(rdata) => rdata.fmt(f),
+            Self::MB(rdata) => rdata.fmt(f),
+            Self::MG(rdata) => rdata.fmt(f),
+            Self::MR(rdata) => rdata.fmt(f),
+            Self::NULL(rdata) => rdata.fmt(f),
+            Self::WKS(rdata) => rdata.fmt(f),
+            Self::PTR(rdata) => rdata.fmt(f),
+            Self::HINFO(rdata) => rdata.fmt(f),
+            Self::MINFO(rdata) => rdata.fmt(f),
+            Self::MX(rdata) => rdata.fmt(f),
+            Self::TXT(rdata) => rdata.fmt(f),
+            Self::AAAA(rdata) => rdata.fmt(f),
+            Self::RRSIG(rdata) => rdata.fmt(f),
+            Self::OPT(rdata) => rdata.fmt(f),
+            Self::Unknown(rdata) => rdata.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        indexed_input::IByteInput,
+        rr::{class::RecordClass, types::RecordType},
+    };
+
+    use super::RecordData;
+
+    #[test]
+    fn test_parse_consumes_rdata() {
+        let bytes = [192, 0, 2, 1];
+        let (rest, rdata) =
+            RecordData::parse(IByteInput::new(&bytes), RecordType::A, RecordClass::IN).unwrap();
+        assert!(matches!(rdata, RecordData::A(_)));
+        assert!(rest.is_empty());
+        assert_eq!(rest.idx(), 4);
+    }
 }
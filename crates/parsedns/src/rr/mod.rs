@@ -1,6 +1,8 @@
+pub mod character_string;
 pub mod class;
 pub mod data;
 pub mod name;
 pub mod rdata;
 pub mod record;
+pub mod rrset;
 pub mod types;
@@ -1,3 +1,11 @@
+use alloc::{string::String, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
 use nom::{
     combinator::{map, map_res, peek},
     multi::length_data,
@@ -9,15 +17,22 @@ use tinyvec::TinyVec;
 pub struct Label(pub String);
 
 use crate::{
-    error::{ParserError, ParserErrorType},
+    encoder::Encoder,
+    error::{EncodeError, ParserError, ParserErrorType, PresentationError},
     indexed_input::IByteInput,
-    traits::Parse,
+    traits::{Encode, Parse},
 };
 
-const MAX_LABEL_LENGTH: u8 = 64;
-const MAX_NAME_LENGTH: usize = 255;
+/// [RFC1035 2.3.4: Size limits](https://datatracker.ietf.org/doc/html/rfc1035#section-2.3.4)
+/// ```text
+/// labels          63 octets or less
+///
+/// names           255 octets or less
+/// ```
+pub(crate) const MAX_LABEL_LENGTH: u8 = 63;
+pub(crate) const MAX_NAME_LENGTH: usize = 255;
 const TYPE_MASK: u8 = 0xC0;
-const ADDR_MASK: u16 = 0x3FF;
+const ADDR_MASK: u16 = 0x3FFF;
 
 enum LabelType {
     Sequence,
@@ -42,6 +57,66 @@ impl Name {
         this
     }
 
+    /// Append a label to the end of the name
+    pub fn push_label(&mut self, label: &[u8]) -> Result<(), ParserErrorType> {
+        if label.len() > MAX_LABEL_LENGTH as usize {
+            return Err(ParserErrorType::LabelBytesTooLong(label.len() as u16));
+        }
+        self.extend_name(label)
+    }
+
+    /// The labels of the name from the leftmost (most specific) to the rightmost, excluding the
+    /// root label
+    pub fn labels(&self) -> impl DoubleEndedIterator<Item = &[u8]> + ExactSizeIterator + '_ {
+        self.label_ends.iter().enumerate().map(move |(idx, &end)| {
+            let start = if idx == 0 {
+                0
+            } else {
+                self.label_ends[idx - 1]
+            };
+            &self.label_data[start as usize..end as usize]
+        })
+    }
+
+    pub fn label_count(&self) -> usize {
+        self.label_ends.len()
+    }
+
+    /// Whether the name is anchored at the root rather than relative to an origin
+    pub fn is_fqdn(&self) -> bool {
+        self.is_fqdn
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.label_ends.is_empty()
+    }
+
+    /// Whether the rightmost labels of the name are the labels of `other`, compared case
+    /// insensitively. Every name is a subdomain of itself and of the root.
+    pub fn is_subdomain_of(&self, other: &Name) -> bool {
+        other.label_count() <= self.label_count()
+            && self
+                .labels()
+                .rev()
+                .zip(other.labels().rev())
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+
+    /// The name made of the rightmost `labels` labels, the whole name if it has fewer. The
+    /// suffix of zero labels is the root.
+    pub fn suffix(&self, labels: usize) -> Name {
+        let skip = self.label_count().saturating_sub(labels);
+        let mut suffix = Name {
+            is_fqdn: self.is_fqdn,
+            ..Default::default()
+        };
+        for label in self.labels().skip(skip) {
+            // A part of a valid name is valid
+            let _ = suffix.extend_name(label);
+        }
+        suffix
+    }
+
     fn extend_name(&mut self, label: &[u8]) -> Result<(), ParserErrorType> {
         self.label_data.extend_from_slice(label);
         self.label_ends.push(self.label_data.len() as u8);
@@ -52,6 +127,7 @@ impl Name {
         }
     }
 
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         let dots = if !self.label_ends.is_empty() {
             self.label_ends.len()
@@ -101,6 +177,7 @@ impl Name {
                     let (_, offset) = Self::peek_ptr_offset(i)?;
 
                     self.parse_label(i.offset_original(offset), Some(name_start))?;
+                    (i, _) = be_u16(i)?;
                     break;
                 }
                 LabelType::Root => {
@@ -149,7 +226,7 @@ impl Name {
 
     fn parse_seq_label(s: IByteInput) -> IResult<IByteInput, IByteInput, ParserError> {
         let parse_len = map_res(be_u8, |num| {
-            if num >= MAX_LABEL_LENGTH {
+            if num > MAX_LABEL_LENGTH {
                 Err(ParserErrorType::LabelBytesTooLong(num.into()))
             } else {
                 Ok(num)
@@ -169,15 +246,206 @@ impl Parse for Name {
     }
 }
 
+impl Encode for Name {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(self, true);
+        Ok(())
+    }
+}
+
+/// Domain names are compared case insensitively
+///
+/// [RFC4343 3: Name lookup, label types, and CLASS](https://datatracker.ietf.org/doc/html/rfc4343#section-3)
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_fqdn == other.is_fqdn
+            && self.label_ends == other.label_ends
+            && self.label_data.eq_ignore_ascii_case(&other.label_data)
+    }
+}
+
+impl Eq for Name {}
+
+impl Hash for Name {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.is_fqdn.hash(state);
+        self.label_ends.hash(state);
+        for b in self.label_data.iter() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+    }
+}
+
+/// ```text
+/// For the purposes of DNS security, owner names are ordered by treating
+/// individual labels as unsigned left-justified octet strings.  The
+/// absence of a octet sorts before a zero value octet, and uppercase
+/// US-ASCII letters are treated as if they were lowercase US-ASCII
+/// letters.
+///
+/// To compute the canonical ordering of a set of DNS names, start by
+/// sorting the names according to their most significant (rightmost)
+/// labels.  For names in which the most significant label is identical,
+/// continue sorting according to their next most significant label, and
+/// so forth.
+/// ```
+///
+/// [RFC4034 6.1: Canonical DNS Name Order](https://datatracker.ietf.org/doc/html/rfc4034#section-6.1)
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lowercase = |label: &[u8]| {
+            label
+                .iter()
+                .map(|b| b.to_ascii_lowercase())
+                .collect::<TinyVec<[u8; 64]>>()
+        };
+
+        self.labels()
+            .rev()
+            .map(lowercase)
+            .cmp(other.labels().rev().map(lowercase))
+            .then(self.is_fqdn.cmp(&other.is_fqdn))
+    }
+}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Characters with a special meaning in master files are escaped with a backslash, and non
+/// printable octets as `\DDD`
+///
+/// [RFC4343 2.1: Escaping unusual DNS label octets](https://datatracker.ietf.org/doc/html/rfc4343#section-2.1)
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return if self.is_fqdn { write!(f, ".") } else { Ok(()) };
+        }
+
+        for (idx, label) in self.labels().enumerate() {
+            if idx != 0 {
+                write!(f, ".")?;
+            }
+            for &b in label {
+                match b {
+                    b'.' | b'\\' | b'"' | b'(' | b')' | b';' | b'@' | b'$' => {
+                        write!(f, "\\{}", b as char)?
+                    }
+                    0x21..=0x7E => write!(f, "{}", b as char)?,
+                    _ => write!(f, "\\{:03}", b)?,
+                }
+            }
+        }
+
+        if self.is_fqdn {
+            write!(f, ".")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Name(\"{}\")", self)
+    }
+}
+
+/// Reads a name in master file format. A trailing dot makes the name fully qualified.
+impl FromStr for Name {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = Name::new();
+        if s == "." {
+            name.is_fqdn = true;
+            return Ok(name);
+        }
+
+        let mut label = Vec::with_capacity(MAX_LABEL_LENGTH as usize);
+        let mut bytes = s.bytes();
+        let mut ended_with_dot = false;
+
+        while let Some(b) = bytes.next() {
+            ended_with_dot = false;
+            match b {
+                b'.' => {
+                    if label.is_empty() {
+                        return Err(PresentationError::EmptyLabel);
+                    }
+                    name.push_label(&label)?;
+                    label.clear();
+                    ended_with_dot = true;
+                }
+                b'\\' => match bytes.next() {
+                    Some(d) if d.is_ascii_digit() => {
+                        let mut value = (d - b'0') as u16;
+                        for _ in 0..2 {
+                            match bytes.next() {
+                                Some(d) if d.is_ascii_digit() => {
+                                    value = value * 10 + (d - b'0') as u16
+                                }
+                                _ => return Err(PresentationError::InvalidEscape),
+                            }
+                        }
+                        let value =
+                            u8::try_from(value).map_err(|_| PresentationError::InvalidEscape)?;
+                        label.push(value);
+                    }
+                    Some(c) => label.push(c),
+                    None => return Err(PresentationError::InvalidEscape),
+                },
+                b => label.push(b),
+            }
+        }
+
+        if ended_with_dot {
+            name.is_fqdn = true;
+        } else if !label.is_empty() {
+            name.push_label(&label)?;
+        } else {
+            return Err(PresentationError::EmptyLabel);
+        }
+
+        Ok(name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::indexed_input::IByteInput;
+    use crate::{encoder::Encoder, indexed_input::IByteInput, traits::Parse};
 
     use super::Name;
 
     #[test]
     fn test_pointer_with_pointer_ending_labels() {}
 
+    #[test]
+    fn test_pointer_consumed() {
+        // "a." followed by a pointer back to it and one trailing byte
+        let bytes = vec![0x01, 0x61, 0x00, 0xC0, 0x00, 0xFF];
+        let i = IByteInput::new(&bytes);
+
+        let mut name = Name::root();
+        let (rest, _) = name.parse_label(i.offset_original(3), None).unwrap();
+        assert_eq!(rest.idx(), 5);
+        assert_eq!(name.len(), 2);
+    }
+
+    #[test]
+    fn test_label_length() {
+        let mut bytes = vec![63];
+        bytes.extend_from_slice(&[b'a'; 63]);
+        bytes.push(0);
+        let (_, name) = Name::parse(IByteInput::new(&bytes)).unwrap();
+        assert_eq!(name.labels().next().map(<[u8]>::len), Some(63));
+
+        let mut name = Name::root();
+        assert!(name.push_label(&[b'a'; 63]).is_ok());
+        assert!(name.push_label(&[b'a'; 64]).is_err());
+    }
+
     #[test]
     fn test_recursive_pointer() {
         // Points to an invalid beginning label marker
@@ -202,4 +470,97 @@ mod tests {
         let i = IByteInput::new(&bytes);
         assert!(name.parse_label(i, None).is_err());
     }
+
+    #[test]
+    fn test_presentation_round_trip() {
+        let name: Name = "www.Example.com.".parse().unwrap();
+        assert!(name.is_fqdn());
+        assert_eq!(name.label_count(), 3);
+        assert_eq!(name.to_string(), "www.Example.com.");
+        assert_eq!(name, "WWW.example.COM.".parse().unwrap());
+
+        let escaped: Name = r"a\.b\032c.example.".parse().unwrap();
+        assert_eq!(escaped.labels().next(), Some(b"a.b c".as_ref()));
+        assert_eq!(escaped.to_string(), r"a\.b\032c.example.");
+
+        assert_eq!(".".parse::<Name>().unwrap(), Name::root());
+        assert!("a..b".parse::<Name>().is_err());
+        assert!(r"a\256".parse::<Name>().is_err());
+        assert!(format!("{}.", "a".repeat(63)).parse::<Name>().is_ok());
+        assert!(format!("{}.", "a".repeat(64)).parse::<Name>().is_err());
+    }
+
+    #[test]
+    fn test_compressed_name_parses() {
+        let first: Name = "mail.example.com.".parse().unwrap();
+        let second: Name = "www.EXAMPLE.com.".parse().unwrap();
+
+        let mut e = Encoder::new();
+        e.write_name(&first, true);
+        e.write_name(&second, true);
+        let bytes = e.finish();
+        // "www" followed by a pointer to "example.com" at offset 5
+        assert_eq!(&bytes[18..], [3, b'w', b'w', b'w', 0xC0, 5].as_ref());
+
+        let i = IByteInput::new(&bytes);
+        let (i, parsed_first) = Name::parse(i).unwrap();
+        let (i, parsed_second) = Name::parse(i).unwrap();
+        assert_eq!(parsed_first, first);
+        assert_eq!(parsed_second, second);
+        assert!(i.is_empty());
+    }
+
+    #[test]
+    fn test_pointer_uses_all_offset_bits() {
+        // A name beyond the first 1024 bytes is still reachable through a 14 bit offset
+        let mut bytes = vec![0; 0x500];
+        bytes.extend_from_slice(&[3, b'c', b'o', b'm', 0, 0xC5, 0x00]);
+        let i = IByteInput::new(&bytes);
+
+        let (_, name) = Name::parse(i.offset_original(0x505)).unwrap();
+        assert_eq!(name, "com.".parse().unwrap());
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        // The example of RFC4034 6.1
+        let names = [
+            "example.",
+            "a.example.",
+            "yljkjljk.a.example.",
+            "Z.a.example.",
+            "zABC.a.EXAMPLE.",
+            "z.example.",
+            "\\001.z.example.",
+            "*.z.example.",
+            "\\200.z.example.",
+        ];
+        let names: Vec<Name> = names.iter().map(|name| name.parse().unwrap()).collect();
+
+        let mut sorted = names.clone();
+        sorted.reverse();
+        sorted.sort();
+        assert_eq!(sorted, names);
+    }
+
+    #[test]
+    fn test_is_subdomain_of() {
+        let name: Name = "www.Example.com.".parse().unwrap();
+        for parent in ["www.example.com.", "EXAMPLE.com.", "com.", "."] {
+            assert!(name.is_subdomain_of(&parent.parse().unwrap()), "{}", parent);
+        }
+        for other in ["ww.example.com.", "xample.com.", "a.www.example.com."] {
+            assert!(!name.is_subdomain_of(&other.parse().unwrap()), "{}", other);
+        }
+    }
+
+    #[test]
+    fn test_suffix() {
+        let name: Name = "a.b.example.com.".parse().unwrap();
+        assert_eq!(name.suffix(2), "example.com.".parse().unwrap());
+        assert_eq!(name.suffix(4), name);
+        assert_eq!(name.suffix(9), name);
+        assert!(name.suffix(0).is_root());
+        assert!(name.is_subdomain_of(&name.suffix(3)));
+    }
 }
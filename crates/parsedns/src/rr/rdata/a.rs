@@ -1,8 +1,15 @@
-use std::net::Ipv4Addr;
+use core::{fmt, str::FromStr};
 
 use nom::{combinator::map, IResult};
 
-use crate::{error::ParserError, indexed_input::IByteInput, traits::Parse};
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    net::Ipv4Addr,
+    presentation::Tokens,
+    traits::{Encode, Parse},
+};
 
 /// ```text
 ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
@@ -22,9 +29,14 @@ use crate::{error::ParserError, indexed_input::IByteInput, traits::Parse};
 /// [RFC1035 3.4.1: A RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.4.1)
 ///
 /// An internet specific RR
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct A(Ipv4Addr);
 
 impl A {
+    pub fn new(address: Ipv4Addr) -> Self {
+        Self(address)
+    }
+
     /// ```text
     /// ADDRESS         A 32 bit Internet address.
     /// ```
@@ -35,6 +47,29 @@ impl A {
 
 impl Parse for A {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
-        map(Ipv4Addr::parse, |addr| Self(addr))(i)
+        map(Ipv4Addr::parse, Self)(i)
+    }
+}
+
+impl Encode for A {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.0.encode(e)
+    }
+}
+
+impl fmt::Display for A {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for A {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let address = tokens.address()?;
+        tokens.finish()?;
+        Ok(Self(address))
     }
 }
@@ -0,0 +1,68 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    net::Ipv6Addr,
+    presentation::Tokens,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+/// A 128 bit IPv6 address is encoded in the data portion of an AAAA
+/// resource record in network byte order (high-order byte first).
+///
+/// An AAAA query for a specified domain name in the Internet class
+/// returns all associated AAAA resource records in the answer section of
+/// a response.
+///
+/// A type AAAA query does not trigger additional section processing.
+/// ```
+/// [RFC3596 2.2: AAAA data format](https://datatracker.ietf.org/doc/html/rfc3596#section-2.2)
+///
+/// An internet specific RR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AAAA(Ipv6Addr);
+
+impl AAAA {
+    pub fn new(address: Ipv6Addr) -> Self {
+        Self(address)
+    }
+
+    /// A 128 bit IPv6 address
+    pub fn address(&self) -> Ipv6Addr {
+        self.0
+    }
+}
+
+impl Parse for AAAA {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Ipv6Addr::parse, Self)(i)
+    }
+}
+
+impl Encode for AAAA {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.0.encode(e)
+    }
+}
+
+impl fmt::Display for AAAA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for AAAA {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let address = tokens.address()?;
+        tokens.finish()?;
+        Ok(Self(address))
+    }
+}
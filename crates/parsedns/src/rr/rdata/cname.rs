@@ -0,0 +1,72 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                     CNAME                     /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// CNAME RRs cause no additional section processing, but name servers may
+/// choose to restart the query at the canonical name in certain cases.  See
+/// the description of name server logic in [RFC-1034] for details.
+/// ```
+/// [RFC1035 3.3.1: CNAME RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CNAME(Name);
+
+impl CNAME {
+    pub fn new(cname: Name) -> Self {
+        Self(cname)
+    }
+
+    /// ```text
+    /// CNAME           A <domain-name> which specifies the canonical or primary
+    ///                 name for the owner.  The owner name is an alias.
+    /// ```
+    pub fn cname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for CNAME {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for CNAME {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for CNAME {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for CNAME {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let cname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(cname))
+    }
+}
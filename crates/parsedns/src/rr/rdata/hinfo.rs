@@ -0,0 +1,87 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, sequence::tuple, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::character_string::CharacterString,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                      CPU                      /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                       OS                      /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// HINFO records are used to acquire general information about a host.  The
+/// main use is for protocols such as FTP that can use special procedures
+/// when talking between machines or operating systems of the same type.
+/// ```
+/// [RFC1035 3.3.2: HINFO RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HINFO {
+    cpu: CharacterString,
+    os: CharacterString,
+}
+
+impl HINFO {
+    pub fn new(cpu: CharacterString, os: CharacterString) -> Self {
+        Self { cpu, os }
+    }
+
+    /// ```text
+    /// CPU             A <character-string> which specifies the CPU type.
+    /// ```
+    pub fn cpu(&self) -> &CharacterString {
+        &self.cpu
+    }
+
+    /// ```text
+    /// OS              A <character-string> which specifies the operating
+    ///                 system type.
+    /// ```
+    pub fn os(&self) -> &CharacterString {
+        &self.os
+    }
+}
+
+impl Parse for HINFO {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(
+            tuple((CharacterString::parse, CharacterString::parse)),
+            |(cpu, os)| Self { cpu, os },
+        )(i)
+    }
+}
+
+impl Encode for HINFO {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.cpu.encode(e)?;
+        self.os.encode(e)
+    }
+}
+
+impl fmt::Display for HINFO {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.cpu, self.os)
+    }
+}
+
+impl FromStr for HINFO {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let cpu = tokens.next_token()?.parse()?;
+        let os = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self { cpu, os })
+    }
+}
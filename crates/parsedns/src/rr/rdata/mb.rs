@@ -0,0 +1,71 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    MADNAME                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// MB records cause additional section processing which looks up an A type
+/// RRs corresponding to MADNAME.
+/// ```
+/// [RFC1035 3.3.3: MB RDATA format (EXPERIMENTAL)](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MB(Name);
+
+impl MB {
+    pub fn new(madname: Name) -> Self {
+        Self(madname)
+    }
+
+    /// ```text
+    /// MADNAME         A <domain-name> which specifies a host which has the
+    ///                 specified mailbox.
+    /// ```
+    pub fn madname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for MB {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for MB {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for MB {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let madname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(madname))
+    }
+}
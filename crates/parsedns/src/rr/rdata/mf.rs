@@ -0,0 +1,77 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    MADNAME                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// MF records cause additional section processing which looks up an A type
+/// record corresponding to MADNAME.
+///
+/// MF is obsolete.  See the definition of MX and [RFC-974] for details of
+/// the new scheme.  The recommended policy for dealing with MD RRs found in
+/// a master file is to reject them, or to convert them to MX RRs with a
+/// preference of 10.
+/// ```
+/// [RFC1035 3.3.5: MF RDATA format (Obsolete)](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.5)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MF(Name);
+
+impl MF {
+    pub fn new(madname: Name) -> Self {
+        Self(madname)
+    }
+
+    /// ```text
+    /// MADNAME         A <domain-name> which specifies a host which has a mail
+    ///                 agent for the domain which will accept mail for
+    ///                 forwarding to the domain.
+    /// ```
+    pub fn madname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for MF {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for MF {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MF {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for MF {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let madname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(madname))
+    }
+}
@@ -0,0 +1,70 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    MGMNAME                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// MG records cause no additional section processing.
+/// ```
+/// [RFC1035 3.3.6: MG RDATA format (EXPERIMENTAL)](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.6)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MG(Name);
+
+impl MG {
+    pub fn new(mgmname: Name) -> Self {
+        Self(mgmname)
+    }
+
+    /// ```text
+    /// MGMNAME         A <domain-name> which specifies a mailbox which is a
+    ///                 member of the mail group specified by the domain name.
+    /// ```
+    pub fn mgmname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for MG {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for MG {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for MG {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let mgmname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(mgmname))
+    }
+}
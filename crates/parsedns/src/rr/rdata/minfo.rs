@@ -0,0 +1,97 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, sequence::tuple, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    RMAILBX                    /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    EMAILBX                    /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// MINFO records cause no additional section processing.  Although these
+/// records can be associated with a simple mailbox, they are usually used
+/// with a mailing list.
+/// ```
+/// [RFC1035 3.3.7: MINFO RDATA format (EXPERIMENTAL)](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.7)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MINFO {
+    rmailbx: Name,
+    emailbx: Name,
+}
+
+impl MINFO {
+    pub fn new(rmailbx: Name, emailbx: Name) -> Self {
+        Self { rmailbx, emailbx }
+    }
+
+    /// ```text
+    /// RMAILBX         A <domain-name> which specifies a mailbox which is
+    ///                 responsible for the mailing list or mailbox.  If this
+    ///                 domain name names the root, the owner of the MINFO RR is
+    ///                 responsible for itself.  Note that many existing mailing
+    ///                 lists use a mailbox X-request for the RMAILBX field of
+    ///                 mailing list X, e.g., Msgroup-request for Msgroup.  This
+    ///                 field provides a more general mechanism.
+    /// ```
+    pub fn rmailbx(&self) -> &Name {
+        &self.rmailbx
+    }
+
+    /// ```text
+    /// EMAILBX         A <domain-name> which specifies a mailbox which is to
+    ///                 receive error messages related to the mailing list or
+    ///                 mailbox specified by the owner of the MINFO RR (similar
+    ///                 to the ERRORS-TO: field which has been proposed).  If
+    ///                 this domain name names the root, errors should be
+    ///                 returned to the sender of the message.
+    /// ```
+    pub fn emailbx(&self) -> &Name {
+        &self.emailbx
+    }
+}
+
+impl Parse for MINFO {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(tuple((Name::parse, Name::parse)), |(rmailbx, emailbx)| {
+            Self { rmailbx, emailbx }
+        })(i)
+    }
+}
+
+impl Encode for MINFO {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.rmailbx, true);
+        e.write_name(&self.emailbx, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MINFO {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.rmailbx, self.emailbx)
+    }
+}
+
+impl FromStr for MINFO {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let rmailbx = tokens.next_token()?.parse()?;
+        let emailbx = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self { rmailbx, emailbx })
+    }
+}
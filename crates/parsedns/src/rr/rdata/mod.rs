@@ -16,7 +16,20 @@ mod soa;
 mod txt;
 mod wks;
 
+// RFC3596
+mod aaaa;
+
+// RFC3597
+mod unknown;
+
+// RFC4034
+mod rrsig;
+
+// RFC6891
+mod opt;
+
 pub use a::*;
+pub use aaaa::*;
 pub use cname::*;
 pub use hinfo::*;
 pub use mb::*;
@@ -28,7 +41,10 @@ pub use mr::*;
 pub use mx::*;
 pub use ns::*;
 pub use null::*;
+pub use opt::*;
 pub use ptr::*;
+pub use rrsig::*;
 pub use soa::*;
 pub use txt::*;
+pub use unknown::*;
 pub use wks::*;
@@ -0,0 +1,72 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    NEWNAME                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// MR records cause no additional section processing.  The main use for MR
+/// is as a forwarding entry for a user who has moved to a different
+/// mailbox.
+/// ```
+/// [RFC1035 3.3.8: MR RDATA format (EXPERIMENTAL)](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.8)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MR(Name);
+
+impl MR {
+    pub fn new(newname: Name) -> Self {
+        Self(newname)
+    }
+
+    /// ```text
+    /// NEWNAME         A <domain-name> which specifies a mailbox which is the
+    ///                 proper rename of the specified mailbox.
+    /// ```
+    pub fn newname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for MR {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for MR {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for MR {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let newname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(newname))
+    }
+}
@@ -0,0 +1,99 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, number::complete::be_u16, sequence::tuple, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     |                  PREFERENCE                   |
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                   EXCHANGE                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// MX records cause type A additional section processing for the host
+/// specified by EXCHANGE.  The use of MX RRs is explained in detail in
+/// [RFC-974].
+/// ```
+/// [RFC1035 3.3.9: MX RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.9)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MX {
+    preference: u16,
+    exchange: Name,
+}
+
+impl MX {
+    pub fn new(preference: u16, exchange: Name) -> Self {
+        Self {
+            preference,
+            exchange,
+        }
+    }
+
+    /// ```text
+    /// PREFERENCE      A 16 bit integer which specifies the preference given to
+    ///                 this RR among others at the same owner.  Lower values
+    ///                 are preferred.
+    /// ```
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// ```text
+    /// EXCHANGE        A <domain-name> which specifies a host willing to act as
+    ///                 a mail exchange for the owner name.
+    /// ```
+    pub fn exchange(&self) -> &Name {
+        &self.exchange
+    }
+}
+
+impl Parse for MX {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(tuple((be_u16, Name::parse)), |(preference, exchange)| {
+            Self {
+                preference,
+                exchange,
+            }
+        })(i)
+    }
+}
+
+impl Encode for MX {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u16(self.preference);
+        e.write_name(&self.exchange, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.preference, self.exchange)
+    }
+}
+
+impl FromStr for MX {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let preference = tokens.parse()?;
+        let exchange = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self {
+            preference,
+            exchange,
+        })
+    }
+}
@@ -0,0 +1,79 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                    NSDNAME                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// NS records cause both the usual additional section processing to locate
+/// a type A record, and, when used in a referral, a special search of the
+/// zone in which they reside for glue information.
+///
+/// The NS RR states that the named host should be expected to have a zone
+/// starting at owner name of the specified class.  Note that the class may
+/// not indicate the protocol family which should be used to communicate
+/// with the host, although it is typically a strong hint.  For example,
+/// hosts which are name servers for either Internet (IN) or Hesiod (HS)
+/// class information are normally queried using IN class protocols.
+/// ```
+/// [RFC1035 3.3.11: NS RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.11)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NS(Name);
+
+impl NS {
+    pub fn new(nsdname: Name) -> Self {
+        Self(nsdname)
+    }
+
+    /// ```text
+    /// NSDNAME         A <domain-name> which specifies a host which should be
+    ///                 authoritative for the specified class and domain.
+    /// ```
+    pub fn nsdname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for NS {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for NS {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for NS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for NS {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let nsdname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(nsdname))
+    }
+}
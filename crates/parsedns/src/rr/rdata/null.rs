@@ -0,0 +1,70 @@
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::rest, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::{fmt_generic, Tokens},
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                  <anything>                   /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// Anything at all may be in the RDATA field so long as it is 65535 octets
+/// or less.
+///
+/// NULL records cause no additional section processing.  NULL RRs are not
+/// allowed in master files.  NULLs are used as placeholders in some
+/// experimental extensions of the DNS.
+/// ```
+/// [RFC1035 3.3.10: NULL RDATA format (EXPERIMENTAL)](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.10)
+///
+/// Without a master file format it is presented in the generic `\#` form of
+/// [RFC3597 5](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NULL(Vec<u8>);
+
+impl NULL {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self(data)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Parse for NULL {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        let (i, data) = rest(i)?;
+        Ok((i, Self(data.to_vec())))
+    }
+}
+
+impl Encode for NULL {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_bytes(&self.0);
+        Ok(())
+    }
+}
+
+impl fmt::Display for NULL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_generic(f, &self.0)
+    }
+}
+
+impl FromStr for NULL {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tokens::new(s).generic().map(Self)
+    }
+}
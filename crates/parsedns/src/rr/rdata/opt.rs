@@ -0,0 +1,220 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use nom::{
+    combinator::map,
+    multi::{length_data, many0},
+    number::complete::be_u16,
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError},
+    indexed_input::IByteInput,
+    presentation::fmt_generic,
+    traits::{Encode, Parse},
+};
+
+const DNSSEC_OK_MASK: u16 = 0x8000;
+
+/// ```text
+///                +0 (MSB)                            +1 (LSB)
+///     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///  0: |                          OPTION-CODE                          |
+///     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///  2: |                         OPTION-LENGTH                         |
+///     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///  4: |                                                               |
+///     /                          OPTION-DATA                          /
+///     /                                                               /
+///     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// ```
+/// [RFC6891 6.1.2: Wire format](https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdnsOption {
+    code: u16,
+    data: Vec<u8>,
+}
+
+impl EdnsOption {
+    pub fn new(code: u16, data: Vec<u8>) -> Self {
+        Self { code, data }
+    }
+
+    /// ```text
+    /// OPTION-CODE     Assigned by the Expert Review process as defined by the
+    ///                 DNSEXT working group and the IESG.
+    /// ```
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// ```text
+    /// OPTION-DATA     Varies per OPTION-CODE.  MUST be treated as a bit field.
+    /// ```
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Parse for EdnsOption {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(
+            tuple((be_u16, length_data(be_u16))),
+            |(code, data): (_, IByteInput)| Self {
+                code,
+                data: data.to_vec(),
+            },
+        )(i)
+    }
+}
+
+impl Encode for EdnsOption {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        let len = u16::try_from(self.data.len())
+            .map_err(|_| EncodeError::RDataTooLong(self.data.len()))?;
+        e.write_u16(self.code);
+        e.write_u16(len);
+        e.write_bytes(&self.data);
+        Ok(())
+    }
+}
+
+/// ```text
+/// The fixed part of an OPT RR is structured as follows:
+///
+///     +------------+--------------+------------------------------+
+///     | Field Name | Field Type   | Description                  |
+///     +------------+--------------+------------------------------+
+///     | NAME       | domain name  | MUST be 0 (root domain)      |
+///     | TYPE       | u_int16_t    | OPT (41)                     |
+///     | CLASS      | u_int16_t    | requestor's UDP payload size |
+///     | TTL        | u_int32_t    | extended RCODE and flags     |
+///     | RDLEN      | u_int16_t    | length of all RDATA          |
+///     | RDATA      | octet stream | {attribute,value} pairs      |
+///     +------------+--------------+------------------------------+
+///
+/// The extended RCODE and flags, which OPT stores in the RR Time to Live
+/// (TTL) field, are structured as follows:
+///
+///                 +0 (MSB)                            +1 (LSB)
+///      +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///   0: |         EXTENDED-RCODE        |            VERSION            |
+///      +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///   2: | DO|                           Z                               |
+///      +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// ```
+/// [RFC6891 6.1.2: Wire format](https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.2)
+///
+/// A pseudo-RR, the CLASS and TTL of its record are read into and written from this struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OPT {
+    udp_payload_size: u16,
+    extended_rcode: u8,
+    version: u8,
+    dnssec_ok: bool,
+    z: u16,
+    options: Vec<EdnsOption>,
+}
+
+impl OPT {
+    pub fn new(udp_payload_size: u16) -> Self {
+        Self {
+            udp_payload_size,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: false,
+            z: 0,
+            options: Vec::new(),
+        }
+    }
+
+    /// Parse the options in RDATA, splitting the CLASS and TTL of the record into their EDNS
+    /// fields
+    pub(crate) fn parse(
+        i: IByteInput,
+        class: u16,
+        ttl: u32,
+    ) -> IResult<IByteInput, Self, ParserError> {
+        let flags = ttl as u16;
+        map(many0(EdnsOption::parse), move |options| Self {
+            udp_payload_size: class,
+            extended_rcode: (ttl >> 24) as u8,
+            version: (ttl >> 16) as u8,
+            dnssec_ok: flags & DNSSEC_OK_MASK != 0,
+            z: flags & !DNSSEC_OK_MASK,
+            options,
+        })(i)
+    }
+
+    /// The EDNS fields stored in the TTL of the OPT record
+    pub(crate) fn record_ttl(&self) -> u32 {
+        let flags = if self.dnssec_ok { DNSSEC_OK_MASK } else { 0 } | (self.z & !DNSSEC_OK_MASK);
+        (self.extended_rcode as u32) << 24 | (self.version as u32) << 16 | flags as u32
+    }
+
+    /// The number of octets of the largest UDP payload that can be reassembled and delivered in
+    /// the requestor's network stack.
+    pub fn udp_payload_size(&self) -> u16 {
+        self.udp_payload_size
+    }
+
+    /// Forms the upper 8 bits of extended 12-bit RCODE (together with the 4 bits defined in
+    /// [RFC1035]). Note that EXTENDED-RCODE value 0 indicates that an unextended RCODE is in use
+    /// (values 0 through 15).
+    pub fn extended_rcode(&self) -> u8 {
+        self.extended_rcode
+    }
+
+    /// Indicates the implementation level of the setter.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// DNSSEC OK bit as defined by [RFC3225](https://datatracker.ietf.org/doc/html/rfc3225).
+    pub fn dnssec_ok(&self) -> bool {
+        self.dnssec_ok
+    }
+
+    /// Set to zero by senders and ignored by receivers, unless modified in a subsequent
+    /// specification.
+    pub fn z(&self) -> u16 {
+        self.z
+    }
+
+    pub fn options(&self) -> &[EdnsOption] {
+        &self.options
+    }
+
+    pub fn set_extended_rcode(&mut self, extended_rcode: u8) {
+        self.extended_rcode = extended_rcode;
+    }
+
+    pub fn set_dnssec_ok(&mut self, dnssec_ok: bool) {
+        self.dnssec_ok = dnssec_ok;
+    }
+
+    pub fn push_option(&mut self, option: EdnsOption) {
+        self.options.push(option);
+    }
+}
+
+impl Encode for OPT {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        for option in &self.options {
+            option.encode(e)?;
+        }
+        Ok(())
+    }
+}
+
+/// OPT has no master file format, the options are shown in the generic `\#` form
+impl fmt::Display for OPT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut e = Encoder::uncompressed();
+        self.encode(&mut e).map_err(|_| fmt::Error)?;
+        fmt_generic(f, &e.finish())
+    }
+}
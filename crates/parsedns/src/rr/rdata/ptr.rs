@@ -0,0 +1,74 @@
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                   PTRDNAME                    /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// PTR records cause no additional section processing.  These RRs are used
+/// in special domains to point to some other location in the domain space.
+/// These records are simple data, and don't imply any special processing
+/// similar to that performed by CNAME, which identifies aliases.  See the
+/// description of the IN-ADDR.ARPA domain for an example.
+/// ```
+/// [RFC1035 3.3.12: PTR RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.12)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PTR(Name);
+
+impl PTR {
+    pub fn new(ptrdname: Name) -> Self {
+        Self(ptrdname)
+    }
+
+    /// ```text
+    /// PTRDNAME        A <domain-name> which points to some location in the
+    ///                 domain name space.
+    /// ```
+    pub fn ptrdname(&self) -> &Name {
+        &self.0
+    }
+}
+
+impl Parse for PTR {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(Name::parse, Self)(i)
+    }
+}
+
+impl Encode for PTR {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.0, true);
+        Ok(())
+    }
+}
+
+impl fmt::Display for PTR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for PTR {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let ptrdname = tokens.next_token()?.parse()?;
+        tokens.finish()?;
+        Ok(Self(ptrdname))
+    }
+}
@@ -0,0 +1,351 @@
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use nom::{
+    combinator::{map, rest},
+    number::complete::{be_u16, be_u32, be_u8},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::{name::Name, types::RecordType},
+    traits::{Encode, Parse},
+    utils::{civil_from_days, days_from_civil, from_base64, to_base64, TTL},
+};
+
+/// ```text
+///                      1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |        Type Covered           |  Algorithm    |     Labels    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Original TTL                          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Signature Expiration                     |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Signature Inception                      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |            Key Tag            |                               /
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+         Signer's Name         /
+/// /                                                               /
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// /                                                               /
+/// /                            Signature                          /
+/// /                                                               /
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+/// [RFC4034 3.1: RRSIG RDATA Wire Format](https://datatracker.ietf.org/doc/html/rfc4034#section-3.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRSIG {
+    type_covered: RecordType,
+    algorithm: u8,
+    labels: u8,
+    original_ttl: TTL,
+    expiration: u32,
+    inception: u32,
+    key_tag: u16,
+    signer_name: Name,
+    signature: Vec<u8>,
+}
+
+impl RRSIG {
+    /// The RRSIG fields that precede the signature. The signature starts out empty so the
+    /// signing input can be built before it is known.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        type_covered: RecordType,
+        algorithm: u8,
+        labels: u8,
+        original_ttl: TTL,
+        expiration: u32,
+        inception: u32,
+        key_tag: u16,
+        signer_name: Name,
+    ) -> Self {
+        Self {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag,
+            signer_name,
+            signature: Vec::new(),
+        }
+    }
+
+    /// ```text
+    /// The Type Covered field identifies the type of the RRset that is
+    /// covered by this RRSIG record.
+    /// ```
+    pub fn type_covered(&self) -> RecordType {
+        self.type_covered
+    }
+
+    /// ```text
+    /// The Algorithm Number field identifies the cryptographic algorithm
+    /// used to create the signature.
+    /// ```
+    pub fn algorithm(&self) -> u8 {
+        self.algorithm
+    }
+
+    /// ```text
+    /// The Labels field specifies the number of labels in the original RRSIG
+    /// RR owner name.  The significance of this field is that a validator
+    /// uses it to determine whether the answer was synthesized from a
+    /// wildcard.
+    /// ```
+    pub fn labels(&self) -> u8 {
+        self.labels
+    }
+
+    /// ```text
+    /// The Original TTL field specifies the TTL of the covered RRset as it
+    /// appears in the authoritative zone.
+    /// ```
+    pub fn original_ttl(&self) -> TTL {
+        self.original_ttl
+    }
+
+    /// ```text
+    /// The Signature Expiration and Inception fields specify a validity
+    /// period for the signature.  The RRSIG record MUST NOT be used for
+    /// authentication prior to the inception date and MUST NOT be used for
+    /// authentication after the expiration date.
+    /// ```
+    /// Seconds since 1 January 1970 00:00:00 UTC, compared with serial number arithmetic.
+    pub fn expiration(&self) -> u32 {
+        self.expiration
+    }
+
+    /// See [`RRSIG::expiration`]
+    pub fn inception(&self) -> u32 {
+        self.inception
+    }
+
+    /// ```text
+    /// The Key Tag field contains the key tag value of the DNSKEY RR that
+    /// validates this signature, in network byte order.
+    /// ```
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    /// ```text
+    /// The Signer's Name field value identifies the owner name of the DNSKEY
+    /// RR that a validator is supposed to use to validate this signature.
+    /// ```
+    pub fn signer_name(&self) -> &Name {
+        &self.signer_name
+    }
+
+    /// ```text
+    /// The Signature field contains the cryptographic signature that covers
+    /// the RRSIG RDATA (excluding the Signature field) and the RRset
+    /// specified by the RRSIG owner name, RRSIG class, and RRSIG Type
+    /// Covered field.
+    /// ```
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    pub fn set_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature;
+    }
+
+    /// The RDATA without the signature. The signer's name is never compressed.
+    pub(crate) fn encode_fields(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.type_covered.encode(e)?;
+        e.write_u8(self.algorithm);
+        e.write_u8(self.labels);
+        self.original_ttl.encode(e)?;
+        e.write_u32(self.expiration);
+        e.write_u32(self.inception);
+        e.write_u16(self.key_tag);
+        e.write_name(&self.signer_name, false);
+        Ok(())
+    }
+}
+
+impl Parse for RRSIG {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(
+            tuple((
+                RecordType::parse,
+                be_u8,
+                be_u8,
+                map(be_u32, TTL::from),
+                be_u32,
+                be_u32,
+                be_u16,
+                Name::parse,
+                rest,
+            )),
+            |(
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer_name,
+                signature,
+            ): (_, _, _, _, _, _, _, _, IByteInput)| Self {
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer_name,
+                signature: signature.to_vec(),
+            },
+        )(i)
+    }
+}
+
+impl Encode for RRSIG {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.encode_fields(e)?;
+        e.write_bytes(&self.signature);
+        Ok(())
+    }
+}
+
+/// Writes a time as `YYYYMMDDHHmmSS` in UTC
+///
+/// [RFC4034 3.2: The RRSIG RR Presentation Format](https://datatracker.ietf.org/doc/html/rfc4034#section-3.2)
+struct Timestamp(u32);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0 as i64;
+        let (year, month, day) = civil_from_days(secs / 86400);
+        let secs = secs % 86400;
+        write!(
+            f,
+            "{:04}{:02}{:02}{:02}{:02}{:02}",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+/// Reads a time as `YYYYMMDDHHmmSS` or as decimal seconds since the epoch
+impl FromStr for Timestamp {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 14 {
+            return s
+                .parse()
+                .map(Self)
+                .map_err(|_| PresentationError::InvalidNumber);
+        }
+
+        let field = |range: core::ops::Range<usize>, max: i64| -> Result<i64, PresentationError> {
+            s[range]
+                .parse::<i64>()
+                .ok()
+                .filter(|&v| v <= max)
+                .ok_or(PresentationError::InvalidNumber)
+        };
+        let (year, month, day) = (field(0..4, 9999)?, field(4..6, 12)?, field(6..8, 31)?);
+        let (hour, minute, second) = (field(8..10, 23)?, field(10..12, 59)?, field(12..14, 59)?);
+        if month == 0 || day == 0 {
+            return Err(PresentationError::InvalidNumber);
+        }
+
+        let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+        u32::try_from(secs)
+            .map(Self)
+            .map_err(|_| PresentationError::InvalidNumber)
+    }
+}
+
+impl fmt::Display for RRSIG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {}",
+            self.type_covered,
+            self.algorithm,
+            self.labels,
+            u32::from(self.original_ttl),
+            Timestamp(self.expiration),
+            Timestamp(self.inception),
+            self.key_tag,
+            self.signer_name,
+            to_base64(&self.signature)
+        )
+    }
+}
+
+/// The signature may be split by whitespace
+impl FromStr for RRSIG {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let type_covered = tokens.next_token()?.parse()?;
+        let algorithm = tokens.parse()?;
+        let labels = tokens.parse()?;
+        let original_ttl = TTL::from(tokens.parse::<u32>()?);
+        let expiration = tokens.next_token()?.parse::<Timestamp>()?.0;
+        let inception = tokens.next_token()?.parse::<Timestamp>()?.0;
+        let key_tag = tokens.parse()?;
+        let signer_name = tokens.next_token()?.parse()?;
+        let signature = from_base64(tokens.rest())?;
+        Ok(Self {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag,
+            signer_name,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::rr::types::RecordType;
+
+    use super::RRSIG;
+
+    #[test]
+    fn test_presentation() {
+        // The example of RFC4034 3.3
+        let text = "A 5 3 86400 20030322173103 20030220173103 2642 example.com. \
+                    oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTrPYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6o \
+                    B9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3tGNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkG \
+                    J5D6fwFm8nN+6pBzeDQfsS3Ap3o=";
+        let rrsig: RRSIG = text.parse().unwrap();
+        assert_eq!(rrsig.type_covered(), RecordType::A);
+        assert_eq!(rrsig.expiration(), 1048354263);
+        assert_eq!(rrsig.inception(), 1045762263);
+        assert_eq!(rrsig.signature().len(), 128);
+        assert_eq!(rrsig.to_string().parse::<RRSIG>().unwrap(), rrsig);
+        assert!(rrsig
+            .to_string()
+            .contains(" 20030322173103 20030220173103 "));
+    }
+}
@@ -1,12 +1,15 @@
-use nom::{
-    combinator::map,
-    number::{complete::be_i32, streaming::be_u32},
-    sequence::tuple,
-    IResult,
-};
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, number::complete::be_u32, sequence::tuple, IResult};
 
 use crate::{
-    error::ParserError, indexed_input::IByteInput, rr::name::Name, traits::Parse, utils::TTL,
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::Tokens,
+    rr::name::Name,
+    traits::{Encode, Parse},
+    utils::{Serial, TTL},
 };
 
 /// ```text
@@ -50,10 +53,11 @@ use crate::{
 /// change the SOA RR with known semantics.
 /// ```
 /// [RFC1035 3.3.13: SOA RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.13)
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SOA {
     mname: Name,
     rname: Name,
-    serial: u32,
+    serial: Serial,
     refresh: u32,
     retry: u32,
     expire: u32,
@@ -61,6 +65,26 @@ pub struct SOA {
 }
 
 impl SOA {
+    pub fn new(
+        mname: Name,
+        rname: Name,
+        serial: Serial,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: TTL,
+    ) -> Self {
+        Self {
+            mname,
+            rname,
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum,
+        }
+    }
+
     /// ```text
     /// MNAME           The <domain-name> of the name server that was the
     ///                 original or primary source of data for this zone.
@@ -83,7 +107,7 @@ impl SOA {
     ///                 value wraps and should be compared using sequence space
     ///                 arithmetic.
     /// ```
-    pub fn serial(&self) -> u32 {
+    pub fn serial(&self) -> Serial {
         self.serial
     }
 
@@ -127,7 +151,7 @@ impl Parse for SOA {
             tuple((
                 Name::parse,
                 Name::parse,
-                be_u32,
+                Serial::parse,
                 be_u32,
                 be_u32,
                 be_u32,
@@ -140,8 +164,55 @@ impl Parse for SOA {
                 refresh,
                 retry,
                 expire,
-                minimum: minimum.into(),
+                minimum,
             },
         )(i)
     }
 }
+
+impl Encode for SOA {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.mname, true);
+        e.write_name(&self.rname, true);
+        self.serial.encode(e)?;
+        e.write_u32(self.refresh);
+        e.write_u32(self.retry);
+        e.write_u32(self.expire);
+        self.minimum.encode(e)
+    }
+}
+
+impl fmt::Display for SOA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {}",
+            self.mname,
+            self.rname,
+            self.serial,
+            self.refresh,
+            self.retry,
+            self.expire,
+            u32::from(self.minimum)
+        )
+    }
+}
+
+impl FromStr for SOA {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let soa = Self {
+            mname: tokens.next_token()?.parse()?,
+            rname: tokens.next_token()?.parse()?,
+            serial: tokens.parse()?,
+            refresh: tokens.parse()?,
+            retry: tokens.parse()?,
+            expire: tokens.parse()?,
+            minimum: TTL::from(tokens.parse::<u32>()?),
+        };
+        tokens.finish()?;
+        Ok(soa)
+    }
+}
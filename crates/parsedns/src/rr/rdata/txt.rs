@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    rr::character_string::{CharacterString, TxtData},
+    traits::{Encode, Parse},
+};
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                   TXT-DATA                    /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// TXT-DATA        One or more <character-string>s.
+///
+/// TXT RRs are used to hold descriptive text.  The semantics of the text
+/// depends on the domain where it is found.
+/// ```
+/// [RFC1035 3.3.14: TXT RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.3.14)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TXT(TxtData);
+
+impl TXT {
+    pub fn new(data: TxtData) -> Self {
+        Self(data)
+    }
+
+    /// A TXT record holding a value of any length, such as a DKIM key, split into
+    /// character-strings
+    pub fn from_long(data: &[u8]) -> Self {
+        Self(TxtData::from_long(data))
+    }
+
+    pub fn data(&self) -> &TxtData {
+        &self.0
+    }
+
+    pub fn strings(&self) -> &[CharacterString] {
+        self.0.strings()
+    }
+
+    /// The value of the record with its strings joined
+    pub fn concat(&self) -> Vec<u8> {
+        self.0.concat()
+    }
+}
+
+impl Parse for TXT {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(TxtData::parse, Self)(i)
+    }
+}
+
+impl Encode for TXT {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.0.encode(e)
+    }
+}
+
+impl fmt::Display for TXT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for TXT {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
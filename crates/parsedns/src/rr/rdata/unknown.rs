@@ -0,0 +1,74 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use nom::{combinator::rest, IResult};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    presentation::{fmt_generic, Tokens},
+    rr::types::RecordType,
+    traits::Encode,
+};
+
+/// ```text
+/// In the absence of a specification of the RDATA format of an RR type
+/// or class, implementations MUST treat the RDATA as an opaque string of
+/// octets.
+/// ```
+/// [RFC3597 2: Definition](https://datatracker.ietf.org/doc/html/rfc3597#section-2)
+///
+/// Holds the RDATA of any type (or type and class pair) without a parsed format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unknown {
+    rtype: RecordType,
+    data: Vec<u8>,
+}
+
+impl Unknown {
+    pub fn new(rtype: RecordType, data: Vec<u8>) -> Self {
+        Self { rtype, data }
+    }
+
+    pub fn rtype(&self) -> RecordType {
+        self.rtype
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub(crate) fn parse(
+        i: IByteInput,
+        rtype: RecordType,
+    ) -> IResult<IByteInput, Self, ParserError> {
+        let (i, data) = rest(i)?;
+        Ok((
+            i,
+            Self {
+                rtype,
+                data: data.to_vec(),
+            },
+        ))
+    }
+
+    /// Read the generic `\#` form of RDATA
+    pub fn from_presentation(rtype: RecordType, s: &str) -> Result<Self, PresentationError> {
+        let data = Tokens::new(s).generic()?;
+        Ok(Self { rtype, data })
+    }
+}
+
+impl Encode for Unknown {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_bytes(&self.data);
+        Ok(())
+    }
+}
+
+impl fmt::Display for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_generic(f, &self.data)
+    }
+}
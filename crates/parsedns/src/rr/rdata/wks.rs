@@ -0,0 +1,157 @@
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use nom::{
+    combinator::{map, rest},
+    number::complete::be_u8,
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    net::Ipv4Addr,
+    presentation::Tokens,
+    traits::{Encode, Parse},
+};
+
+const PROTOCOL_TCP: u8 = 6;
+const PROTOCOL_UDP: u8 = 17;
+
+/// ```text
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     |                    ADDRESS                    |
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     |       PROTOCOL        |                       |
+///     +--+--+--+--+--+--+--+--+                       |
+///     |                                               |
+///     /                   <BIT MAP>                   /
+///     /                                               /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// The WKS record is used to describe the well known services supported by
+/// a particular protocol on a particular internet address.  The PROTOCOL
+/// field specifies an IP protocol number, and the bit map has one bit per
+/// port of the specified protocol.  The first bit corresponds to port 0,
+/// the second to port 1, etc.  If the bit map does not include a bit for a
+/// protocol of interest, that bit is assumed zero.
+///
+/// WKS RRs cause no additional section processing.
+///
+/// In master files, both ports and protocols are expressed using mnemonics
+/// or decimal numbers.
+/// ```
+/// [RFC1035 3.4.2: WKS RDATA format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.4.2)
+///
+/// An internet specific RR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WKS {
+    address: Ipv4Addr,
+    protocol: u8,
+    bitmap: Vec<u8>,
+}
+
+impl WKS {
+    pub fn new(address: Ipv4Addr, protocol: u8, bitmap: Vec<u8>) -> Self {
+        Self {
+            address,
+            protocol,
+            bitmap,
+        }
+    }
+
+    /// ```text
+    /// ADDRESS         An 32 bit Internet address
+    /// ```
+    pub fn address(&self) -> Ipv4Addr {
+        self.address
+    }
+
+    /// ```text
+    /// PROTOCOL        An 8 bit IP protocol number
+    /// ```
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// ```text
+    /// <BIT MAP>       A variable length bit map.  The bit map must be a
+    ///                 multiple of 8 bits long.
+    /// ```
+    pub fn bitmap(&self) -> &[u8] {
+        &self.bitmap
+    }
+
+    /// The ports set in the bit map
+    pub fn ports(&self) -> impl Iterator<Item = u16> + '_ {
+        self.bitmap.iter().enumerate().flat_map(|(idx, byte)| {
+            (0..8)
+                .filter(move |bit| byte & (0x80 >> bit) != 0)
+                .map(move |bit| (idx * 8 + bit) as u16)
+        })
+    }
+}
+
+impl Parse for WKS {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(
+            tuple((Ipv4Addr::parse, be_u8, rest)),
+            |(address, protocol, bitmap): (_, _, IByteInput)| Self {
+                address,
+                protocol,
+                bitmap: bitmap.to_vec(),
+            },
+        )(i)
+    }
+}
+
+impl Encode for WKS {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        self.address.encode(e)?;
+        e.write_u8(self.protocol);
+        e.write_bytes(&self.bitmap);
+        Ok(())
+    }
+}
+
+impl fmt::Display for WKS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.address, self.protocol)?;
+        for port in self.ports() {
+            write!(f, " {}", port)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for WKS {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let address = tokens.address()?;
+        let protocol = match tokens.next_token()? {
+            p if p.eq_ignore_ascii_case("tcp") => PROTOCOL_TCP,
+            p if p.eq_ignore_ascii_case("udp") => PROTOCOL_UDP,
+            p => p.parse().map_err(|_| PresentationError::InvalidNumber)?,
+        };
+
+        let mut bitmap = Vec::new();
+        while !tokens.is_empty() {
+            let port: u16 = tokens.parse()?;
+            let idx = (port / 8) as usize;
+            if bitmap.len() <= idx {
+                bitmap.resize(idx + 1, 0);
+            }
+            bitmap[idx] |= 0x80 >> (port % 8);
+        }
+
+        Ok(Self {
+            address,
+            protocol,
+            bitmap,
+        })
+    }
+}
@@ -1,18 +1,21 @@
+use core::fmt;
+
 use nom::{
     bytes::complete::take,
-    combinator::{map, map_res},
     number::complete::{be_u16, be_u32},
-    IResult, InputTake,
+    IResult,
 };
 
 use crate::{
-    error::{ParserError, ParserErrorType},
+    encoder::Encoder,
+    error::{EncodeError, ParserError},
     indexed_input::IByteInput,
-    traits::Parse,
+    presentation::Columns,
+    traits::{Encode, Parse},
     utils::TTL,
 };
 
-use super::{class::RecordClass, data::RecordData, name::Name, rdata::*, types::RecordType};
+use super::{class::RecordClass, data::RecordData, name::Name, rdata::OPT, types::RecordType};
 
 /// ```text
 /// The answer, authority, and additional sections all share the same
@@ -42,6 +45,9 @@ use super::{class::RecordClass, data::RecordData, name::Name, rdata::*, types::R
 /// ```
 ///
 /// [RFC1035 4.1.3 Resource record format](https://www.rfc-editor.org/rfc/rfc1035.html#section-4.1.3)
+///
+/// For OPT pseudo-records the CLASS and TTL fields carry EDNS data, which is kept in [`OPT`].
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Record {
     /// A domain name to which this resource record pertains
     name: Name,
@@ -67,6 +73,55 @@ pub struct Record {
 }
 
 impl Record {
+    pub fn new(name: Name, class: RecordClass, ttl: TTL, rdata: RecordData) -> Self {
+        Self {
+            name,
+            rtype: rdata.rtype(),
+            class,
+            ttl,
+            rdata,
+        }
+    }
+
+    /// The OPT pseudo-record, owned by the root
+    pub fn opt(opt: OPT) -> Self {
+        Self {
+            name: Name::root(),
+            rtype: RecordType::OPT,
+            class: RecordClass::from(opt.udp_payload_size()),
+            ttl: TTL::from(opt.record_ttl()),
+            rdata: RecordData::OPT(opt),
+        }
+    }
+
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn rtype(&self) -> RecordType {
+        self.rtype
+    }
+
+    pub fn class(&self) -> RecordClass {
+        self.class
+    }
+
+    pub fn ttl(&self) -> TTL {
+        self.ttl
+    }
+
+    /// Replace the TTL, such as when counting it down in a cache. The TTL field of an OPT
+    /// pseudo-record carries EDNS data and is left as is.
+    pub fn set_ttl(&mut self, ttl: TTL) {
+        if !matches!(self.rdata, RecordData::OPT(_)) {
+            self.ttl = ttl;
+        }
+    }
+
+    pub fn rdata(&self) -> &RecordData {
+        &self.rdata
+    }
+
     fn parse_rdata<'a>(
         i: IByteInput<'a>,
         rtype: &RecordType,
@@ -75,13 +130,21 @@ impl Record {
         let (i, rd_length) = be_u16(i)?;
         let (i, rdata_buf) = take(rd_length)(i)?;
 
-        let rdata = match (rtype, class) {
-            (RecordType::A, RecordClass::IN) => RecordData::A(A::parse(rdata_buf)?.1),
-            (RecordType::SOA, _) => RecordData::SOA(SOA::parse(rdata_buf)?.1),
-            _ => todo!(),
-        };
+        let (_, rdata) = RecordData::parse(rdata_buf, *rtype, *class)?;
 
-        return Ok((i, rdata));
+        Ok((i, rdata))
+    }
+
+    fn parse_opt(i: IByteInput, name: Name) -> IResult<IByteInput, Self, ParserError> {
+        let (i, class) = be_u16(i)?;
+        let (i, ttl) = be_u32(i)?;
+        let (i, rd_length) = be_u16(i)?;
+        let (i, rdata_buf) = take(rd_length)(i)?;
+        let (_, opt) = nom::combinator::all_consuming(|i| OPT::parse(i, class, ttl))(rdata_buf)?;
+
+        let mut record = Self::opt(opt);
+        record.name = name;
+        Ok((i, record))
     }
 }
 
@@ -89,6 +152,10 @@ impl Parse for Record {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
         let (i, name) = Name::parse(i)?;
         let (i, rtype) = RecordType::parse(i)?;
+        if let RecordType::OPT = rtype {
+            return Self::parse_opt(i, name);
+        }
+
         let (i, class) = RecordClass::parse(i)?;
         let (i, ttl) = TTL::parse(i)?;
         let (i, rdata) = Self::parse_rdata(i, &rtype, &class)?;
@@ -105,3 +172,48 @@ impl Parse for Record {
         ))
     }
 }
+
+impl Encode for Record {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_name(&self.name, true);
+        self.rtype.encode(e)?;
+        match &self.rdata {
+            RecordData::OPT(opt) => {
+                e.write_u16(opt.udp_payload_size());
+                e.write_u32(opt.record_ttl());
+            }
+            _ => {
+                self.class.encode(e)?;
+                self.ttl.encode(e)?;
+            }
+        }
+
+        let rd_length_pos = e.len();
+        e.write_u16(0);
+        self.rdata.encode(e)?;
+
+        let rd_length = e.len() - rd_length_pos - 2;
+        let rd_length =
+            u16::try_from(rd_length).map_err(|_| EncodeError::RDataTooLong(rd_length))?;
+        e.patch_u16(rd_length_pos, rd_length);
+        Ok(())
+    }
+}
+
+/// The master file format of the record, aligned as `dig` prints it
+///
+/// ```text
+/// example.com.           3600    IN      A       93.184.216.34
+/// ```
+///
+/// [RFC1035 5.1: Master file format](https://datatracker.ietf.org/doc/html/rfc1035#section-5.1)
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut columns = Columns::new(f);
+        columns.field(0, &self.name)?;
+        columns.field(Columns::TTL, u32::from(self.ttl))?;
+        columns.field(Columns::CLASS, self.class)?;
+        columns.field(Columns::TYPE, self.rtype)?;
+        columns.field(Columns::RDATA, &self.rdata)
+    }
+}
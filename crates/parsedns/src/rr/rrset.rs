@@ -0,0 +1,312 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::{
+    encoder::Encoder,
+    message::{Message, Section},
+    traits::Encode,
+    utils::TTL,
+};
+
+use super::{class::RecordClass, data::RecordData, name::Name, record::Record, types::RecordType};
+
+/// The owner name, type and class shared by the records of an RRset
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RRsetKey {
+    pub name: Name,
+    pub rtype: RecordType,
+    pub class: RecordClass,
+}
+
+impl RRsetKey {
+    pub fn new(name: Name, rtype: RecordType, class: RecordClass) -> Self {
+        Self { name, rtype, class }
+    }
+
+    pub fn of(record: &Record) -> Self {
+        Self::new(record.name().clone(), record.rtype(), record.class())
+    }
+}
+
+/// ```text
+/// Each DNS Resource Record (RR) has a label, class, type, and data.  It
+/// is meaningless for two records to ever have label, class, type and
+/// data all equal - servers should suppress such duplicates if
+/// encountered.  It is however possible for most record types to exist
+/// with the same label, class and type, but with different data.  Such a
+/// group of records is hereby defined to be a Resource Record Set
+/// (RRSet).
+/// ```
+/// [RFC2181 5: Resource Record Sets](https://datatracker.ietf.org/doc/html/rfc2181#section-5)
+///
+/// The RDATA is kept deduplicated in canonical order, and all records share one TTL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRset {
+    key: RRsetKey,
+    ttl: TTL,
+    rdatas: Vec<RecordData>,
+}
+
+/// RDATA in canonical form: uncompressed with lowercase names, or None when it can't be encoded
+///
+/// [RFC4034 6.2: Canonical RR Form](https://datatracker.ietf.org/doc/html/rfc4034#section-6.2)
+pub(crate) fn canonical_rdata(rdata: &RecordData) -> Option<Vec<u8>> {
+    let mut e = Encoder::canonical();
+    rdata.encode(&mut e).ok()?;
+    Some(e.finish())
+}
+
+impl RRset {
+    /// An empty RRset
+    pub fn new(key: RRsetKey, ttl: TTL) -> Self {
+        Self {
+            key,
+            ttl,
+            rdatas: Vec::new(),
+        }
+    }
+
+    pub fn key(&self) -> &RRsetKey {
+        &self.key
+    }
+
+    pub fn name(&self) -> &Name {
+        &self.key.name
+    }
+
+    pub fn rtype(&self) -> RecordType {
+        self.key.rtype
+    }
+
+    pub fn class(&self) -> RecordClass {
+        self.key.class
+    }
+
+    pub fn ttl(&self) -> TTL {
+        self.ttl
+    }
+
+    pub fn set_ttl(&mut self, ttl: TTL) {
+        self.ttl = ttl;
+    }
+
+    /// The RDATA of the records in canonical order
+    pub fn rdatas(&self) -> &[RecordData] {
+        &self.rdatas
+    }
+
+    pub fn len(&self) -> usize {
+        self.rdatas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rdatas.is_empty()
+    }
+
+    /// Add RDATA at its canonical position, returning false when it is already present
+    ///
+    /// ```text
+    /// For the purposes of DNS security, the canonical ordering of RRs is
+    /// to sort them by the canonical form of their RDATA as left-justified
+    /// unsigned octet sequences in which the absence of an octet sorts
+    /// before a zero octet.
+    /// ```
+    /// [RFC4034 6.3: Canonical RR Ordering within an RRset](https://datatracker.ietf.org/doc/html/rfc4034#section-6.3)
+    ///
+    /// RDATA that can't be encoded has no canonical form. It sorts first, in insertion order, and
+    /// is only deduplicated against equal RDATA.
+    pub fn insert(&mut self, rdata: RecordData) -> bool {
+        let Some(canonical) = canonical_rdata(&rdata) else {
+            if self.rdatas.contains(&rdata) {
+                return false;
+            }
+            let idx = self
+                .rdatas
+                .partition_point(|other| canonical_rdata(other).is_none());
+            self.rdatas.insert(idx, rdata);
+            return true;
+        };
+
+        match self
+            .rdatas
+            .binary_search_by(|other| match canonical_rdata(other) {
+                Some(other) => other.cmp(&canonical),
+                None => Ordering::Less,
+            }) {
+            Ok(_) => false,
+            Err(idx) => {
+                self.rdatas.insert(idx, rdata);
+                true
+            }
+        }
+    }
+
+    /// Whether the record has the owner name, type and class of the RRset
+    pub fn matches(&self, record: &Record) -> bool {
+        record.name() == self.name()
+            && record.rtype() == self.rtype()
+            && record.class() == self.class()
+    }
+
+    /// Add the RDATA of a record, returning false when it is already present or the record
+    /// belongs to another RRset.
+    ///
+    /// ```text
+    /// Should an authoritative source send such a malformed RRSet, the
+    /// client should treat the RRs for all purposes as if all TTLs in the
+    /// RRSet had been set to the value of the lowest TTL in the RRSet.
+    /// ```
+    /// [RFC2181 5.2: TTLs of RRs in an RRSet](https://datatracker.ietf.org/doc/html/rfc2181#section-5.2)
+    pub fn push(&mut self, record: &Record) -> bool {
+        if !self.matches(record) {
+            return false;
+        }
+
+        if self.is_empty() || record.ttl() < self.ttl {
+            self.ttl = record.ttl();
+        }
+        self.insert(record.rdata().clone())
+    }
+
+    /// The records of the RRset in canonical order
+    pub fn records(&self) -> impl ExactSizeIterator<Item = Record> + '_ {
+        self.rdatas.iter().map(move |rdata| {
+            Record::new(self.name().clone(), self.class(), self.ttl, rdata.clone())
+        })
+    }
+
+    /// Group records into RRsets, in the order each RRset first appears. OPT pseudo-records
+    /// describe the message rather than data and are skipped.
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a Record>) -> Vec<RRset> {
+        let mut rrsets: Vec<RRset> = Vec::new();
+        for record in records {
+            if let RecordData::OPT(_) = record.rdata() {
+                continue;
+            }
+
+            let key = RRsetKey::of(record);
+            let idx = match rrsets.iter().position(|rrset| rrset.key == key) {
+                Some(idx) => idx,
+                None => {
+                    rrsets.push(RRset::new(key, record.ttl()));
+                    rrsets.len() - 1
+                }
+            };
+            rrsets[idx].push(record);
+        }
+        rrsets
+    }
+}
+
+impl Message {
+    /// The RRsets of a section of the message
+    pub fn rrsets(&self, section: Section) -> Vec<RRset> {
+        RRset::from_records(self.records(section))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        message::{Message, Section},
+        net::Ipv4Addr,
+        rr::{
+            class::RecordClass,
+            data::RecordData,
+            rdata::{EdnsOption, A, MX, NS, OPT},
+            record::Record,
+            types::RecordType,
+        },
+        utils::TTL,
+    };
+
+    use super::{RRset, RRsetKey};
+
+    fn record(name: &str, ttl: u32, rdata: RecordData) -> Record {
+        Record::new(
+            name.parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(ttl),
+            rdata,
+        )
+    }
+
+    fn a(address: [u8; 4]) -> RecordData {
+        RecordData::A(A::new(Ipv4Addr::from(address)))
+    }
+
+    #[test]
+    fn test_rrsets_from_section() {
+        let mut message = Message::new();
+        message.answers = vec![
+            record("example.com.", 300, a([192, 0, 2, 2])),
+            record("example.com.", 60, a([192, 0, 2, 10])),
+            record(
+                "example.com.",
+                3600,
+                RecordData::NS(NS::new("ns.example.com.".parse().unwrap())),
+            ),
+            record("EXAMPLE.com.", 120, a([192, 0, 2, 1])),
+            record("example.com.", 300, a([192, 0, 2, 2])),
+        ];
+        message.additionals.push(Record::opt(OPT::new(1232)));
+
+        let rrsets = message.rrsets(Section::Answer);
+        assert_eq!(rrsets.len(), 2);
+
+        let addresses = &rrsets[0];
+        assert_eq!(addresses.ttl(), TTL::from(60));
+        assert_eq!(
+            addresses.rdatas(),
+            [a([192, 0, 2, 1]), a([192, 0, 2, 2]), a([192, 0, 2, 10])]
+        );
+        assert!(addresses.records().all(|r| r.ttl() == TTL::from(60)));
+        assert_eq!(rrsets[1].len(), 1);
+
+        assert!(message.rrsets(Section::Additional).is_empty());
+    }
+
+    #[test]
+    fn test_canonical_rdata_order() {
+        let mx = |preference, exchange: &str| {
+            RecordData::MX(MX::new(preference, exchange.parse().unwrap()))
+        };
+        let records = [
+            record("example.com.", 300, mx(10, "b.example.com.")),
+            record("example.com.", 300, mx(10, "A.example.com.")),
+            record("example.com.", 300, mx(10, "a.example.com.")),
+            record("example.com.", 300, mx(5, "z.example.com.")),
+        ];
+
+        let rrsets = RRset::from_records(&records);
+        assert_eq!(
+            rrsets[0].rdatas(),
+            [
+                mx(5, "z.example.com."),
+                mx(10, "a.example.com."),
+                mx(10, "b.example.com.")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unencodable_rdata_kept_apart() {
+        // Options too long for their 16 bit length field
+        let oversized = |byte| {
+            let mut opt = OPT::new(1232);
+            opt.push_option(EdnsOption::new(65001, vec![byte; 70_000]));
+            RecordData::OPT(opt)
+        };
+
+        let key = RRsetKey::new(".".parse().unwrap(), RecordType::OPT, RecordClass::IN);
+        let mut rrset = RRset::new(key, TTL::from(0));
+        assert!(rrset.insert(RecordData::OPT(OPT::new(1232))));
+        assert!(rrset.insert(oversized(1)));
+        assert!(rrset.insert(oversized(2)));
+        assert!(!rrset.insert(oversized(1)));
+        assert_eq!(
+            rrset.rdatas(),
+            [oversized(1), oversized(2), RecordData::OPT(OPT::new(1232))]
+        );
+    }
+}
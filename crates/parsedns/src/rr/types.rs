@@ -1,9 +1,12 @@
-use nom::{number::complete::be_u16, IResult};
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::map, number::complete::be_u16, IResult};
 
 use crate::{
-    error::{ParserError, ParserErrorType},
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
     indexed_input::IByteInput,
-    traits::Parse,
+    traits::{Encode, Parse},
 };
 
 /// ```text
@@ -11,6 +14,9 @@ use crate::{
 /// ```
 ///
 /// [RFC1035 3.2.2: TYPE values](https://datatracker.ietf.org/doc/html/rfc1035#section-3.2.2)
+///
+/// IANA [link](https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RecordType {
     /// A host address [RFC1035 3.4.1: A RDATA Format](https://datatracker.ietf.org/doc/html/rfc1035#section-3.4.1)
     A,
@@ -78,6 +84,10 @@ pub enum RecordType {
     APL,
     DS,
     SSHFP,
+    /// IPsec keying material [RFC4025](https://datatracker.ietf.org/doc/html/rfc4025)
+    IPSECKEY,
+    /// Signature over an RRset [RFC4034 3: The RRSIG Resource Record](https://datatracker.ietf.org/doc/html/rfc4034#section-3)
+    RRSIG,
     NSEC,
     DNSKEY,
     DHCID,
@@ -95,6 +105,12 @@ pub enum RecordType {
     CSYNC,
     ZONEMD,
     SVCB,
+    /// HTTPS service binding [RFC9460](https://datatracker.ietf.org/doc/html/rfc9460)
+    HTTPS,
+    /// Transaction key [RFC2930](https://datatracker.ietf.org/doc/html/rfc2930)
+    TKEY,
+    /// Transaction signature [RFC8945](https://datatracker.ietf.org/doc/html/rfc8945)
+    TSIG,
     Unknown(u16),
 }
 
@@ -122,22 +138,407 @@ impl From<u16> for RecordType {
             19 => Self::X25,
             20 => Self::ISDN,
             21 => Self::RT,
+            22 => Self::NSAP,
+            23 => Self::NSAP_PTR,
+            24 => Self::SIG,
+            25 => Self::KEY,
+            26 => Self::PX,
+            27 => Self::GPOS,
+            28 => Self::AAAA,
+            29 => Self::LOC,
+            30 => Self::NXT,
+            31 => Self::EID,
+            32 => Self::NIMLOC,
+            33 => Self::SRV,
+            34 => Self::ATMA,
+            35 => Self::NAPTR,
+            36 => Self::KX,
+            37 => Self::CERT,
+            38 => Self::A6,
+            39 => Self::DNAME,
+            40 => Self::SINK,
+            41 => Self::OPT,
+            42 => Self::APL,
+            43 => Self::DS,
+            44 => Self::SSHFP,
+            45 => Self::IPSECKEY,
+            46 => Self::RRSIG,
+            47 => Self::NSEC,
+            48 => Self::DNSKEY,
+            49 => Self::DHCID,
+            50 => Self::NSEC3,
+            51 => Self::NSEC3PARAM,
+            52 => Self::TLSA,
+            53 => Self::SMIMEA,
+            55 => Self::HIP,
+            56 => Self::NINFO,
+            57 => Self::RKEY,
+            58 => Self::TALINK,
+            59 => Self::CDS,
+            60 => Self::CDNSKEY,
+            61 => Self::OPENPGPKEY,
+            62 => Self::CSYNC,
+            63 => Self::ZONEMD,
+            64 => Self::SVCB,
+            65 => Self::HTTPS,
+            249 => Self::TKEY,
+            250 => Self::TSIG,
             v => Self::Unknown(v),
         }
     }
 }
 
+impl From<RecordType> for u16 {
+    fn from(value: RecordType) -> Self {
+        match value {
+            RecordType::A => 1,
+            RecordType::NS => 2,
+            RecordType::MD => 3,
+            RecordType::MF => 4,
+            RecordType::CNAME => 5,
+            RecordType::SOA => 6,
+            RecordType::MB => 7,
+            RecordType::MG => 8,
+            RecordType::MR => 9,
+            RecordType::NULL => 10,
+            RecordType::WKS => 11,
+            RecordType::PTR => 12,
+            RecordType::HINFO => 13,
+            RecordType::MINFO => 14,
+            RecordType::MX => 15,
+            RecordType::TXT => 16,
+            RecordType::RP => 17,
+            RecordType::AFSDB => 18,
+            RecordType::X25 => 19,
+            RecordType::ISDN => 20,
+            RecordType::RT => 21,
+            RecordType::NSAP => 22,
+            RecordType::NSAP_PTR => 23,
+            RecordType::SIG => 24,
+            RecordType::KEY => 25,
+            RecordType::PX => 26,
+            RecordType::GPOS => 27,
+            RecordType::AAAA => 28,
+            RecordType::LOC => 29,
+            RecordType::NXT => 30,
+            RecordType::EID => 31,
+            RecordType::NIMLOC => 32,
+            RecordType::SRV => 33,
+            RecordType::ATMA => 34,
+            RecordType::NAPTR => 35,
+            RecordType::KX => 36,
+            RecordType::CERT => 37,
+            RecordType::A6 => 38,
+            RecordType::DNAME => 39,
+            RecordType::SINK => 40,
+            RecordType::OPT => 41,
+            RecordType::APL => 42,
+            RecordType::DS => 43,
+            RecordType::SSHFP => 44,
+            RecordType::IPSECKEY => 45,
+            RecordType::RRSIG => 46,
+            RecordType::NSEC => 47,
+            RecordType::DNSKEY => 48,
+            RecordType::DHCID => 49,
+            RecordType::NSEC3 => 50,
+            RecordType::NSEC3PARAM => 51,
+            RecordType::TLSA => 52,
+            RecordType::SMIMEA => 53,
+            RecordType::HIP => 55,
+            RecordType::NINFO => 56,
+            RecordType::RKEY => 57,
+            RecordType::TALINK => 58,
+            RecordType::CDS => 59,
+            RecordType::CDNSKEY => 60,
+            RecordType::OPENPGPKEY => 61,
+            RecordType::CSYNC => 62,
+            RecordType::ZONEMD => 63,
+            RecordType::SVCB => 64,
+            RecordType::HTTPS => 65,
+            RecordType::TKEY => 249,
+            RecordType::TSIG => 250,
+            RecordType::Unknown(v) => v,
+        }
+    }
+}
+
+impl RecordType {
+    /// The registered mnemonic, `None` for types without one
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        let mnemonic = match self {
+            Self::A => "A",
+            Self::NS => "NS",
+            Self::MD => "MD",
+            Self::MF => "MF",
+            Self::CNAME => "CNAME",
+            Self::SOA => "SOA",
+            Self::MB => "MB",
+            Self::MG => "MG",
+            Self::MR => "MR",
+            Self::NULL => "NULL",
+            Self::WKS => "WKS",
+            Self::PTR => "PTR",
+            Self::HINFO => "HINFO",
+            Self::MINFO => "MINFO",
+            Self::MX => "MX",
+            Self::TXT => "TXT",
+            Self::RP => "RP",
+            Self::AFSDB => "AFSDB",
+            Self::X25 => "X25",
+            Self::ISDN => "ISDN",
+            Self::RT => "RT",
+            Self::NSAP => "NSAP",
+            Self::NSAP_PTR => "NSAP-PTR",
+            Self::SIG => "SIG",
+            Self::KEY => "KEY",
+            Self::PX => "PX",
+            Self::GPOS => "GPOS",
+            Self::AAAA => "AAAA",
+            Self::LOC => "LOC",
+            Self::NXT => "NXT",
+            Self::EID => "EID",
+            Self::NIMLOC => "NIMLOC",
+            Self::SRV => "SRV",
+            Self::ATMA => "ATMA",
+            Self::NAPTR => "NAPTR",
+            Self::KX => "KX",
+            Self::CERT => "CERT",
+            Self::A6 => "A6",
+            Self::DNAME => "DNAME",
+            Self::SINK => "SINK",
+            Self::OPT => "OPT",
+            Self::APL => "APL",
+            Self::DS => "DS",
+            Self::SSHFP => "SSHFP",
+            Self::IPSECKEY => "IPSECKEY",
+            Self::RRSIG => "RRSIG",
+            Self::NSEC => "NSEC",
+            Self::DNSKEY => "DNSKEY",
+            Self::DHCID => "DHCID",
+            Self::NSEC3 => "NSEC3",
+            Self::NSEC3PARAM => "NSEC3PARAM",
+            Self::TLSA => "TLSA",
+            Self::SMIMEA => "SMIMEA",
+            Self::HIP => "HIP",
+            Self::NINFO => "NINFO",
+            Self::RKEY => "RKEY",
+            Self::TALINK => "TALINK",
+            Self::CDS => "CDS",
+            Self::CDNSKEY => "CDNSKEY",
+            Self::OPENPGPKEY => "OPENPGPKEY",
+            Self::CSYNC => "CSYNC",
+            Self::ZONEMD => "ZONEMD",
+            Self::SVCB => "SVCB",
+            Self::HTTPS => "HTTPS",
+            Self::TKEY => "TKEY",
+            Self::TSIG => "TSIG",
+            Self::Unknown(_) => return None,
+        };
+        Some(mnemonic)
+    }
+}
+
+/// Unknown types are written as `TYPE` followed by the decimal type number
+///
+/// [RFC3597 5: Text representation](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(mnemonic) => write!(f, "{}", mnemonic),
+            None => write!(f, "TYPE{}", u16::from(*self)),
+        }
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_ascii_uppercase();
+        if let Some(number) = upper.strip_prefix("TYPE") {
+            if let Ok(v) = number.parse::<u16>() {
+                return Ok(Self::from(v));
+            }
+        }
+
+        let t = match upper.as_str() {
+            "A" => Self::A,
+            "NS" => Self::NS,
+            "MD" => Self::MD,
+            "MF" => Self::MF,
+            "CNAME" => Self::CNAME,
+            "SOA" => Self::SOA,
+            "MB" => Self::MB,
+            "MG" => Self::MG,
+            "MR" => Self::MR,
+            "NULL" => Self::NULL,
+            "WKS" => Self::WKS,
+            "PTR" => Self::PTR,
+            "HINFO" => Self::HINFO,
+            "MINFO" => Self::MINFO,
+            "MX" => Self::MX,
+            "TXT" => Self::TXT,
+            "RP" => Self::RP,
+            "AFSDB" => Self::AFSDB,
+            "X25" => Self::X25,
+            "ISDN" => Self::ISDN,
+            "RT" => Self::RT,
+            "NSAP" => Self::NSAP,
+            "NSAP-PTR" => Self::NSAP_PTR,
+            "SIG" => Self::SIG,
+            "KEY" => Self::KEY,
+            "PX" => Self::PX,
+            "GPOS" => Self::GPOS,
+            "AAAA" => Self::AAAA,
+            "LOC" => Self::LOC,
+            "NXT" => Self::NXT,
+            "EID" => Self::EID,
+            "NIMLOC" => Self::NIMLOC,
+            "SRV" => Self::SRV,
+            "ATMA" => Self::ATMA,
+            "NAPTR" => Self::NAPTR,
+            "KX" => Self::KX,
+            "CERT" => Self::CERT,
+            "A6" => Self::A6,
+            "DNAME" => Self::DNAME,
+            "SINK" => Self::SINK,
+            "OPT" => Self::OPT,
+            "APL" => Self::APL,
+            "DS" => Self::DS,
+            "SSHFP" => Self::SSHFP,
+            "IPSECKEY" => Self::IPSECKEY,
+            "RRSIG" => Self::RRSIG,
+            "NSEC" => Self::NSEC,
+            "DNSKEY" => Self::DNSKEY,
+            "DHCID" => Self::DHCID,
+            "NSEC3" => Self::NSEC3,
+            "NSEC3PARAM" => Self::NSEC3PARAM,
+            "TLSA" => Self::TLSA,
+            "SMIMEA" => Self::SMIMEA,
+            "HIP" => Self::HIP,
+            "NINFO" => Self::NINFO,
+            "RKEY" => Self::RKEY,
+            "TALINK" => Self::TALINK,
+            "CDS" => Self::CDS,
+            "CDNSKEY" => Self::CDNSKEY,
+            "OPENPGPKEY" => Self::OPENPGPKEY,
+            "CSYNC" => Self::CSYNC,
+            "ZONEMD" => Self::ZONEMD,
+            "SVCB" => Self::SVCB,
+            "HTTPS" => Self::HTTPS,
+            "TKEY" => Self::TKEY,
+            "TSIG" => Self::TSIG,
+            _ => return Err(PresentationError::UnknownMnemonic),
+        };
+        Ok(t)
+    }
+}
+
+/// Types without a known RDATA format are still parsed, their RDATA is kept opaque
+///
+/// [RFC3597 2: Definition](https://datatracker.ietf.org/doc/html/rfc3597#section-2)
 impl Parse for RecordType {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
-        let (ir, v) = be_u16(i)?;
-
-        match v.into() {
-            Self::Unknown(v) => Err(nom::Err::Failure(ParserError {
-                position: i.idx(),
-                nom_kind: None,
-                err_type: Some(ParserErrorType::UnrecognizedRecordType(v)),
-            })),
-            c => Ok((ir, c)),
+        map(be_u16, Self::from)(i)
+    }
+}
+
+impl Encode for RecordType {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u16((*self).into());
+        Ok(())
+    }
+}
+
+/// QTYPE fields appear in the question part of a query. QTYPES are a superset of TYPEs, hence
+/// all TYPEs are valid QTYPEs.
+///
+/// [RFC1035 3.2.3: QTYPE values](https://datatracker.ietf.org/doc/html/rfc1035#section-3.2.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordQType {
+    /// A general type
+    RecordType(RecordType),
+    /// Incremental transfer [RFC1995](https://datatracker.ietf.org/doc/html/rfc1995)
+    IXFR,
+    /// A request for a transfer of an entire zone
+    AXFR,
+    /// A request for mailbox-related records (MB, MG or MR)
+    MAILB,
+    /// A request for mail agent RRs (Obsolete - see MX)
+    MAILA,
+    /// "*" A request for all records
+    Any,
+}
+
+impl From<u16> for RecordQType {
+    fn from(value: u16) -> Self {
+        match value {
+            251 => Self::IXFR,
+            252 => Self::AXFR,
+            253 => Self::MAILB,
+            254 => Self::MAILA,
+            255 => Self::Any,
+            v => Self::RecordType(RecordType::from(v)),
+        }
+    }
+}
+
+impl From<RecordQType> for u16 {
+    fn from(value: RecordQType) -> Self {
+        match value {
+            RecordQType::RecordType(t) => t.into(),
+            RecordQType::IXFR => 251,
+            RecordQType::AXFR => 252,
+            RecordQType::MAILB => 253,
+            RecordQType::MAILA => 254,
+            RecordQType::Any => 255,
+        }
+    }
+}
+
+impl From<RecordType> for RecordQType {
+    fn from(value: RecordType) -> Self {
+        Self::RecordType(value)
+    }
+}
+
+impl fmt::Display for RecordQType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RecordType(t) => write!(f, "{}", t),
+            Self::IXFR => write!(f, "IXFR"),
+            Self::AXFR => write!(f, "AXFR"),
+            Self::MAILB => write!(f, "MAILB"),
+            Self::MAILA => write!(f, "MAILA"),
+            Self::Any => write!(f, "ANY"),
+        }
+    }
+}
+
+impl FromStr for RecordQType {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "IXFR" => Ok(Self::IXFR),
+            "AXFR" => Ok(Self::AXFR),
+            "MAILB" => Ok(Self::MAILB),
+            "MAILA" => Ok(Self::MAILA),
+            "ANY" | "*" => Ok(Self::Any),
+            _ => s.parse::<RecordType>().map(|t| Self::from(u16::from(t))),
         }
     }
 }
+
+impl Parse for RecordQType {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(be_u16, Self::from)(i)
+    }
+}
+
+impl Encode for RecordQType {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u16((*self).into());
+        Ok(())
+    }
+}
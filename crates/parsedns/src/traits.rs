@@ -1,9 +1,13 @@
 use nom::IResult;
 
-use crate::{error::ParserError, indexed_input::IByteInput};
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError},
+    indexed_input::IByteInput,
+};
 
 pub trait Encode {
-    fn encode(&self) -> Self;
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError>;
 }
 
 pub trait Parse: Sized {
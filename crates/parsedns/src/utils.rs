@@ -1,29 +1,219 @@
-use std::net::Ipv4Addr;
+use alloc::{string::String, vec::Vec};
+use core::{cmp::Ordering, fmt, str::FromStr};
 
-use nom::{combinator::map, number::complete::be_u32, IResult};
+use nom::{
+    combinator::map,
+    number::complete::{be_u128, be_u32},
+    IResult,
+};
 
-use crate::{error::ParserError, indexed_input::IByteInput, traits::Parse};
+use crate::{
+    encoder::Encoder,
+    error::{EncodeError, ParserError, PresentationError},
+    indexed_input::IByteInput,
+    net::{Ipv4Addr, Ipv6Addr},
+    traits::{Encode, Parse},
+};
+
+/// ```text
+/// Serial numbers are formed from non-negative integers from a finite
+/// subset of the range of all integer values.  The lowest integer in
+/// every subset used for this purpose is zero, the maximum is always one
+/// less than a power of two.
+/// ```
+/// [RFC1982 2: Serial Number Arithmetic](https://datatracker.ietf.org/doc/html/rfc1982#section-2)
+///
+/// The 32 bit serial numbers of SOA records. Serials are only partially ordered: two serials
+/// exactly 2^31 apart compare as neither greater nor less than each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Serial(u32);
+
+const SERIAL_HALF: u32 = 1 << 31;
+
+impl Serial {
+    /// ```text
+    /// Serial numbers may be incremented by the addition of a positive
+    /// integer n, where n is taken from the range of integers
+    /// [0 .. (2^(SERIAL_BITS - 1) - 1)].
+    /// ```
+    /// [RFC1982 3.1: Addition](https://datatracker.ietf.org/doc/html/rfc1982#section-3.1)
+    ///
+    /// None when `n` is outside that range.
+    pub fn checked_add(self, n: u32) -> Option<Self> {
+        if n < SERIAL_HALF {
+            Some(Self(self.0.wrapping_add(n)))
+        } else {
+            None
+        }
+    }
+
+    /// The next serial of a zone that counts its versions
+    pub fn next(self) -> Self {
+        Self(self.0.wrapping_add(1))
+    }
+
+    /// The next serial of a zone that numbers its versions `YYYYMMDDnn`, a date followed by a two
+    /// digit counter of the changes on that day. `now` is in seconds since the Unix epoch.
+    ///
+    /// Once the counter of a day is exhausted, or the current serial is ahead of the date, the
+    /// serial is incremented by one.
+    pub fn next_date(self, now: u64) -> Self {
+        let (year, month, day) = civil_from_days((now / 86400) as i64);
+        let date = (year * 10000 + month * 100 + day) * 100;
+        match u32::try_from(date).map(Self) {
+            Ok(candidate) if candidate > self => candidate,
+            _ => self.next(),
+        }
+    }
+
+    /// The next serial of a zone that numbers its versions with the Unix time at which they were
+    /// made, falling back to an increment when the clock is behind the serial
+    pub fn next_unixtime(self, now: u64) -> Self {
+        let candidate = Self(now as u32);
+        if candidate > self {
+            candidate
+        } else {
+            self.next()
+        }
+    }
+}
+
+/// ```text
+/// s1 is said to be less than s2 if, and only if, s1 is not equal to s2,
+/// and
+///
+///      (i1 < i2 and i2 - i1 < 2^(SERIAL_BITS - 1)) or
+///      (i1 > i2 and i1 - i2 > 2^(SERIAL_BITS - 1))
+/// ```
+/// [RFC1982 3.2: Comparison](https://datatracker.ietf.org/doc/html/rfc1982#section-3.2)
+impl PartialOrd for Serial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match other.0.wrapping_sub(self.0) {
+            0 => Some(Ordering::Equal),
+            SERIAL_HALF => None,
+            distance if distance < SERIAL_HALF => Some(Ordering::Less),
+            _ => Some(Ordering::Greater),
+        }
+    }
+}
+
+impl From<u32> for Serial {
+    fn from(v: u32) -> Self {
+        Self(v)
+    }
+}
+
+impl From<Serial> for u32 {
+    fn from(serial: Serial) -> Self {
+        serial.0
+    }
+}
+
+impl Parse for Serial {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(be_u32, Serial)(i)
+    }
+}
+
+impl Encode for Serial {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u32(self.0);
+        Ok(())
+    }
+}
+
+impl fmt::Display for Serial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Serial {
+    type Err = PresentationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(Self)
+            .map_err(|_| PresentationError::InvalidNumber)
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date of a count of days since 1970-01-01
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
 
 impl Parse for Ipv4Addr {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
-        map(be_u32, |addr| Ipv4Addr::from(addr))(i)
+        map(be_u32, Ipv4Addr::from)(i)
     }
 }
 
-#[derive(Clone, Copy)]
+impl Encode for Ipv4Addr {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_bytes(&self.octets());
+        Ok(())
+    }
+}
+
+impl Parse for Ipv6Addr {
+    fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
+        map(be_u128, Ipv6Addr::from)(i)
+    }
+}
+
+impl Encode for Ipv6Addr {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_bytes(&self.octets());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TTL(u32);
 
+const SIGN_MASK: u32 = 0x1 << 31;
+
 impl Parse for TTL {
     fn parse(i: IByteInput) -> IResult<IByteInput, Self, ParserError> {
-        const SIGN_MASK: u32 = 0x1 << 31;
+        map(be_u32, TTL::from)(i)
+    }
+}
 
-        map(be_u32, |v| {
-            if v & SIGN_MASK == SIGN_MASK {
-                TTL(0)
-            } else {
-                TTL(v)
-            }
-        })(i)
+impl Encode for TTL {
+    fn encode(&self, e: &mut Encoder) -> Result<(), EncodeError> {
+        e.write_u32(self.0);
+        Ok(())
+    }
+}
+
+impl From<u32> for TTL {
+    fn from(v: u32) -> Self {
+        if v & SIGN_MASK == SIGN_MASK {
+            TTL(0)
+        } else {
+            TTL(v)
+        }
     }
 }
 
@@ -32,3 +222,135 @@ impl From<TTL> for u32 {
         ttl.0
     }
 }
+
+/// Uppercase base16 as used by the generic RDATA format and RFC8427
+pub fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        s.push(DIGITS[(b >> 4) as usize] as char);
+        s.push(DIGITS[(b & 0xF) as usize] as char);
+    }
+    s
+}
+
+/// Parse base16 in either case, ignoring whitespace between digit pairs
+pub fn from_hex(s: &str) -> Result<Vec<u8>, PresentationError> {
+    let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(PresentationError::InvalidHex);
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16);
+            let lo = (pair[1] as char).to_digit(16);
+            match (hi, lo) {
+                (Some(hi), Some(lo)) => Ok((hi << 4 | lo) as u8),
+                _ => Err(PresentationError::InvalidHex),
+            }
+        })
+        .collect()
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Base64 with padding as used by the presentation format of DNSSEC records
+///
+/// [RFC4648 4: Base 64 Encoding](https://datatracker.ietf.org/doc/html/rfc4648#section-4)
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (idx, &b)| {
+            group | (b as u32) << (16 - 8 * idx)
+        });
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                s.push(BASE64_ALPHABET[(group >> (18 - 6 * idx) & 0x3F) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// Parse padded base64, ignoring whitespace
+pub fn from_base64(s: &str) -> Result<Vec<u8>, PresentationError> {
+    let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(4) {
+        return Err(PresentationError::InvalidBase64);
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3);
+    for (idx, quad) in digits.chunks(4).enumerate() {
+        let last = idx == digits.len() / 4 - 1;
+        let padding = quad.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return Err(PresentationError::InvalidBase64);
+        }
+
+        let mut group = 0u32;
+        for &b in &quad[..4 - padding] {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|&digit| digit == b)
+                .ok_or(PresentationError::InvalidBase64)?;
+            group = group << 6 | value as u32;
+        }
+        group <<= 6 * padding;
+        bytes.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Serial;
+
+    #[test]
+    fn test_serial_comparison() {
+        let serial = Serial::from(u32::MAX);
+        assert!(serial < Serial::from(0));
+        assert!(serial.next() == Serial::from(0));
+        assert!(Serial::from(5) > Serial::from(1));
+        assert!(Serial::from(1) > Serial::from(1 << 31 | 5));
+
+        // 2^31 apart the comparison is undefined
+        let (a, b) = (Serial::from(0), Serial::from(1 << 31));
+        assert_eq!(a.partial_cmp(&b), None);
+        assert_eq!(b.partial_cmp(&a), None);
+
+        assert_eq!(serial.checked_add(1 << 31), None);
+        assert_eq!(
+            serial.checked_add((1 << 31) - 1),
+            Some(Serial::from((1 << 31) - 2))
+        );
+    }
+
+    #[test]
+    fn test_serial_policies() {
+        // 2024-03-01 12:00:00 UTC
+        let now = 1_709_294_400;
+        assert_eq!(Serial::from(1).next_date(now), Serial::from(2024030100));
+        assert_eq!(
+            Serial::from(2024030100).next_date(now),
+            Serial::from(2024030101)
+        );
+        assert_eq!(
+            Serial::from(2024030199).next_date(now),
+            Serial::from(2024030200)
+        );
+
+        assert_eq!(
+            Serial::from(1).next_unixtime(now),
+            Serial::from(1_709_294_400)
+        );
+        assert_eq!(
+            Serial::from(1_709_294_400).next_unixtime(now),
+            Serial::from(1_709_294_401)
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! Protocol level checks of a message that parsed without wire errors.
+//!
+//! Every violation reported here makes the message malformed, a server answers it with
+//! `FORMERR`.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    header::OpCode,
+    message::{Message, Section},
+    rr::{class::RecordClass, data::RecordData, name::Name, record::Record, types::RecordType},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// More than one OPT record is present
+    /// [RFC6891 6.1.1](https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.1)
+    MultipleOpt,
+    /// An OPT record appears in the answer or authority section
+    /// [RFC6891 6.1.1](https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.1)
+    OptOutsideAdditional(Section),
+    /// A TSIG record is not the last record of the additional section
+    /// [RFC8945 5.1](https://datatracker.ietf.org/doc/html/rfc8945#section-5.1)
+    TsigNotLast,
+    /// A standard query does not carry exactly one question
+    /// [RFC9619: In the DNS, QDCOUNT is (usually) one](https://datatracker.ietf.org/doc/html/rfc9619)
+    QuestionCount(usize),
+    /// A record has class NONE or ANY outside of an UPDATE message
+    /// [RFC2136 2.4](https://datatracker.ietf.org/doc/html/rfc2136#section-2.4)
+    MetaClassOutsideUpdate { section: Section, index: usize },
+    /// A CNAME shares its owner name with other data
+    /// [RFC2181 10.1](https://datatracker.ietf.org/doc/html/rfc2181#section-10.1)
+    CnameAndOtherData { section: Section, owner: Name },
+    /// A record repeats an earlier record of its RRset
+    /// [RFC2181 5](https://datatracker.ietf.org/doc/html/rfc2181#section-5)
+    DuplicateRecord { section: Section, index: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MultipleOpt => write!(f, "more than one OPT record"),
+            Self::OptOutsideAdditional(section) => {
+                write!(f, "OPT record in the {} section", section)
+            }
+            Self::TsigNotLast => write!(f, "TSIG record is not the last record"),
+            Self::QuestionCount(count) => write!(f, "query has {} questions", count),
+            Self::MetaClassOutsideUpdate { section, index } => write!(
+                f,
+                "record {} of the {} section has class NONE or ANY",
+                index, section
+            ),
+            Self::CnameAndOtherData { section, owner } => {
+                write!(
+                    f,
+                    "CNAME at {} has other data in the {} section",
+                    owner, section
+                )
+            }
+            Self::DuplicateRecord { section, index } => {
+                write!(
+                    f,
+                    "record {} of the {} section is a duplicate",
+                    index, section
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Violation {}
+
+/// Meta-RRs that carry class ANY by definition
+fn is_meta(record: &Record) -> bool {
+    matches!(record.rdata(), RecordData::OPT(_))
+        || matches!(record.rtype(), RecordType::TSIG | RecordType::TKEY)
+}
+
+/// Types that may share an owner with a CNAME to secure it
+/// [RFC4035 2.5](https://datatracker.ietf.org/doc/html/rfc4035#section-2.5)
+fn may_accompany_cname(rtype: RecordType) -> bool {
+    matches!(
+        rtype,
+        RecordType::CNAME | RecordType::RRSIG | RecordType::NSEC
+    )
+}
+
+fn check_opt(message: &Message, violations: &mut Vec<Violation>) {
+    let mut opts = 0;
+    for section in Section::ALL {
+        for record in message.records(section) {
+            if let RecordData::OPT(_) = record.rdata() {
+                opts += 1;
+                if section != Section::Additional {
+                    violations.push(Violation::OptOutsideAdditional(section));
+                }
+            }
+        }
+    }
+    if opts > 1 {
+        violations.push(Violation::MultipleOpt);
+    }
+}
+
+fn check_tsig(message: &Message, violations: &mut Vec<Violation>) {
+    let last = message.additionals.len().checked_sub(1);
+    let misplaced = Section::ALL.iter().any(|&section| {
+        message
+            .records(section)
+            .iter()
+            .enumerate()
+            .any(|(index, record)| {
+                record.rtype() == RecordType::TSIG
+                    && (section != Section::Additional || Some(index) != last)
+            })
+    });
+    if misplaced {
+        violations.push(Violation::TsigNotLast);
+    }
+}
+
+fn check_section(
+    section: Section,
+    records: &[Record],
+    update: bool,
+    violations: &mut Vec<Violation>,
+) {
+    let mut cname_owners: Vec<&Name> = Vec::new();
+
+    for (index, record) in records.iter().enumerate() {
+        if !update
+            && !is_meta(record)
+            && matches!(record.class(), RecordClass::NONE | RecordClass::ANY)
+        {
+            violations.push(Violation::MetaClassOutsideUpdate { section, index });
+        }
+
+        let earlier = &records[..index];
+        if !is_meta(record)
+            && earlier.iter().any(|other| {
+                other.name() == record.name()
+                    && other.rtype() == record.rtype()
+                    && other.class() == record.class()
+                    && other.rdata() == record.rdata()
+            })
+        {
+            violations.push(Violation::DuplicateRecord { section, index });
+        }
+
+        if record.rtype() == RecordType::CNAME && !cname_owners.contains(&record.name()) {
+            let other_data = records.iter().any(|other| {
+                other.name() == record.name()
+                    && other.class() == record.class()
+                    && !may_accompany_cname(other.rtype())
+            });
+            if other_data {
+                violations.push(Violation::CnameAndOtherData {
+                    section,
+                    owner: record.name().clone(),
+                });
+            }
+            cname_owners.push(record.name());
+        }
+    }
+}
+
+impl Message {
+    /// Check the message for protocol violations, reporting all of them
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+
+        check_opt(self, &mut violations);
+        check_tsig(self, &mut violations);
+
+        if self.header.opcode == OpCode::Query && self.questions.len() != 1 {
+            violations.push(Violation::QuestionCount(self.questions.len()));
+        }
+
+        let update = self.header.opcode == OpCode::Update;
+        for section in Section::ALL {
+            check_section(section, self.records(section), update, &mut violations);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        header::OpCode,
+        message::{Message, Section},
+        net::Ipv4Addr,
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            data::RecordData,
+            rdata::{Unknown, A, CNAME, OPT},
+            record::Record,
+            types::{RecordQType, RecordType},
+        },
+        utils::TTL,
+    };
+
+    use super::Violation;
+
+    fn a(name: &str, class: RecordClass, address: Ipv4Addr) -> Record {
+        Record::new(
+            name.parse().unwrap(),
+            class,
+            TTL::from(300),
+            RecordData::A(A::new(address)),
+        )
+    }
+
+    fn query() -> Message {
+        let mut message = Message::new();
+        message.questions.push(Question::new(
+            "example.com.".parse().unwrap(),
+            RecordQType::RecordType(RecordType::A),
+            RecordQClass::RecordClass(RecordClass::IN),
+        ));
+        message
+    }
+
+    #[test]
+    fn test_valid_response() {
+        let mut message = query();
+        message.header.qr = true;
+        message.answers.push(Record::new(
+            "www.example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(300),
+            RecordData::CNAME(CNAME::new("example.com.".parse().unwrap())),
+        ));
+        message.answers.push(a(
+            "example.com.",
+            RecordClass::IN,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ));
+        message.answers.push(a(
+            "example.com.",
+            RecordClass::IN,
+            Ipv4Addr::new(192, 0, 2, 2),
+        ));
+        message.additionals.push(Record::opt(OPT::new(1232)));
+        message.additionals.push(Record::new(
+            "key.".parse().unwrap(),
+            RecordClass::ANY,
+            TTL::from(0),
+            RecordData::Unknown(Unknown::new(RecordType::TSIG, vec![0; 8])),
+        ));
+
+        assert_eq!(message.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut message = query();
+        message.questions.push(message.questions[0].clone());
+        message.answers.push(Record::opt(OPT::new(512)));
+        message.answers.push(Record::new(
+            "key.".parse().unwrap(),
+            RecordClass::ANY,
+            TTL::from(0),
+            RecordData::Unknown(Unknown::new(RecordType::TSIG, vec![0; 8])),
+        ));
+        message.answers.push(Record::new(
+            "www.example.com.".parse().unwrap(),
+            RecordClass::IN,
+            TTL::from(300),
+            RecordData::CNAME(CNAME::new("example.com.".parse().unwrap())),
+        ));
+        message.answers.push(a(
+            "WWW.example.com.",
+            RecordClass::IN,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ));
+        message.authorities.push(a(
+            "example.com.",
+            RecordClass::NONE,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ));
+        message.additionals.push(a(
+            "example.com.",
+            RecordClass::IN,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ));
+        message.additionals.push(a(
+            "example.com.",
+            RecordClass::IN,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ));
+        message.additionals.push(Record::opt(OPT::new(1232)));
+
+        assert_eq!(
+            message.validate(),
+            Err(vec![
+                Violation::OptOutsideAdditional(Section::Answer),
+                Violation::MultipleOpt,
+                Violation::TsigNotLast,
+                Violation::QuestionCount(2),
+                Violation::CnameAndOtherData {
+                    section: Section::Answer,
+                    owner: "www.example.com.".parse().unwrap(),
+                },
+                Violation::MetaClassOutsideUpdate {
+                    section: Section::Authority,
+                    index: 0,
+                },
+                Violation::DuplicateRecord {
+                    section: Section::Additional,
+                    index: 1,
+                },
+            ])
+        );
+
+        // Deleting an RRset is expressed with class ANY in an UPDATE
+        let mut update = Message::new();
+        update.header.opcode = OpCode::Update;
+        update
+            .authorities
+            .push(a("example.com.", RecordClass::ANY, Ipv4Addr::UNSPECIFIED));
+        assert_eq!(update.validate(), Ok(()));
+    }
+}
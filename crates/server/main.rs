@@ -1,83 +1,189 @@
+#[path = "../dns/mod.rs"]
 pub mod dns;
+#[path = "../udp/mod.rs"]
 pub mod udp;
 
 use std::{
-    net::{Ipv4Addr, SocketAddr},
+    future::Future,
+    io,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
-use dns::packet::{new_dns_packet, Dns, RCode};
+use dns::{
+    context::{CacheConfig, ServerContext},
+    forward_table::ForwardTable,
+    forwarder::{Forwarder, RetryPolicy},
+    handler::RequestHandler,
+    middleware::{Acl, AclAction, Coalesce, Logging, Network, RateLimit},
+    resolver::{Resolver, ResolverConfig},
+    root_hints::RootHints,
+    upstream::{HealthPolicy, Strategy, Upstreams},
+};
+use udp::server::UdpServer;
+
+/// Environment variable naming the file the server keeps its cache in across restarts
+const CACHE_SNAPSHOT: &str = "JDNS_CACHE";
+/// How often the server logs how its cache is used
+const STATS_INTERVAL: Duration = Duration::from_secs(300);
+/// Queries per second and burst size allowed to each client
+const RATE_LIMIT: (u32, u32) = (100, 200);
 
-use bytes::Bytes;
-use dashmap::{mapref::entry::Entry, DashMap};
-use udp::{server::UdpServer, TxUdp};
+/// Loopback and private networks, everything else is refused so the server is not an open
+/// resolver
+fn acl_rules() -> Vec<(Network, AclAction)> {
+    [
+        "127.0.0.0/8",
+        "10.0.0.0/8",
+        "172.16.0.0/12",
+        "192.168.0.0/16",
+        "::1",
+        "fc00::/7",
+    ]
+    .iter()
+    .map(|network| (network.parse().unwrap(), AclAction::Allow))
+    .collect()
+}
 
-struct DnsState {
-    addr: SocketAddr,
-    tx: TxUdp,
+/// Forwards to public resolvers, with the answers cached by RRset in the context
+async fn forwarder(context: Arc<ServerContext>) -> Arc<Forwarder> {
+    let upstreams = Upstreams::new(
+        vec![
+            ((Ipv4Addr::new(8, 8, 8, 8), 53).into(), 1),
+            ((Ipv4Addr::new(8, 8, 4, 4), 53).into(), 1),
+        ],
+        Strategy::LowestLatency,
+        HealthPolicy::default(),
+    );
+    let mut table = ForwardTable::new(upstreams);
+    // Internal names are not for the public resolvers
+    table.nxdomain("corp.".parse().unwrap());
+    Forwarder::new(table, RetryPolicy::default(), context)
+        .await
+        .unwrap()
 }
 
-fn lookup(packet: Dns, state: DnsState) {
-    if !packet.flags.qr {
-        println!("a query when supposed to be response");
-        return;
+/// Resolves from the root servers, with the root hints read from `hints` if given. Answers are
+/// cached by RRset in the context.
+async fn resolver(hints: Option<String>, context: Arc<ServerContext>) -> Arc<Resolver> {
+    let hints = match hints {
+        Some(path) => RootHints::from_file(path).unwrap(),
+        None => RootHints::iana(),
+    };
+    let resolver = Resolver::new(hints, ResolverConfig::default(), context)
+        .await
+        .unwrap();
+    if let Err(e) = resolver.prime().await {
+        println!("Priming failed, starting from the root hints: {}", e);
     }
+    resolver
+}
+
+/// The middleware every query passes through before reaching `handler`
+fn stack<H: RequestHandler>(handler: H) -> impl RequestHandler {
+    let (rate, burst) = RATE_LIMIT;
+    Logging::new(Acl::new(
+        RateLimit::new(Coalesce::new(handler), rate, burst),
+        acl_rules(),
+        AclAction::Refuse,
+    ))
+}
 
-    println!("Response: {:?}", packet.encode().unwrap());
+async fn serve<H: RequestHandler>(handler: H) {
+    let server = UdpServer::new("0:2053", stack(handler)).unwrap();
+    server.start().await.unwrap();
+}
 
-    state
-        .tx
-        .send((packet.encode().unwrap().freeze(), state.addr))
-        .unwrap();
+fn log_stats(context: &ServerContext) {
+    let cache = &context.cache;
+    println!(
+        "Cache: {} entries, {} bytes, {}",
+        cache.entry_count(),
+        cache.weighted_size(),
+        cache.stats()
+    );
 }
 
-async fn handle(
-    addr: SocketAddr,
-    data: Bytes,
-    state: Arc<DashMap<u16, DnsState>>,
-    tx: TxUdp,
-) -> () {
-    let packet = Dns::decode(data).unwrap();
-    let server: SocketAddr = (Ipv4Addr::new(8, 8, 8, 8), 53).into();
-    let entry = state.entry(packet.id);
-
-    match entry {
-        Entry::Occupied(entry) => {
-            if addr != server {
-                return;
-            }
-            let (_, dns_state) = entry.remove_entry();
-        }
-        Entry::Vacant(entry) => {
-            let mut lookup = new_dns_packet();
-            lookup.flags.ra = true;
-
-            if packet.questions.len() == 1 {
-                entry.insert(DnsState {
-                    addr,
-                    tx: tx.clone(),
-                });
-            } else {
-                lookup.flags.rcode = RCode::FormErr;
-                tx.send((lookup.encode().unwrap().freeze(), addr)).unwrap();
-                return;
-            }
+/// Log the cache statistics every `STATS_INTERVAL`
+async fn report_stats(context: Arc<ServerContext>) {
+    let mut interval = tokio::time::interval(STATS_INTERVAL);
+    // The first tick is immediate, with nothing to report yet
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        log_stats(&context);
+    }
+}
 
-            lookup.id = packet.id;
-            lookup.questions = packet.questions;
-            lookup.flags.rd = packet.flags.rd;
+async fn dump(context: &ServerContext, path: &Path) {
+    match context.cache.dump(path).await {
+        Ok(entries) => println!("Saved {} cache entries to {}", entries, path.display()),
+        Err(e) => println!("Failed to save the cache to {}: {}", path.display(), e),
+    }
+}
 
-            println!("Lookup {:?}", lookup);
+/// Serve with the cache loaded from the snapshot at `path`, saving it there on SIGUSR1 and on
+/// shutdown by SIGINT or SIGTERM. SIGUSR1 also logs the cache statistics. The handler is built
+/// once the signals are set up, as that can take a while.
+async fn serve_with_snapshot<H: RequestHandler>(
+    handler: impl Future<Output = H>,
+    context: Arc<ServerContext>,
+    path: PathBuf,
+) {
+    match context.cache.load(&path).await {
+        Ok(entries) => println!("Loaded {} cache entries from {}", entries, path.display()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => println!("Failed to load the cache from {}: {}", path.display(), e),
+    }
+
+    #[cfg(unix)]
+    let shutdown = {
+        use tokio::signal::unix::{signal, SignalKind};
 
-            tx.send((lookup.encode().unwrap().freeze(), server))
-                .unwrap();
+        let mut on_demand = signal(SignalKind::user_defined1()).unwrap();
+        let mut interrupt = signal(SignalKind::interrupt()).unwrap();
+        let mut terminate = signal(SignalKind::terminate()).unwrap();
+        let (context, path) = (context.clone(), path.clone());
+        tokio::spawn(async move {
+            while on_demand.recv().await.is_some() {
+                dump(&context, &path).await;
+                log_stats(&context);
+            }
+        });
+        async move {
+            tokio::select! {
+                _ = interrupt.recv() => {}
+                _ = terminate.recv() => {}
+            }
         }
     };
+    #[cfg(not(unix))]
+    let shutdown = tokio::signal::ctrl_c();
+
+    tokio::select! {
+        _ = async { serve(handler.await).await } => {}
+        _ = shutdown => dump(&context, &path).await,
+    }
 }
 
+/// `jdns` forwards queries, `jdns resolve [named.root]` resolves them itself. Either keeps its
+/// cache across restarts in the file named by `JDNS_CACHE` if set.
 #[tokio::main]
 async fn main() {
-    let init_state = Arc::new(DashMap::new());
-    let server = UdpServer::new("0:2053", handle, init_state).unwrap();
-    server.start().await.unwrap();
+    let context = Arc::new(ServerContext::new(CacheConfig::default()));
+    tokio::spawn(report_stats(context.clone()));
+    let snapshot = std::env::var_os(CACHE_SNAPSHOT).map(PathBuf::from);
+
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), snapshot) {
+        (Some("resolve"), Some(path)) => {
+            let resolver = resolver(args.next(), context.clone());
+            serve_with_snapshot(resolver, context, path).await
+        }
+        (Some("resolve"), None) => serve(resolver(args.next(), context).await).await,
+        (_, Some(path)) => serve_with_snapshot(forwarder(context.clone()), context, path).await,
+        (_, None) => serve(forwarder(context).await).await,
+    }
 }
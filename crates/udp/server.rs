@@ -1,25 +1,25 @@
 use std::{
     error::Error,
-    future::Future,
     io,
-    marker::PhantomData,
     net::{SocketAddr, ToSocketAddrs},
     sync::Arc,
 };
 
 use super::send::{SendPool, TxUdp};
+use crate::dns::handler::{
+    encode_response, Request, RequestHandler, Transport, DEFAULT_UDP_PAYLOAD_SIZE,
+};
 use bytes::Bytes;
 use futures::future::join_all;
+use parsedns::{header::RCode, message::Message};
 use socket2::{Domain, Socket, Type};
 use tokio::net::UdpSocket;
 
 pub const BUFF_MAX_SIZE: usize = 4096;
 
-pub struct UdpServer<H, R, S> {
+pub struct UdpServer<H> {
     threads: Vec<UdpThread>,
-    handle: Arc<H>,
-    state: S,
-    handle_return: PhantomData<R>,
+    handler: Arc<H>,
 }
 
 pub struct UdpThread {
@@ -33,29 +33,64 @@ fn one_addr<T: ToSocketAddrs>(tsa: T) -> io::Result<std::net::SocketAddr> {
     let mut addrs = tsa.to_socket_addrs()?;
     let addr = match addrs.next() {
         Some(addr) => addr,
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "no socket addresses could be resolved",
-            ))
-        }
+        None => return Err(io::Error::other("no socket addresses could be resolved")),
     };
     if addrs.next().is_none() {
         Ok(addr)
     } else {
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "more than one address resolved",
-        ))
+        Err(io::Error::other("more than one address resolved"))
+    }
+}
+
+/// An empty FORMERR response to a query that failed to parse, echoing its header and question
+/// when those can be read
+fn format_error(data: &[u8], addr: SocketAddr) -> Option<Message> {
+    let (header, question) = Message::header_and_question(data)?;
+    if header.qr {
+        return None;
     }
+    let mut message = Message::new();
+    message.header = header;
+    message.questions.extend(question);
+    Some(Request::new(message, addr, Transport::Udp).response(RCode::FormErr))
 }
 
-impl<H, R, S> UdpServer<H, R, S>
-where
-    H: Fn(SocketAddr, Bytes, S, TxUdp) -> R + Send + Sync + 'static,
-    R: Future<Output = ()> + Send,
-    S: Send + Sync + Clone + 'static,
-{
+/// Answer one datagram. Queries that are malformed get FORMERR, anything that isn't a query is
+/// dropped without a reply.
+///
+/// [RFC1035 4.1.1: Header section format](https://datatracker.ietf.org/doc/html/rfc1035#section-4.1.1)
+async fn respond<H: RequestHandler>(handler: &H, addr: SocketAddr, data: Bytes, tx: TxUdp) {
+    let request = match Message::from_bytes(&data) {
+        Ok(message) if message.header.qr => return,
+        Ok(message) => Request::new(message, addr, Transport::Udp),
+        Err(e) => {
+            println!("Invalid message from {}: {}", addr, e);
+            if let Some(bytes) = format_error(&data, addr)
+                .and_then(|response| encode_response(&response, DEFAULT_UDP_PAYLOAD_SIZE as usize))
+            {
+                let _ = tx.send((bytes.into(), addr));
+            }
+            return;
+        }
+    };
+
+    let max_size = request.max_response_size();
+    let response = match request.message.validate() {
+        Ok(()) => handler.handle(request).await,
+        Err(violations) => {
+            let violations: Vec<_> = violations.iter().map(ToString::to_string).collect();
+            println!("Malformed query from {}: {}", addr, violations.join(", "));
+            Some(request.response(RCode::FormErr))
+        }
+    };
+    if let Some(response) = response {
+        if let Some(bytes) = encode_response(&response, max_size) {
+            let _ = tx.send((bytes.into(), addr));
+        }
+    }
+}
+
+impl<H: RequestHandler> UdpServer<H> {
     /// Create a udp socket
     fn create_udp_socket<A: ToSocketAddrs>(addr: &A) -> Result<UdpSocket, Box<dyn Error>> {
         let sock = Socket::new(Domain::IPV4, Type::DGRAM, None)?;
@@ -70,7 +105,7 @@ where
 
         sock.set_nonblocking(true)?;
         let udp_sock = UdpSocket::from_std(sock.into())?;
-        Ok(udp_sock.into())
+        Ok(udp_sock)
     }
 
     /// Create a list of udp sockets
@@ -86,11 +121,7 @@ where
         Ok(socks)
     }
 
-    pub fn new<A: ToSocketAddrs>(
-        addr: A,
-        handle: H,
-        init_state: S,
-    ) -> Result<Self, Box<dyn Error>> {
+    pub fn new<A: ToSocketAddrs>(addr: A, handler: H) -> Result<Self, Box<dyn Error>> {
         let num_cpus = num_cpus::get();
         let sock_list = Self::create_udp_socket_list(&addr, num_cpus)?;
 
@@ -106,17 +137,14 @@ where
 
         Ok(Self {
             threads,
-            handle: Arc::new(handle),
-            state: init_state,
-            handle_return: PhantomData::default(),
+            handler: Arc::new(handler),
         })
     }
 
     pub async fn start(&self) -> Result<(), Box<dyn Error>> {
         let mut join_handles = Vec::with_capacity(self.threads.len());
         for udp_thread in &self.threads {
-            let state = self.state.clone();
-            let handle = self.handle.clone();
+            let handler = self.handler.clone();
 
             let move_sock = udp_thread.sock.clone();
             let tx = udp_thread.send.get_tx().clone();
@@ -127,12 +155,11 @@ where
                     match move_sock.recv_from(&mut buff).await {
                         Ok((size, addr)) => {
                             let tx = tx.clone();
-                            let state = state.clone();
-                            let handle = handle.clone();
+                            let handler = handler.clone();
+                            let data = Bytes::copy_from_slice(&buff[..size]);
 
                             tokio::spawn(async move {
-                                handle(addr, Bytes::copy_from_slice(&buff[..size]), state, tx)
-                                    .await;
+                                respond(handler.as_ref(), addr, data, tx).await;
                             });
                         }
                         Err(er) => {
@@ -154,3 +181,67 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use bytes::Bytes;
+    use parsedns::{
+        header::RCode,
+        message::Message,
+        question::Question,
+        rr::{
+            class::{RecordClass, RecordQClass},
+            types::{RecordQType, RecordType},
+        },
+    };
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::respond;
+    use crate::dns::handler::fixtures::Counting;
+
+    fn query(questions: usize) -> Message {
+        let mut message = Message::new();
+        message.header.id = 0x1234;
+        for _ in 0..questions {
+            message.questions.push(Question::new(
+                "example.com.".parse().unwrap(),
+                RecordQType::RecordType(RecordType::A),
+                RecordQClass::RecordClass(RecordClass::IN),
+            ));
+        }
+        message
+    }
+
+    /// The response sent for one datagram, if any
+    async fn exchange(data: Vec<u8>) -> Option<Message> {
+        let (tx, mut rx) = unbounded_channel();
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 5353));
+        respond(&Counting::default(), addr, Bytes::from(data), tx).await;
+        rx.try_recv()
+            .ok()
+            .map(|(bytes, _)| Message::from_bytes(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_format_error() {
+        let response = exchange(query(1).to_bytes().unwrap()).await.unwrap();
+        assert_eq!(response.header.rcode, RCode::NoError);
+
+        // Claims an answer record that isn't there
+        let mut bytes = query(1).to_bytes().unwrap();
+        bytes[7] = 1;
+        let response = exchange(bytes).await.unwrap();
+        assert_eq!(response.header.id, 0x1234);
+        assert_eq!(response.header.rcode, RCode::FormErr);
+        assert_eq!(response.questions, query(1).questions);
+
+        // Parses, but a query must have exactly one question
+        let response = exchange(query(2).to_bytes().unwrap()).await.unwrap();
+        assert_eq!(response.header.rcode, RCode::FormErr);
+
+        // Too short for a header
+        assert!(exchange(vec![0x12, 0x34, 0x01]).await.is_none());
+    }
+}